@@ -9,14 +9,17 @@
 import { Config } from '@/core/config/config.ts'
 import { isTruthy } from '@/lib/utils/compare.ts'
 import { LogLevel } from '@/relayer/logger.ts'
-import { Relayer } from '@/relayer/relayer.ts'
-import { showAction, showError, showInfo, showWarning } from '@/relayer/ui/show.ts'
+import { OutputFormat, Relayer } from '@/relayer/relayer.ts'
+import { InterfaceRelayer } from '@/relayer/ui/interface.ts'
+import { showAction, showInfo, showWarning } from '@/relayer/ui/show.ts'
 import { colors } from '@cliffy/ansi/colors'
 import { Command, EnumType } from '@cliffy/command'
 import { CompletionsCommand } from '@cliffy/command/completions'
 
 const logLevelType = new EnumType(['debug', 'info', 'warning', 'error', 'fatal'])
+const outputType = new EnumType(['text', 'json', 'yaml'])
 let timerId: string | undefined
+let commandName: string | undefined
 
 // Base command options
 const main = new Command()
@@ -30,6 +33,10 @@ const main = new Command()
   })
   .globalOption('-d, --debug', 'Enable debugging output.')
   .globalOption('-D, --verbose', 'Enable verbose output for the log level.')
+  .globalType('output', outputType)
+  .globalOption('-o, --output <format:output>', 'Output format for command results.', {
+    default: 'text',
+  })
   .globalOption(
     '-c --config <configFile:string>',
     'Path to a project config file.',
@@ -37,8 +44,13 @@ const main = new Command()
       default: Config.defaultConfigFilePath,
     },
   )
+  .globalOption(
+    '-e, --env <environment:string>',
+    'Environment overlay to use, e.g. ci loads .llemonstack/config.ci.json and .env.ci',
+  )
   .globalEnv('DEBUG=<boolean>', 'Enable debugging output.')
   .globalEnv('LOG_LEVEL=<log-level>', 'Set level of logs to output')
+  .globalEnv('LLEMONSTACK_ENV=<environment:string>', 'Environment overlay to use, same as --env.')
   .action(function (_options) {
     // Show help as the default action
     this.showHelp()
@@ -49,6 +61,7 @@ main
   .command('config')
   .description('Enable or disable services')
   .option('--all', 'Show all services in one list', { default: false })
+  .option('--dry-run', 'Show the changes without saving them', { default: false })
   .action(async (options) => {
     const config = await initConfig('config', options)
     const { configure } = await import('./scripts/configure.ts')
@@ -73,6 +86,12 @@ main
   .arguments('[service:string]')
   .option('-H, --hide', 'Hide credentials', { default: false })
   .option('--build', 'Rebuild the services during start', { default: false })
+  .option('--remap-ports', 'Use the next available port for host ports that are in use', {
+    default: false,
+  })
+  .option('--dry-run', 'Show what would be started without starting anything', {
+    default: false,
+  })
   .action(async (options, service?: string) => {
     const config = await initConfig('start', options)
     const { start } = await import('./scripts/start.ts')
@@ -81,6 +100,8 @@ main
       skipOutput: false,
       hideCredentials: options.hide,
       build: options.build,
+      remapPorts: options.remapPorts,
+      dryRun: options.dryRun,
     })
   })
 
@@ -89,11 +110,14 @@ main
   .command('stop')
   .description('Stop the LLemonStack services')
   .option('--all', 'Stop all services', { default: true })
+  .option('--dry-run', 'Show what would be stopped without stopping anything', {
+    default: false,
+  })
   .arguments('[service:string]')
   .action(async (options, service?: string) => {
     const config = await initConfig('stop', options)
     const { stop } = await import('./scripts/stop.ts')
-    await stop(config, { all: options.all, service })
+    await stop(config, { all: options.all, service, dryRun: options.dryRun })
   })
 
 // Restart the LLemonStack services
@@ -107,6 +131,72 @@ main
     await restart(config, { service, skipOutput: !!service })
   })
 
+// Show and follow logs for the LLemonStack services
+main
+  .command('logs')
+  .description('Show and follow logs for services')
+  .arguments('[...service:string]')
+  .option('--no-follow', 'Show existing logs and exit')
+  .option(
+    '--since <since:string>',
+    'Show logs since a timestamp (2025-01-02T13:23:37Z) or relative time (42m)',
+  )
+  .option('-n, --tail <lines:string>', 'Number of lines to show from the end of each log', {
+    default: '100',
+  })
+  .option('-g, --grep <pattern:string>', 'Only show lines matching the pattern')
+  .option('-t, --timestamps', 'Show timestamps', { default: false })
+  .option('--json', 'Output each log line as JSON, same as --output json', { default: false })
+  .example('Follow logs for all enabled services:', 'llmn logs')
+  .example('Show errors for n8n and postgres:', 'llmn logs n8n postgres --grep error')
+  .action(async (options, ...services: string[]) => {
+    const config = await initConfig('logs', {
+      ...options,
+      output: options.json ? 'json' : options.output,
+    })
+    const { logs } = await import('./scripts/logs.ts')
+    await logs(config, {
+      services,
+      follow: options.follow,
+      since: options.since,
+      tail: options.tail,
+      grep: options.grep,
+      timestamps: options.timestamps,
+    })
+  })
+
+// Run a command in a service container
+main
+  .command('exec')
+  .description('Run a command in a service container, opens a shell if no command is given')
+  .arguments('<service:string> [...command:string]')
+  .option('-u, --user <user:string>', 'User to run the command as')
+  .stopEarly()
+  .example('Open a shell in the n8n container:', 'llmn exec n8n')
+  .example('Run psql in the postgres container:', 'llmn exec postgres -- psql -U postgres')
+  .action(async function (options, service: string, ...command: string[]) {
+    const config = await initConfig('exec', options)
+    const { exec } = await import('./scripts/exec.ts')
+    const cmd = [...command, ...this.getLiteralArgs()]
+    await exec(config, service, {
+      cmd: cmd[0] === '--' ? cmd.slice(1) : cmd,
+      user: options.user,
+    })
+  })
+
+// Open a shell in a service container
+main
+  .command('shell')
+  .description('Open a shell in a service container')
+  .arguments('<service:string>')
+  .option('--root', 'Open the shell as the root user', { default: false })
+  .example('Open a root shell in the postgres container:', 'llmn shell postgres --root')
+  .action(async (options, service: string) => {
+    const config = await initConfig('shell', options)
+    const { shell } = await import('./scripts/exec.ts')
+    await shell(config, service, { root: options.root })
+  })
+
 // Reset the LLemonStack environment
 main
   .command('reset')
@@ -124,10 +214,68 @@ main
   .command('update')
   .description('Update the LLemonStack environment')
   .arguments('[service:string]')
+  .option('--dry-run', 'Show what would be updated without updating anything', {
+    default: false,
+  })
   .action(async (options, service?: string) => {
     const config = await initConfig('update', options)
     const { update } = await import('./scripts/update.ts')
-    await update(config, { service })
+    await update(config, { service, dryRun: options.dryRun })
+  })
+
+// Migrate the project config to the current LLemonStack version
+main
+  .command('migrate')
+  .description('Migrate the project config and .env to the current LLemonStack version')
+  .option('--check', 'Show the pending migrations without changing any files', {
+    default: false,
+  })
+  .action(async (options) => {
+    // Outdated configs can't be initialized until they're migrated
+    const relayer = await initRelayer('migrate', options)
+    if (options.config === Config.defaultConfigFilePath) {
+      await useRegistryProject(options.config, relayer)
+    }
+    const { migrate } = await import('./scripts/migrate.ts')
+    await migrate(relayer.show, options.config, { check: options.check })
+  })
+
+// Lock the images of the enabled services to their digests
+main
+  .command('lock')
+  .description('Lock the images of enabled services to their digests in images.lock')
+  .option('--upgrade [service:string]', 'Pull the latest images and update the digests')
+  .example('Lock new images:', 'llmn lock')
+  .example('Upgrade the n8n images:', 'llmn lock --upgrade n8n')
+  .action(async (options) => {
+    const config = await initConfig('lock', options)
+    const { lock } = await import('./scripts/lock.ts')
+    await lock(config, { upgrade: options.upgrade })
+  })
+
+// Create and load offline bundles
+main
+  .command('bundle')
+  .description('Create or load a bundle to run the stack on a machine without internet access')
+  .type('actions', new EnumType(['create', 'load']))
+  .arguments('<action:actions> [file:string]')
+  .example('Bundle the images, repos and services of enabled services:', 'llmn bundle create')
+  .example('Load a bundle on the offline machine:', 'llmn bundle load stack-bundle.tar')
+  .action(async (options, action, file?: string) => {
+    // Load creates the project config if the bundle is loaded into a new project
+    const config = await initConfig('bundle', options, action === 'load')
+    const bundle = await import('./scripts/bundle.ts')
+    switch (action) {
+      case 'create':
+        await bundle.bundleCreate(config, { output: file })
+        break
+      case 'load':
+        if (!file) {
+          config.relayer.show.fatal('Bundle file is required: llmn bundle load <file>')
+        }
+        await bundle.bundleLoad(config, file!)
+        break
+    }
   })
 
 // Show all versions of all services in the stack
@@ -140,6 +288,30 @@ main
     await versions(config)
   })
 
+// Show the status of the LLemonStack services
+main
+  .command('status')
+  .description('Show the status of services')
+  .option('-w, --watch', 'Continuously refresh the status', { default: false })
+  .option('-i, --interval <seconds:number>', 'Seconds between refreshes in watch mode', {
+    default: 2,
+  })
+  .option('--json', 'Output the status as JSON, same as --output json', {
+    default: false,
+    conflicts: ['watch'],
+  })
+  .action(async (options) => {
+    const config = await initConfig('status', {
+      ...options,
+      output: options.json ? 'json' : options.output,
+    })
+    const { status } = await import('./scripts/status.ts')
+    await status(config, {
+      watch: options.watch,
+      interval: options.interval,
+    })
+  })
+
 main
   .command('info')
   .description('Show project info')
@@ -150,6 +322,149 @@ main
     await info({ config, hideCredentials: options.hide })
   })
 
+main
+  .command('validate')
+  .description('Validate the llemonstack.yaml files of custom services in dirs.services')
+  .option('-a, --all', 'Also validate the built-in LLemonStack services', { default: false })
+  .action(async (options) => {
+    const config = await initConfig('validate', options)
+    const { validate } = await import('./scripts/validate.ts')
+    await validate(config, { all: options.all })
+  })
+
+main
+  .command('doctor')
+  .description('Check for common problems that prevent the stack from starting')
+  .option('--fix', 'Apply safe automatic fixes', { default: false })
+  .action(async (options) => {
+    const config = await initConfig('doctor', options)
+    const { doctor } = await import('./scripts/doctor.ts')
+    await doctor(config, { fix: options.fix })
+  })
+
+main
+  .command('service')
+  .description('Create custom services for the project')
+  .type('actions', new EnumType(['new']))
+  .type('groups', new EnumType(['apps', 'middleware', 'databases']))
+  .arguments('<action:actions> [service:string]')
+  .option('--name <name:string>', 'Friendly name of the service')
+  .option('--description <description:string>', 'Description of the service')
+  .option('--group <group:groups>', 'Service group')
+  .option('--image <image:string>', 'Docker image, e.g. nginx:latest')
+  .option('--port <port:string>', 'Host port mapping, e.g. 8080:80', { collect: true })
+  .option('--provides <provides:string>', 'Name other services can depend on', { collect: true })
+  .option('--depends-on <service:string>', 'Dependency, e.g. postgres:service_healthy', {
+    collect: true,
+  })
+  .option('--dashboard-url <url:string>', 'Dashboard url on the host')
+  .option('--api-url <url:string>', 'API url inside the stack network')
+  .option('--secret <secret:string>', 'Env var to generate, e.g. MY_KEY:generateSecretKey:32', {
+    collect: true,
+  })
+  .option('--healthcheck <command:string>', 'Healthcheck command run in the container')
+  .option('--service-ts', 'Create a service.ts file to customize the service')
+  .option('--no-service-ts', 'Skip creating a service.ts file')
+  .option('--dir <dir:string>', 'Services dir to create the service in')
+  .option('--no-enable', 'Add the service to config.json without enabling it')
+  .option('--skip-prompt', 'Use the options and defaults without prompting', { default: false })
+  .example('Create a service interactively:', 'llmn service new')
+  .example(
+    'Create a service from flags:',
+    'llmn service new my-app --image nginx:latest --port 8080:80 --skip-prompt',
+  )
+  .action(async (options, _action, service?: string) => {
+    const config = await initConfig('service', options)
+    const { newService } = await import('./scripts/service.ts')
+    await newService(config, service, options)
+  })
+
+main
+  .command('secrets')
+  .description('Manage the secrets generated for services and the encrypted secrets store')
+  .type('actions', new EnumType(['rotate', 'import', 'export', 'edit']))
+  .arguments('<action:actions> [service:string] [keys...:string]')
+  .option('--dry-run', 'Show the secrets and services that would change', { default: false })
+  .option('--no-restart', "Don't restart the services that use the secrets")
+  .option('--to-env', 'Export: move the secrets back into .env and disable encryption', {
+    default: false,
+  })
+  .option('--skip-prompt', 'Skip confirmation prompts', { default: false })
+  .example('Rotate all generated secrets for a service:', 'llmn secrets rotate flowise')
+  .example('Rotate a single secret:', 'llmn secrets rotate flowise FLOWISE_PASSWORD')
+  .example('Show what would be rotated:', 'llmn secrets rotate --dry-run')
+  .example('Move the secrets in .env to the encrypted store:', 'llmn secrets import')
+  .example('Edit the encrypted secrets:', 'llmn secrets edit')
+  .example('Decrypt the secrets back into .env:', 'llmn secrets export --to-env')
+  .action(async (options, action, service?: string, ...keys: string[]) => {
+    const config = await initConfig('secrets', options)
+    const secrets = await import('./scripts/secrets.ts')
+    switch (action) {
+      case 'rotate':
+        await secrets.rotateSecrets(config, service, keys, {
+          dryRun: options.dryRun,
+          skipPrompt: options.skipPrompt,
+          restart: options.restart,
+        })
+        break
+      case 'import':
+        // Import takes env var names instead of a service
+        await secrets.importSecrets(config, [service, ...keys].filter(Boolean) as string[], {
+          dryRun: options.dryRun,
+          skipPrompt: options.skipPrompt,
+        })
+        break
+      case 'export':
+        await secrets.exportSecrets(config, {
+          toEnv: options.toEnv,
+          skipPrompt: options.skipPrompt,
+        })
+        break
+      case 'edit':
+        await secrets.editSecrets(config)
+        break
+    }
+  })
+
+main
+  .command('env')
+  .description('Inspect, edit, diff and validate the project env vars')
+  .type('actions', new EnumType(['list', 'get', 'set', 'unset', 'diff', 'validate']))
+  .arguments('[action:actions] [key:string] [value:string]')
+  .option('--reveal', 'Show secret values instead of masking them', { default: false })
+  .example('List the env vars and where each value comes from:', 'llmn env list')
+  .example('Show a secret:', 'llmn env get FLOWISE_PASSWORD --reveal')
+  .example('Set an env var:', 'llmn env set OPENAI_API_KEY sk-...')
+  .example('Compare .env with the shipped .env.example:', 'llmn env diff')
+  .example('Check the env vars used by the enabled services are set:', 'llmn env validate')
+  .action(async (options, action = 'list', key?: string, value?: string) => {
+    const config = await initConfig('env', options)
+    const env = await import('./scripts/env.ts')
+    if (['get', 'set', 'unset'].includes(action) && !key) {
+      config.relayer.show.fatal(`Env var is required: llmn env ${action} <key>`)
+    }
+    switch (action) {
+      case 'list':
+        await env.envList(config, { reveal: options.reveal })
+        break
+      case 'get':
+        await env.envGet(config, key!, { reveal: options.reveal })
+        break
+      case 'set':
+        await env.envSet(config, key!, value ?? '', { reveal: options.reveal })
+        break
+      case 'unset':
+        await env.envUnset(config, key!)
+        break
+      case 'diff':
+        await env.envDiff(config, { reveal: options.reveal })
+        break
+      case 'validate':
+        await env.envValidate(config)
+        break
+    }
+  })
+
 // Import data into services that support it
 const importServices = new EnumType(['n8n', 'flowise'])
 main
@@ -202,6 +517,42 @@ main
     }
   })
 
+// Backup the stack data to a single archive
+main
+  .command('backup')
+  .description('Backup config, .env, volumes, postgres schemas, and service data')
+  .option('--dir <dir:string>', 'Dir to save the backup archive to, default: ./shared/backups')
+  .action(async (options) => {
+    const config = await initConfig('backup', options)
+    const { backup } = await import('./scripts/backup.ts')
+    const archive = await backup(config, { dir: options.dir })
+    if (!archive) {
+      config.relayer.show.fatal('Backup not created')
+    }
+  })
+
+// Restore the stack data from a backup archive
+main
+  .command('restore')
+  .description('Restore the stack data from a backup archive')
+  .arguments('<archive:string>')
+  .option('--force', 'Restore even if the project name or version does not match', {
+    default: false,
+  })
+  .option('--skip-prompt', 'Skip confirmation prompts', { default: false })
+  .example('Restore a backup:', 'llmn restore shared/backups/llemonstack-backup-<timestamp>.tar')
+  .action(async (options, archive: string) => {
+    const config = await initConfig('restore', options)
+    const { restore } = await import('./scripts/backup.ts')
+    const restored = await restore(config, archive, {
+      force: options.force,
+      skipPrompt: options.skipPrompt,
+    })
+    if (!restored) {
+      config.relayer.show.fatal('Backup not restored')
+    }
+  })
+
 // Schema management commands
 main.command('schema')
   .description('Postgres schema management commands')
@@ -238,12 +589,48 @@ main.command('litellm')
     }
   })
 
+// Manage all LLemonStack projects on this machine
+main.command('projects')
+  .description('Manage the global registry of LLemonStack projects')
+  .type('actions', new EnumType(['list', 'switch', 'prune']))
+  .arguments('[action:actions] [project:string]')
+  .option('--skip-prompt', 'Skip confirmation prompts', { default: false })
+  .example('List all projects:', 'llmn projects list')
+  .example('Use a project when running llmn outside of a project dir:', 'llmn projects switch myproject')
+  .example('Remove projects with missing config files:', 'llmn projects prune')
+  .action(async (options, action = 'list', project?: string) => {
+    // Projects commands don't require a project config in the current dir
+    const relayer = await initRelayer('projects', options)
+    const { listProjects, pruneProjects, switchProject } = await import('./scripts/projects.ts')
+    if (action === 'switch') {
+      if (!project) {
+        relayer.show.fatal('Project name or path is required: llmn projects switch <project>')
+      }
+      await switchProject(relayer.show, project!)
+    } else if (action === 'prune') {
+      await pruneProjects(relayer.show, { skipPrompt: options.skipPrompt })
+    } else {
+      await listProjects(relayer.show)
+    }
+  })
+
 main
   .command('completions', new CompletionsCommand())
 
 // Run the command
 await main.parse(Deno.args)
 
+// Commands without a structured result still output a document in json and yaml mode
+// Errors shown by commands that continue after an error are included in the document
+if (commandName && !InterfaceRelayer.resultShown) {
+  const errors = InterfaceRelayer.errors
+  InterfaceRelayer.writeResult({
+    command: commandName,
+    success: errors.length === 0,
+    ...(errors.length ? { error: errors.join('\n') } : {}),
+  })
+}
+
 if (timerId !== undefined) {
   console.timeEnd(timerId)
 }
@@ -259,19 +646,76 @@ if (timerId !== undefined) {
  */
 async function initConfig(
   command: string,
-  options: { config: string; debug?: boolean; logLevel?: LogLevel; verbose?: boolean },
+  options: {
+    config: string
+    env?: string
+    debug?: boolean
+    logLevel?: LogLevel
+    verbose?: boolean
+    output?: OutputFormat
+  },
   init = false,
 ) {
-  // Start the timer
-  timerId = colors.gray(`LLemonStack CLI [${command}]`)
-  console.time(timerId)
+  const relayer = await initRelayer(command, options)
+  const logLevel = isTruthy(options.debug) ? 'debug' : options.logLevel ?? 'info'
+
+  // Use the current project from the global registry when running outside of a project dir
+  if (!init && options.config === Config.defaultConfigFilePath) {
+    await useRegistryProject(options.config, relayer)
+  }
+
+  const config = Config.getInstance()
+  const result = await config.initialize(options.config, {
+    logLevel,
+    init,
+    relayer,
+    environment: options.env,
+  })
+
+  relayer.show.logMessages(result.messages)
+
+  if (!result.success && result.error instanceof Deno.errors.NotFound) {
+    // Show a friendly message if the config file is not found
+    relayer.show.fatal('Config file not found, run `llmn init` to create a new project')
+  }
+
+  if (!result.success) {
+    relayer.show.fatal('Error initializing config', { error: result.error })
+  }
+
+  return config
+}
+
+/**
+ * Initialize the relayer for a command
+ *
+ * Called by initConfig, or directly by commands that don't require a project config.
+ * @param command - The command name
+ * @returns The relayer instance
+ */
+async function initRelayer(
+  command: string,
+  options: {
+    debug?: boolean
+    logLevel?: LogLevel
+    verbose?: boolean
+    output?: OutputFormat
+  },
+) {
+  commandName = command
 
   const logLevel = isTruthy(options.debug) ? 'debug' : options.logLevel ?? 'info'
 
   // Initialize the relayer to capture config log messages
-  await Relayer.initialize({ logLevel, verbose: options.verbose })
+  await Relayer.initialize({ logLevel, verbose: options.verbose, output: options.output })
   const relayer = Relayer.getInstance()
 
+  // Start the timer, console.timeEnd writes to stdout so it's skipped for json and yaml output
+  if (!InterfaceRelayer.isStructuredOutput()) {
+    timerId = colors.gray(`LLemonStack CLI [${command}]`)
+    console.time(timerId)
+  }
+
   // TODO: set the show relayer
   // TODO: pass in relayer instance to config
 
@@ -282,21 +726,35 @@ async function initConfig(
     relayer.show.debug('CLI options:', options)
   }
 
-  const config = Config.getInstance()
-  const result = await config.initialize(options.config, { logLevel, init, relayer })
-
-  relayer.show.logMessages(result.messages)
+  return relayer
+}
 
-  if (!result.success && result.error instanceof Deno.errors.NotFound) {
-    // Show a friendly message if the config file is not found
-    showAction('Please run `llmn init` to create a new project')
-    Deno.exit(1)
+/**
+ * Change to the current project dir from `llmn projects switch` if there's no project config
+ * in the current dir
+ *
+ * Paths in the project config are relative to the project dir, so the process cwd is changed
+ * to the project dir. The switch is logged so it's clear which project the command runs in.
+ * @param configFile - The project config file path, relative to the project dir
+ * @param relayer - The relayer to log the dir switch to
+ */
+async function useRegistryProject(configFile: string, relayer: Relayer) {
+  try {
+    await Deno.stat(configFile)
+    return // Config file exists in the current dir
+  } catch (_error) {
+    // Config file not found, check the registry below
   }
-
-  if (!result.success) {
-    showError('Error initializing config', result.error)
-    Deno.exit(1)
+  const { ProjectRegistry } = await import('@/core/config/lib/registry.ts')
+  const registry = ProjectRegistry.getInstance()
+  const result = await registry.load()
+  const project = result.success ? registry.current : null
+  if (project) {
+    const cwd = Deno.cwd()
+    Deno.chdir(project.path)
+    relayer.show.info(
+      `No project in ${cwd}, switched to the current project ` +
+        `${colors.yellow(project.projectName)}: ${project.path}`,
+    )
   }
-
-  return config
 }
@@ -0,0 +1,562 @@
+/**
+ * Backup and restore the stack data
+ *
+ * A backup archive contains:
+ * - manifest.json: LLemonStack version, project name, and contents of the archive
+ * - config/: the project config.json and .env files
+ * - volumes.tar.gz: the project volumes dir
+ * - postgres/: a pg_dump of each service schema
+ * - data/: Qdrant, Neo4j, and MinIO data
+ */
+
+import { Config } from '@/core/config/config.ts'
+import { runCommand } from '@/lib/command.ts'
+import {
+  dockerArchiveVolume,
+  dockerComposePs,
+  dockerRestoreVolume,
+  tryDockerCompose,
+} from '@/lib/docker.ts'
+import { ensureDir, fileExists, path, readJson, saveJson } from '@/lib/fs.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import { ServiceType } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+
+const MANIFEST_FILE = 'manifest.json'
+const VOLUMES_ARCHIVE = 'volumes.tar.gz'
+
+// Postgres superuser in the supabase db container, uses POSTGRES_PASSWORD
+const POSTGRES_ADMIN_USER = 'supabase_admin'
+
+// Postgres data dir in the volumes dir, only archived when postgres is not running.
+// When postgres is running, service schemas are dumped with pg_dump instead.
+const POSTGRES_DATA_PATH = 'supabase/db/data'
+
+// Service data that is archived separately from the volumes dir.
+// volume is a docker compose volume name, path is relative to the volumes dir.
+// Containers are stopped while their data is archived to keep the data consistent.
+const SERVICE_DATA: Array<{ service: string; volume?: string; path?: string }> = [
+  { service: 'qdrant', volume: 'qdrant_storage' },
+  { service: 'neo4j', volume: 'neo4j_data' },
+  { service: 'minio', path: 'minio' },
+]
+
+export interface BackupManifest {
+  llemonstackVersion: string
+  configVersion: string
+  projectName: string
+  created: string
+  services: string[] // Enabled services at the time of the backup
+  postgres: string[] // Dumped postgres schemas
+  data: string[] // Services with data in the data dir
+  volumes: boolean // True if the volumes dir is included
+}
+
+/**
+ * Get a timestamp that is safe to use in file names
+ */
+function getTimestamp(date: Date = new Date()): string {
+  return date.toISOString().replace(/[:.]/g, '-')
+}
+
+/**
+ * Get the docker source for a service's data, a volume name or host path
+ */
+function getServiceDataSource(
+  config: Config,
+  { volume, path: volumePath }: { volume?: string; path?: string },
+): string {
+  return volume ? `${config.projectName}_${volume}` : path.join(config.volumesDir, volumePath!)
+}
+
+/**
+ * Run a command in the postgres container as the admin user
+ */
+async function postgresExec(
+  config: Config,
+  postgres: ServiceType,
+  container: string,
+  args: string[],
+): Promise<TryCatchResult<string>> {
+  const result = await tryDockerCompose('exec', {
+    projectName: config.projectName,
+    composeFile: postgres.composeFile,
+    profiles: postgres.getProfiles(),
+    // Forward the password from the process env so it's not visible in the command line
+    args: ['-T', '-e', 'PGPASSWORD', container, ...args],
+    env: { PGPASSWORD: config.env.POSTGRES_PASSWORD },
+    captureOutput: true,
+    silent: true,
+  })
+  if (!result.success) {
+    return failure<string>(`Postgres command failed: ${args[0]}`, result)
+  }
+  return success<string>(result.data?.toString().trim() || '')
+}
+
+/**
+ * Get the postgres service and container if postgres is running
+ */
+async function getRunningPostgres(
+  config: Config,
+): Promise<[ServiceType, string] | null> {
+  const [postgres, containers] = config.getServiceContainers('postgres') || []
+  if (!postgres || !containers?.length || !postgres.isEnabled()) {
+    return null
+  }
+  return await postgres.isRunning() ? [postgres, containers[0]] : null
+}
+
+/**
+ * Dump each service schema with pg_dump
+ *
+ * @returns The names of the dumped schemas
+ */
+async function dumpPostgresSchemas(
+  config: Config,
+  [postgres, container]: [ServiceType, string],
+  outputDir: string,
+): Promise<TryCatchResult<string[]>> {
+  const results = success<string[]>([])
+  const psql = ['psql', '-h', 'localhost', '-U', POSTGRES_ADMIN_USER, '-d', 'postgres']
+
+  const schemasResult = await postgresExec(config, postgres, container, [
+    ...psql,
+    '-Atc',
+    "SELECT schema_name FROM information_schema.schemata WHERE schema_name LIKE 'service\\_%'",
+  ])
+  if (!schemasResult.success) {
+    return failure<string[]>('Unable to list postgres service schemas', schemasResult)
+  }
+
+  const schemas = (schemasResult.data || '').split('\n').map((s) => s.trim()).filter(Boolean)
+  await ensureDir(outputDir)
+
+  for (const schema of schemas) {
+    const tmpFile = `/tmp/${schema}.dump`
+    const dumpResult = await postgresExec(config, postgres, container, [
+      'pg_dump',
+      '-h',
+      'localhost',
+      '-U',
+      POSTGRES_ADMIN_USER,
+      '-d',
+      'postgres',
+      '-n',
+      schema,
+      '-Fc',
+      '-f',
+      tmpFile,
+    ])
+    if (!dumpResult.success) {
+      return failure<string[]>(`Unable to dump postgres schema: ${schema}`, dumpResult)
+    }
+    const copyResult = await tryDockerCompose('cp', {
+      projectName: config.projectName,
+      composeFile: postgres.composeFile,
+      args: [`${container}:${tmpFile}`, path.join(outputDir, `${schema}.dump`)],
+      silent: true,
+    })
+    await postgresExec(config, postgres, container, ['rm', '-f', tmpFile])
+    if (!copyResult.success) {
+      return failure<string[]>(`Unable to copy postgres dump: ${schema}`, copyResult)
+    }
+    results.data!.push(schema)
+    results.addMessage('info', `Dumped postgres schema: ${schema}`)
+  }
+
+  return results
+}
+
+/**
+ * Restore service schemas from pg_dump files
+ *
+ * Service users are created with the passwords from the restored .env before the schemas
+ * are restored so the schema owners and grants are restored as well.
+ */
+async function restorePostgresSchemas(
+  config: Config,
+  [postgres, container]: [ServiceType, string],
+  inputDir: string,
+  schemas: string[],
+): Promise<TryCatchResult<boolean>> {
+  const results = success<boolean>(true)
+
+  for (const service of config.getAllServices().values()) {
+    const dbEnvKeys = service.config.init?.postgres_schema
+    const username = dbEnvKeys && config.env[dbEnvKeys.user]
+    const password = dbEnvKeys && config.env[dbEnvKeys.pass]
+    if (!username || !password || !schemas.includes(`service_${username}`)) {
+      continue
+    }
+    const sql = [
+      `DO $$ BEGIN IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = '${username}')`,
+      `THEN CREATE ROLE ${username}; END IF; END $$;`,
+      `ALTER ROLE ${username} WITH LOGIN PASSWORD '${password.replaceAll("'", "''")}';`,
+    ].join(' ')
+    const roleResult = await postgresExec(config, postgres, container, [
+      'psql',
+      '-h',
+      'localhost',
+      '-U',
+      POSTGRES_ADMIN_USER,
+      '-d',
+      'postgres',
+      '-c',
+      sql,
+    ])
+    if (!roleResult.success) {
+      return failure<boolean>(`Unable to create postgres user for ${service.name}`, roleResult)
+    }
+  }
+
+  for (const schema of schemas) {
+    const tmpFile = `/tmp/${schema}.dump`
+    const copyResult = await tryDockerCompose('cp', {
+      projectName: config.projectName,
+      composeFile: postgres.composeFile,
+      args: [path.join(inputDir, `${schema}.dump`), `${container}:${tmpFile}`],
+      silent: true,
+    })
+    if (!copyResult.success) {
+      return failure<boolean>(`Unable to copy postgres dump: ${schema}`, copyResult)
+    }
+    const restoreResult = await postgresExec(config, postgres, container, [
+      'pg_restore',
+      '-h',
+      'localhost',
+      '-U',
+      POSTGRES_ADMIN_USER,
+      '-d',
+      'postgres',
+      '--clean',
+      '--if-exists',
+      tmpFile,
+    ])
+    await postgresExec(config, postgres, container, ['rm', '-f', tmpFile])
+    if (!restoreResult.success) {
+      return failure<boolean>(`Unable to restore postgres schema: ${schema}`, restoreResult)
+    }
+    results.addMessage('info', `Restored postgres schema: ${schema}`)
+  }
+
+  return results
+}
+
+/**
+ * Stop a service container while running fn, then start it again if it was running
+ */
+async function withServiceStopped<T>(
+  config: Config,
+  service: ServiceType,
+  fn: () => Promise<T>,
+): Promise<T> {
+  const running = await service.isRunning()
+  const options = {
+    projectName: config.projectName,
+    composeFile: service.composeFile,
+    profiles: service.getProfiles(),
+    args: service.containerNames,
+    silent: true,
+  }
+  if (running) {
+    await tryDockerCompose('stop', options)
+  }
+  try {
+    return await fn()
+  } finally {
+    if (running) {
+      await tryDockerCompose('start', options)
+    }
+  }
+}
+
+/**
+ * Check the backup manifest matches the current project
+ *
+ * @returns A failure if the project name or LLemonStack version doesn't match
+ */
+export function verifyManifest(
+  config: Config,
+  manifest: BackupManifest,
+): TryCatchResult<boolean> {
+  const results = success<boolean>(true)
+  if (manifest.projectName !== config.projectName) {
+    return failure<boolean>(
+      `Backup is for project '${manifest.projectName}', current project is '${config.projectName}'`,
+      results,
+      false,
+    )
+  }
+  // Only compare major and minor versions, patch releases don't change the data layout
+  const version = (v: string) => (v || '').split('.').slice(0, 2).join('.')
+  if (version(manifest.llemonstackVersion) !== version(Config.llemonstackVersion)) {
+    return failure<boolean>(
+      `Backup was created with LLemonStack ${manifest.llemonstackVersion}, ` +
+        `current version is ${Config.llemonstackVersion}`,
+      results,
+      false,
+    )
+  }
+  return results
+}
+
+export async function backup(
+  config: Config,
+  { dir }: { dir?: string } = {},
+): Promise<string | null> {
+  const show = config.relayer.show
+
+  const timestamp = getTimestamp()
+  const backupDir = path.resolve(dir || path.join(config.sharedDir, 'backups'))
+  const archiveFile = path.join(backupDir, `${config.projectName}-backup-${timestamp}.tar`)
+  const stagingDir = path.join(config.configDir, 'tmp', `backup-${timestamp}`)
+
+  const manifest: BackupManifest = {
+    llemonstackVersion: Config.llemonstackVersion,
+    configVersion: config.version,
+    projectName: config.projectName,
+    created: new Date().toISOString(),
+    services: config.getEnabledServices().map((service) => service.service),
+    postgres: [],
+    data: [],
+    volumes: true,
+  }
+
+  show.action(`Creating backup for project: ${config.projectName}...`)
+
+  try {
+    await ensureDir(backupDir)
+    await ensureDir(path.join(stagingDir, 'config'))
+    await ensureDir(path.join(stagingDir, 'data'))
+
+    // Config, .env and the encrypted secrets file, the secrets key file is not backed up
+    await Deno.copyFile(config.configFile, path.join(stagingDir, 'config', 'config.json'))
+    if ((await fileExists(config.envFile)).data) {
+      await Deno.copyFile(config.envFile, path.join(stagingDir, 'config', '.env'))
+    }
+    if ((await fileExists(config.secretsFile)).data) {
+      await Deno.copyFile(config.secretsFile, path.join(stagingDir, 'config', 'secrets.enc'))
+    }
+
+    // Postgres service schemas
+    const postgres = await getRunningPostgres(config)
+    if (postgres) {
+      show.action('Dumping postgres service schemas...')
+      const dumpResult = await dumpPostgresSchemas(
+        config,
+        postgres,
+        path.join(stagingDir, 'postgres'),
+      )
+      show.logMessages(dumpResult.messages)
+      if (!dumpResult.success) {
+        throw dumpResult.error
+      }
+      manifest.postgres = dumpResult.data || []
+    } else {
+      show.info('Postgres is not running, its data dir will be included in the volumes archive')
+    }
+
+    // Qdrant, Neo4j, and MinIO data
+    for (const item of SERVICE_DATA) {
+      const service = config.getServiceByName(item.service)
+      if (!service?.isEnabled()) {
+        continue
+      }
+      show.action(`Archiving ${service.name} data...`)
+      const result = await withServiceStopped(config, service, () =>
+        dockerArchiveVolume(
+          getServiceDataSource(config, item),
+          path.join(stagingDir, 'data', `${item.service}.tar.gz`),
+        ))
+      if (!result.success) {
+        throw result.error
+      }
+      manifest.data.push(item.service)
+    }
+
+    // Volumes dir, excluding data archived above
+    show.action('Archiving volumes dir...')
+    const exclude = [
+      ...SERVICE_DATA.filter((item) => item.path).map((item) => item.path!),
+      ...(postgres ? [POSTGRES_DATA_PATH] : []),
+    ]
+    const volumesResult = await dockerArchiveVolume(
+      config.volumesDir,
+      path.join(stagingDir, VOLUMES_ARCHIVE),
+      { exclude },
+    )
+    if (!volumesResult.success) {
+      throw volumesResult.error
+    }
+
+    await saveJson(path.join(stagingDir, MANIFEST_FILE), manifest)
+
+    // Combine everything into a single archive
+    await runCommand('tar', {
+      args: ['-cf', archiveFile, '-C', stagingDir, '.'],
+      silent: true,
+      autoLoadEnv: false,
+    })
+
+    show.action(`\nBackup created: ${colors.yellow(path.relative(Deno.cwd(), archiveFile))}`)
+    show.result({ archive: archiveFile, ...manifest })
+    return archiveFile
+  } catch (error) {
+    show.error('Backup failed', { error })
+    return null
+  } finally {
+    await Deno.remove(stagingDir, { recursive: true }).catch(() => {})
+  }
+}
+
+export async function restore(
+  config: Config,
+  archive: string,
+  { force = false, skipPrompt = false }: { force?: boolean; skipPrompt?: boolean } = {},
+): Promise<boolean> {
+  const show = config.relayer.show
+
+  const archiveFile = path.resolve(archive)
+  if (!(await fileExists(archiveFile)).data) {
+    show.error(`Backup archive not found: ${archive}`)
+    return false
+  }
+
+  const stagingDir = path.join(config.configDir, 'tmp', `restore-${getTimestamp()}`)
+
+  try {
+    await ensureDir(stagingDir)
+    await runCommand('tar', {
+      args: ['-xf', archiveFile, '-C', stagingDir],
+      silent: true,
+      autoLoadEnv: false,
+    })
+
+    // Verify the archive before overwriting anything
+    const manifestResult = await readJson<BackupManifest>(path.join(stagingDir, MANIFEST_FILE))
+    if (!manifestResult.success || !manifestResult.data) {
+      show.error('Invalid backup archive, manifest.json not found')
+      return false
+    }
+    const manifest = manifestResult.data
+    const verifyResult = verifyManifest(config, manifest)
+    if (!verifyResult.success) {
+      if (!force) {
+        show.logMessages(verifyResult.messages)
+        show.info('Use --force to restore anyway')
+        return false
+      }
+      show.warn(verifyResult.messages.map((m) => m.message).join('\n'))
+    }
+
+    show.info(`Backup created: ${colors.yellow(manifest.created)}`)
+    show.info(`LLemonStack version: ${colors.yellow(manifest.llemonstackVersion)}`)
+    if (
+      !skipPrompt &&
+      !show.confirm('Restore will overwrite the project config, .env, and data. Continue?')
+    ) {
+      return false
+    }
+
+    // Services must be stopped before their data is overwritten
+    const containers = await dockerComposePs(config.projectName)
+    if (containers.some((c) => c.State === 'running')) {
+      if (!skipPrompt && !show.confirm('The stack is running and will be stopped. Continue?')) {
+        return false
+      }
+      const { stop } = await import('./stop.ts')
+      await stop(config, { all: true })
+    }
+
+    // Config and .env
+    show.action('Restoring config and .env...')
+    const backupSuffix = `.${getTimestamp()}.bak`
+    await Deno.copyFile(config.configFile, `${config.configFile}${backupSuffix}`)
+    await Deno.copyFile(path.join(stagingDir, 'config', 'config.json'), config.configFile)
+    const envBackup = path.join(stagingDir, 'config', '.env')
+    if ((await fileExists(envBackup)).data) {
+      if ((await fileExists(config.envFile)).data) {
+        await Deno.copyFile(config.envFile, `${config.envFile}${backupSuffix}`)
+      }
+      await Deno.copyFile(envBackup, config.envFile)
+    }
+    const secretsBackup = path.join(stagingDir, 'config', 'secrets.enc')
+    if ((await fileExists(secretsBackup)).data) {
+      if ((await fileExists(config.secretsFile)).data) {
+        await Deno.copyFile(config.secretsFile, `${config.secretsFile}${backupSuffix}`)
+      }
+      await Deno.copyFile(secretsBackup, config.secretsFile)
+      const secretsResult = await config.loadSecrets()
+      if (!secretsResult.success) {
+        show.warn('Unable to decrypt the restored secrets file, check the secrets passphrase')
+      }
+    }
+    await config.loadEnv({ reload: true })
+
+    // Volumes dir
+    if (manifest.volumes) {
+      show.action('Restoring volumes dir...')
+      await ensureDir(config.volumesDir)
+      const result = await dockerRestoreVolume(
+        config.volumesDir,
+        path.join(stagingDir, VOLUMES_ARCHIVE),
+      )
+      if (!result.success) {
+        throw result.error
+      }
+    }
+
+    // Qdrant, Neo4j, and MinIO data
+    for (const item of SERVICE_DATA.filter((item) => manifest.data.includes(item.service))) {
+      show.action(`Restoring ${item.service} data...`)
+      if (item.path) {
+        await ensureDir(path.join(config.volumesDir, item.path))
+      }
+      const result = await dockerRestoreVolume(
+        getServiceDataSource(config, item),
+        path.join(stagingDir, 'data', `${item.service}.tar.gz`),
+        { clean: true },
+      )
+      if (!result.success) {
+        throw result.error
+      }
+    }
+
+    // Postgres service schemas
+    if (manifest.postgres.length > 0) {
+      show.action('Restoring postgres service schemas...')
+      const [postgresService, postgresContainers] = config.getServiceContainers('postgres') || []
+      if (!postgresService || !postgresContainers?.length) {
+        throw new Error('Postgres service not found, unable to restore schemas')
+      }
+      const startResult = await postgresService.start()
+      const readyResult = startResult.success
+        ? await postgresService.waitForCondition('service_healthy')
+        : startResult
+      if (!readyResult.success) {
+        show.logMessages(readyResult.messages)
+        throw new Error('Unable to start postgres to restore schemas')
+      }
+      const restoreResult = await restorePostgresSchemas(
+        config,
+        [postgresService, postgresContainers[0]],
+        path.join(stagingDir, 'postgres'),
+        manifest.postgres,
+      )
+      show.logMessages(restoreResult.messages)
+      if (!restoreResult.success) {
+        throw restoreResult.error
+      }
+    }
+
+    show.action('\nRestore complete, start the stack with: llmn start')
+    show.result({ archive: archiveFile, restored: true, ...manifest })
+    return true
+  } catch (error) {
+    show.error('Restore failed', { error })
+    return false
+  } finally {
+    await Deno.remove(stagingDir, { recursive: true }).catch(() => {})
+  }
+}
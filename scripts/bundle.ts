@@ -0,0 +1,307 @@
+/**
+ * Create and load offline bundles for machines without internet access
+ *
+ * See src/lib/bundle.ts for the bundle format.
+ */
+import { Config } from '@/core/config/config.ts'
+import { getComposeImages } from '@/core/services/utils/mod.ts'
+import {
+  BUNDLE_IMAGES_FILE,
+  BUNDLE_REPOS_FILE,
+  BUNDLE_SERVICES_DIR,
+  createArchive,
+  extractArchive,
+  getBundleFileName,
+  readBundleManifest,
+  writeBundleManifest,
+} from '@/lib/bundle.ts'
+import {
+  getDockerImageDigests,
+  loadDockerImages,
+  pullDockerImage,
+  saveDockerImages,
+  tagDockerImage,
+  tryDockerCompose,
+} from '@/lib/docker.ts'
+import { dirExists, ensureDir, fileExists, fs, path } from '@/lib/fs.ts'
+import { getDigestReference } from '@/lib/images-lock.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import { ServiceType } from '@/types'
+import packageJson from '@packageJson' with { type: 'json' }
+
+/**
+ * Create a temporary dir in the project config dir
+ *
+ * Kept on the same filesystem as the project so bundle files aren't copied twice.
+ */
+async function makeBundleDir(config: Config): Promise<string> {
+  await ensureDir(config.configDir)
+  return await Deno.makeTempDir({ dir: config.configDir, prefix: 'bundle-' })
+}
+
+/**
+ * Make sure an image is available locally so it can be saved
+ *
+ * Locked images are pulled by digest and tagged with the image reference.
+ * Built images are built with the service compose file.
+ */
+async function ensureImage(
+  config: Config,
+  service: ServiceType,
+  { image, build }: { image: string; build: boolean },
+): Promise<TryCatchResult<boolean>> {
+  const show = config.relayer.show
+  const digests = await getDockerImageDigests(image)
+  const digest = config.imagesLock?.images[image]?.digest
+
+  if (build) {
+    if (digests.success) {
+      return success<boolean>(true)
+    }
+    show.info(`Building ${image}...`)
+    const result = await tryDockerCompose('build', {
+      projectName: config.projectName,
+      composeFile: service.composeFile,
+      profiles: service.getProfiles(),
+      silent: true,
+    })
+    return result.success
+      ? success<boolean>(true)
+      : failure<boolean>(`Unable to build image: ${image}`, result, false)
+  }
+
+  if (digests.success && (!digest || digests.data?.some((d) => d.endsWith(`@${digest}`)))) {
+    return success<boolean>(true)
+  }
+
+  if (digest) {
+    const reference = getDigestReference(image, digest)
+    show.info(`Pulling ${reference}...`)
+    const pullResult = await pullDockerImage(reference)
+    if (!pullResult.success) {
+      return pullResult
+    }
+    // Images pulled by digest don't have a tag, docker save needs the image reference
+    return await tagDockerImage(reference, image)
+  }
+
+  show.info(`Pulling ${image}...`)
+  return await pullDockerImage(image)
+}
+
+/**
+ * Create a bundle with the images, repos and service definitions of the enabled services
+ *
+ * @param output - The bundle file, defaults to <project>-bundle-<date>.tar in the current dir
+ */
+export async function bundleCreate(
+  config: Config,
+  { output }: { output?: string } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  const file = path.resolve(Deno.cwd(), output || getBundleFileName(config.projectName))
+
+  show.action(`Creating offline bundle for ${config.projectName}...`)
+
+  // Clone repos and load the images lock
+  const prepareResult = await config.prepareEnv({ silent: true })
+  if (!prepareResult.success) {
+    show.logMessages(prepareResult.messages)
+    show.fatal('Failed to prepare the environment')
+  }
+
+  const services = config.getEnabledServices().toArray()
+
+  // Get the images of the enabled services and make sure they're available locally
+  const images = new Set<string>()
+  for (const service of services) {
+    const imagesResult = await getComposeImages(service, { projectName: config.projectName })
+    if (!imagesResult.success || !imagesResult.data) {
+      show.logMessages(imagesResult.messages)
+      show.fatal(`Unable to get the images for ${service.name}`)
+    }
+    for (const composeImage of imagesResult.data!) {
+      if (images.has(composeImage.image)) continue
+      const imageResult = await ensureImage(config, service, composeImage)
+      if (!imageResult.success) {
+        show.logMessages(imageResult.messages)
+        show.fatal(`Unable to get image ${composeImage.image} for ${service.name}`)
+      }
+      images.add(composeImage.image)
+    }
+  }
+
+  const repos = services
+    .filter((service) => service.repoConfig?.dir && service.repoDir)
+    .map((service) => service.repoConfig!.dir)
+    .filter((dir, index, all) => all.indexOf(dir) === index)
+
+  const bundleDir = await makeBundleDir(config)
+  const fail = async (message: string, result?: TryCatchResult<unknown>) => {
+    await Deno.remove(bundleDir, { recursive: true })
+    if (result) {
+      show.logMessages(result.messages)
+    }
+    show.fatal(message, { error: result?.error })
+  }
+
+  show.action(`Saving ${images.size} images, this can take a while...`)
+  const saveResult = await saveDockerImages(
+    [...images],
+    path.join(bundleDir, BUNDLE_IMAGES_FILE),
+    { silent: false },
+  )
+  if (!saveResult.success) {
+    return await fail('Failed to save images', saveResult)
+  }
+
+  if (repos.length) {
+    show.action(`Archiving ${repos.length} repos...`)
+    const reposResult = await createArchive(
+      path.join(bundleDir, BUNDLE_REPOS_FILE),
+      config.reposDir,
+      repos,
+    )
+    if (!reposResult.success) {
+      return await fail('Failed to archive repos', reposResult)
+    }
+  }
+
+  // Service definitions are loaded instead of the installed services after the bundle is loaded
+  const serviceDirs = services.map((service) => path.basename(service.dir))
+  for (const service of services) {
+    await fs.copy(
+      service.dir,
+      path.join(bundleDir, BUNDLE_SERVICES_DIR, path.basename(service.dir)),
+    )
+  }
+
+  await writeBundleManifest(bundleDir, {
+    llemonstack: packageJson.version,
+    project: config.projectName,
+    images: [...images].sort(),
+    repos,
+    services: serviceDirs,
+  })
+
+  const archiveResult = await createArchive(file, bundleDir)
+  if (!archiveResult.success) {
+    return await fail(`Failed to create bundle: ${file}`, archiveResult)
+  }
+  await Deno.remove(bundleDir, { recursive: true })
+
+  const size = (await Deno.stat(file)).size
+  show.result({ file, images: [...images].sort(), repos, services: serviceDirs, size })
+  show.info(`\n✔️ Bundle created: ${path.relative(Deno.cwd(), file)}`)
+  show.info(`  ${images.size} images, ${repos.length} repos, ${serviceDirs.length} services`)
+  show.info(`  ${(size / 1024 / 1024).toFixed(0)} MB`)
+  show.userAction('Copy the bundle to the offline machine and run `llmn bundle load <file>`')
+}
+
+/**
+ * Load a bundle and mark the project as offline
+ *
+ * Existing repos are kept, bundled services replace the services from a previous bundle.
+ *
+ * @param file - The bundle file
+ */
+export async function bundleLoad(config: Config, file: string): Promise<void> {
+  const show = config.relayer.show
+  const bundleFile = path.resolve(Deno.cwd(), file)
+
+  if (!(await fileExists(bundleFile)).data) {
+    show.fatal(`Bundle not found: ${file}`)
+  }
+
+  show.action(`Loading offline bundle ${path.relative(Deno.cwd(), bundleFile)}...`)
+
+  const bundleDir = await makeBundleDir(config)
+  const fail = async (message: string, result?: TryCatchResult<unknown>) => {
+    await Deno.remove(bundleDir, { recursive: true })
+    if (result) {
+      show.logMessages(result.messages)
+    }
+    show.fatal(message, { error: result?.error })
+  }
+
+  const extractResult = await extractArchive(bundleFile, bundleDir)
+  if (!extractResult.success) {
+    return await fail('Failed to extract bundle', extractResult)
+  }
+  const manifestResult = await readBundleManifest(bundleDir)
+  if (!manifestResult.success || !manifestResult.data) {
+    return await fail('Invalid bundle', manifestResult)
+  }
+  const manifest = manifestResult.data!
+
+  if (manifest.llemonstack !== packageJson.version) {
+    show.warn(
+      `Bundle was created with LLemonStack ${manifest.llemonstack}, ` +
+        `this is ${packageJson.version}`,
+    )
+  }
+
+  show.action(`Loading ${manifest.images.length} images, this can take a while...`)
+  const loadResult = await loadDockerImages(path.join(bundleDir, BUNDLE_IMAGES_FILE), {
+    silent: false,
+  })
+  if (!loadResult.success) {
+    return await fail('Failed to load images', loadResult)
+  }
+
+  // Built images are named after the compose project, rename them for this project
+  if (manifest.project !== config.projectName) {
+    for (const image of manifest.images.filter((i) => i.startsWith(`${manifest.project}-`))) {
+      await tagDockerImage(
+        image,
+        `${config.projectName}-${image.slice(manifest.project.length + 1)}`,
+      )
+    }
+  }
+
+  if (manifest.repos.length) {
+    show.action(`Restoring ${manifest.repos.length} repos...`)
+    const reposDir = path.join(bundleDir, 'repos')
+    await ensureDir(reposDir, { allowOutsideCwd: true })
+    const reposResult = await extractArchive(path.join(bundleDir, BUNDLE_REPOS_FILE), reposDir)
+    if (!reposResult.success) {
+      return await fail('Failed to extract repos', reposResult)
+    }
+    await ensureDir(config.reposDir)
+    for (const repo of manifest.repos) {
+      const repoDir = path.join(config.reposDir, repo)
+      if ((await dirExists(repoDir)).data) {
+        show.info(`Keeping existing repo: ${path.relative(Deno.cwd(), repoDir)}`)
+        continue
+      }
+      await fs.copy(path.join(reposDir, repo), repoDir)
+    }
+  }
+
+  // Bundled services take priority over the installed services
+  const servicesDir = path.join(config.configDir, BUNDLE_SERVICES_DIR)
+  if ((await dirExists(servicesDir)).data) {
+    await Deno.remove(servicesDir, { recursive: true })
+  }
+  await fs.copy(path.join(bundleDir, BUNDLE_SERVICES_DIR), servicesDir)
+  config.addServicesDir(servicesDir)
+
+  await Deno.remove(bundleDir, { recursive: true })
+
+  const saveResult = await config.setOffline(true)
+  if (!saveResult.success) {
+    show.fatal('Failed to save config', { error: saveResult.error })
+  }
+
+  show.result({ file: bundleFile, ...manifest })
+  show.info(`\n✔️ Bundle loaded, ${config.projectName} is now offline`)
+  show.info(
+    'Repos are not cloned and images are not pulled while offline.\n' +
+      'Set "offline" to false in config.json to go back online.',
+  )
+  show.userAction(
+    config.isProjectInitialized()
+      ? 'Start the stack with `llmn start`'
+      : 'Initialize the project with `llmn init`, then start it with `llmn start`',
+  )
+}
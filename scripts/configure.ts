@@ -61,10 +61,15 @@ function getServiceOption(
 
 export async function configure(
   config: Config, // An initialized config instance
-  options: { all: boolean } = { all: false },
+  options: { all: boolean; dryRun?: boolean } = { all: false },
 ): Promise<void> {
   const show = config.relayer.show
 
+  // Nothing is saved in dry run mode, the changes are shown as a plan at the end
+  const { getServicesSnapshot, planConfig, showPlan } = await import('./plan.ts')
+  const before = getServicesSnapshot(config)
+  config.setDryRun(!!options.dryRun)
+
   show.action(`Configuring services for ${config.projectName}...`)
 
   // Display a key for the status emojis to help users understand the symbols
@@ -178,12 +183,25 @@ export async function configure(
     }
   }
 
+  if (config.dryRun) {
+    showPlan(config, await planConfig(config, before))
+    return
+  }
+
   // Save the configuration
   const saveResult = await config.save()
   if (!saveResult.success) {
     show.warn(`Failed to save configuration: ${saveResult.error?.message}`)
   } else {
     show.info('Configuration saved successfully.')
+    // Add the env vars of newly enabled services
+    const mergeResult = await config.mergeServicesEnv()
+    show.logMessages(mergeResult.messages)
+    if (!mergeResult.success) {
+      show.warn(`Failed to add service env vars to .env: ${mergeResult.error?.message}`)
+    } else if (mergeResult.data?.length) {
+      show.info(`Added ${mergeResult.data.length} service env vars to .env`)
+    }
     show.userAction('Start services with `llmn start`')
     // Show the list of enabled services
     const enabledServices = config.getEnabledServices().toArray().sort((a, b) =>
@@ -0,0 +1,361 @@
+/**
+ * Diagnose common problems that prevent the stack from starting
+ */
+
+import { Config } from '@/core/config/config.ts'
+import { dirExists, path } from '@/lib/fs.ts'
+import {
+  dockerComposePs,
+  type DockerComposePsResult,
+  getDockerInfo,
+  inspectDockerNetworks,
+  removeDockerNetwork,
+} from '@/lib/docker.ts'
+import { tryGetContainerRuntime } from '@/lib/runtime.ts'
+import { failure, success, tryCatch, TryCatchResult } from '@/lib/try-catch.ts'
+import { getUnsetEnvVars } from '@/lib/utils/envvars.ts'
+import { searchObjectPaths } from '@/lib/utils/search-object.ts'
+import type { ExposeHost } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { RowType } from '@cliffy/table'
+
+const GiB = 1024 ** 3
+const MIN_MEMORY = 4 * GiB
+const RECOMMENDED_MEMORY = 8 * GiB
+const OLLAMA_MEMORY = 12 * GiB // See Ollama Memory Issues in the README
+
+type DoctorStatus = 'pass' | 'warn' | 'fail'
+
+interface IDoctorCheck {
+  check: string
+  status: DoctorStatus
+  message: string
+  fix?: string // Suggested fix shown to the user
+  repair?: () => Promise<TryCatchResult<boolean>> // Safe automatic repair, run with --fix
+}
+
+const STATUS_COLORS: Record<DoctorStatus, (str: string) => string> = {
+  pass: colors.green,
+  warn: colors.yellow,
+  fail: colors.red,
+}
+
+/**
+ * Check the container runtime is installed and has enough memory for the enabled services
+ */
+async function checkRuntime(config: Config): Promise<IDoctorCheck[]> {
+  const runtimeResult = await tryGetContainerRuntime(config.containerRuntime)
+  if (!runtimeResult.success || !runtimeResult.data) {
+    return [{
+      check: 'Runtime',
+      status: 'fail',
+      message: runtimeResult.messages[0]?.message || 'No container runtime found',
+      fix: 'Install Docker Desktop, see Prerequisites in the README',
+    }]
+  }
+  const runtime = runtimeResult.data
+
+  const infoResult = await getDockerInfo()
+  if (!infoResult.success || !infoResult.data) {
+    return [{
+      check: 'Runtime',
+      status: 'fail',
+      message: `${runtime.name} is installed but not running`,
+      fix: `Start ${runtime.name === 'docker' ? 'Docker Desktop' : runtime.name}`,
+    }]
+  }
+
+  const checks: IDoctorCheck[] = [{
+    check: 'Runtime',
+    status: 'pass',
+    message: `${runtime.name} ${runtime.version} with ${runtime.compose.join(' ')} ${
+      runtime.composeVersion
+    }`,
+  }]
+
+  // Ollama in a container needs more memory than the rest of the stack
+  const ollama = config.getServiceByName('ollama')
+  const ollamaInDocker = ollama?.isEnabled() && !ollama.getProfiles().includes('ollama-host')
+  const recommended = ollamaInDocker ? OLLAMA_MEMORY : RECOMMENDED_MEMORY
+  const memory = infoResult.data.MemTotal
+  const memoryMessage = `${(memory / GiB).toFixed(1)} GiB of memory available to containers`
+  checks.push(
+    memory >= recommended
+      ? { check: 'Memory', status: 'pass', message: memoryMessage }
+      : {
+        check: 'Memory',
+        status: memory < MIN_MEMORY ? 'fail' : 'warn',
+        message: `${memoryMessage}, ${recommended / GiB} GiB recommended${
+          ollamaInDocker ? ' when running Ollama in a container' : ''
+        }`,
+        fix: 'Increase the memory in Docker Desktop > Settings > Resources',
+      },
+  )
+  return checks
+}
+
+/**
+ * Check for duplicate or unused project networks left over from a previous run
+ */
+async function checkNetwork(config: Config, running: boolean): Promise<IDoctorCheck[]> {
+  const name = config.dockerNetworkName
+  const networksResult = await inspectDockerNetworks(name)
+  if (!networksResult.success || !networksResult.data) {
+    return [{
+      check: 'Network',
+      status: 'warn',
+      message: `Unable to inspect the ${name} network`,
+      fix: 'Check that docker is running',
+    }]
+  }
+  const networks = networksResult.data
+  const inUse = networks.some((network) => Object.keys(network.Containers || {}).length > 0)
+  const remove = () => removeDockerNetwork(networks.map((network) => network.Id))
+  const removeFix = `Run ${colors.yellow('llmn doctor --fix')} to remove, it's recreated on start`
+
+  if (networks.length > 1) {
+    return [{
+      check: 'Network',
+      status: 'fail',
+      message: `Found ${networks.length} networks named ${name}, compose can't choose one`,
+      fix: inUse
+        ? `Run ${colors.yellow('llmn stop')} then ${colors.yellow('llmn doctor --fix')}`
+        : removeFix,
+      repair: inUse ? undefined : remove,
+    }]
+  }
+  if (networks.length === 1 && !running && !inUse) {
+    return [{
+      check: 'Network',
+      status: 'warn',
+      message: `Stale ${name} network left over from a previous run`,
+      fix: removeFix,
+      repair: remove,
+    }]
+  }
+  return [{
+    check: 'Network',
+    status: 'pass',
+    message: networks.length ? `${name} is in use` : `${name} is created on start`,
+  }]
+}
+
+/**
+ * Check the host ports of the enabled services are not used by other processes
+ */
+async function checkPorts(config: Config): Promise<IDoctorCheck[]> {
+  const conflictsResult = await config.getPortConflicts()
+  const conflicts = conflictsResult.data || []
+  if (conflicts.length === 0) {
+    return [{
+      check: 'Ports',
+      status: 'pass',
+      message: 'Host ports of the enabled services are available',
+    }]
+  }
+  return conflicts.map((conflict): IDoctorCheck => ({
+    check: 'Ports',
+    status: 'fail',
+    message: `${conflict.name} port ${conflict.hostPort} is already in use`,
+    fix: `Stop the process using the port or run ${colors.yellow('llmn doctor --fix')} to remap it`,
+    repair: async () => {
+      const remapResult = await config.remapPorts([conflict])
+      return remapResult.success
+        ? success<boolean>(true).collect([remapResult])
+        : failure<boolean>(`Unable to remap port ${conflict.hostPort}`, remapResult, false)
+    },
+  }))
+}
+
+/**
+ * Check the repos of the enabled services have been cloned
+ */
+async function checkRepos(config: Config): Promise<IDoctorCheck[]> {
+  const checks: IDoctorCheck[] = []
+  for (const service of config.getEnabledServices().toArray()) {
+    if (!service.repoConfig || !service.repoDir) {
+      continue
+    }
+    if ((await dirExists(service.repoDir)).data) {
+      continue
+    }
+    checks.push({
+      check: 'Repos',
+      status: 'fail',
+      message: `${service.name} repo is missing: ${path.relative(Deno.cwd(), service.repoDir)}`,
+      fix: `Run ${colors.yellow('llmn doctor --fix')} or ${
+        colors.yellow('llmn update')
+      } to clone it`,
+      repair: () => service.prepareRepo({ silent: false }),
+    })
+  }
+  return checks.length ? checks : [{
+    check: 'Repos',
+    status: 'pass',
+    message: 'Repos of the enabled services are cloned',
+  }]
+}
+
+/**
+ * Check the env vars used in the exposes section of the enabled services are set
+ */
+function checkExposesEnv(config: Config): IDoctorCheck[] {
+  const checks: IDoctorCheck[] = []
+  config.getEnabledServices().forEach((service) => {
+    const values = searchObjectPaths<ExposeHost>(service.config.exposes, '*.*').flatMap((item) =>
+      typeof item.data === 'string'
+        ? [item.data]
+        : [item.data.url, item.data.info, ...Object.values(item.data.credentials || {})]
+    )
+    const unset = new Set(
+      values.filter((value) => typeof value === 'string')
+        .flatMap((value) => getUnsetEnvVars(value, config.env)),
+    )
+    unset.forEach((key) => {
+      checks.push({
+        check: 'Env',
+        status: 'warn',
+        message: `${key} is used by ${service.name} exposes but is not set`,
+        fix: `Set ${key} in .env or run ${colors.yellow(`llmn init ${service.service}`)}`,
+      })
+    })
+  })
+  return checks.length ? checks : [{
+    check: 'Env',
+    status: 'pass',
+    message: 'Env vars used by the enabled services are set',
+  }]
+}
+
+/**
+ * Check the services the enabled services depend on are provided and enabled
+ */
+function checkDependencies(config: Config): IDoctorCheck[] {
+  const checks: IDoctorCheck[] = []
+  config.getEnabledServices().forEach((service) => {
+    service.depends_on.forEach((dependency) => {
+      const provider = config.getServiceByProvides(dependency)
+      if (!provider) {
+        checks.push({
+          check: 'Services',
+          status: 'fail',
+          message: `${service.name} depends on ${dependency}, no service provides it`,
+          fix: `Run ${colors.yellow('llmn validate')} to check the service configs`,
+        })
+      } else if (!provider.isEnabled()) {
+        checks.push({
+          check: 'Services',
+          status: 'fail',
+          message: `${service.name} depends on ${provider.name}, which is disabled`,
+          fix: `Run ${colors.yellow('llmn doctor --fix')} to set ${provider.service} to auto`,
+          repair: async () => {
+            config.updateServiceEnabledState(provider, 'auto')
+            return await config.save()
+          },
+        })
+      }
+    })
+  })
+  return checks.length ? checks : [{
+    check: 'Services',
+    status: 'pass',
+    message: 'Dependencies of the enabled services are enabled',
+  }]
+}
+
+/**
+ * Run all checks
+ */
+async function runChecks(config: Config): Promise<IDoctorCheck[]> {
+  const checks = await checkRuntime(config)
+  const runtimeOk = !checks.some((check) => check.check === 'Runtime' && check.status === 'fail')
+
+  if (runtimeOk) {
+    const psResult = await tryCatch(dockerComposePs(config.projectName))
+    const containers = (psResult.data as DockerComposePsResult | null) || []
+    checks.push(
+      ...await checkNetwork(
+        config,
+        containers.some((container) => container.State === 'running'),
+      ),
+    )
+  }
+
+  checks.push(
+    ...await checkPorts(config),
+    ...await checkRepos(config),
+    ...checkExposesEnv(config),
+    ...checkDependencies(config),
+  )
+  return checks
+}
+
+function showChecks(config: Config, checks: IDoctorCheck[]): void {
+  const show = config.relayer.show
+  const rows: RowType[] = checks.map((check) => [
+    check.check,
+    STATUS_COLORS[check.status](check.status),
+    check.message,
+  ])
+  show.table(['Check', 'Status', 'Result'], rows, { maxColumnWidth: 0 })
+
+  const fixes = checks.filter((check) => check.status !== 'pass' && check.fix)
+  if (fixes.length) {
+    show.info('\nSuggested fixes:')
+    fixes.forEach((check) => show.info(`- ${check.message}\n  ${check.fix}`))
+  }
+}
+
+/**
+ * Diagnose common problems that prevent the stack from starting
+ *
+ * @param fix - Apply the safe automatic repairs, then check again
+ */
+export async function doctor(
+  config: Config,
+  { fix = false }: { fix?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  show.action(`Checking ${config.projectName} for problems...`)
+
+  let checks = await runChecks(config)
+
+  const repairs = checks.filter((check) => check.status !== 'pass' && check.repair)
+  if (fix && repairs.length) {
+    for (const check of repairs) {
+      show.action(`Fixing: ${check.message}`)
+      const result = await check.repair!()
+      show.logMessages(result.messages)
+      if (!result.success) {
+        show.error(`Unable to fix: ${check.message}`, { error: result.error })
+      }
+    }
+    checks = await runChecks(config)
+  }
+
+  const failed = checks.filter((check) => check.status === 'fail')
+  const warnings = checks.filter((check) => check.status === 'warn')
+  show.result({
+    healthy: failed.length === 0,
+    checks: checks.map((check) => ({
+      check: check.check,
+      status: check.status,
+      message: check.message,
+      fix: check.fix || null,
+      auto_fix: !!check.repair,
+    })),
+  })
+  showChecks(config, checks)
+
+  if (!fix && checks.some((check) => check.status !== 'pass' && check.repair)) {
+    show.userAction(`\nRun ${colors.yellow('llmn doctor --fix')} to apply the automatic fixes`)
+  }
+  if (failed.length) {
+    show.fatal(`Found ${failed.length} problem(s) and ${warnings.length} warning(s)`)
+  }
+  show.info(
+    warnings.length
+      ? `✔️ No problems found, ${warnings.length} warning(s)`
+      : '✔️ No problems found',
+  )
+}
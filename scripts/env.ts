@@ -0,0 +1,369 @@
+/**
+ * Inspect, edit, diff and validate the project env vars
+ */
+import { Config } from '@/core/config/config.ts'
+import { dockerEnv } from '@/lib/docker.ts'
+import { EnvDocument, readEnvDocument } from '@/lib/env.ts'
+import { path, readTextFile, readYaml } from '@/lib/fs.ts'
+import { maskSecret } from '@/lib/secrets.ts'
+import { getUnsetEnvVars } from '@/lib/utils/envvars.ts'
+import { searchObjectPaths } from '@/lib/utils/search-object.ts'
+import type { ExposeHost } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { RowType } from '@cliffy/table'
+
+type EnvVarOrigin = 'env' | 'overlay' | 'secrets' | 'service' | 'config'
+
+interface IEnvVar {
+  key: string
+  value: string
+  origin: EnvVarOrigin
+  service: string | null // Service that sets the var in its loadEnv
+  generated: string | null // Service that generates the var with init.generate
+  secret: boolean
+}
+
+interface IEnvDiff {
+  key: string
+  status: 'missing' | 'extra' | 'changed'
+  value: string | null // Value in .env
+  example: string | null // Value in .env.example
+  source: string | null // .env.example file the key is from
+}
+
+interface IEnvReference {
+  service: string
+  key: string
+  file: string // File the var is referenced in
+}
+
+const RE_ENV_KEY = /^[a-zA-Z_][a-zA-Z0-9_]*$/
+
+/**
+ * Read the project .env file or the env overlay file, exits if the file can't be read
+ */
+async function readEnvFile(config: Config, file = config.envFile): Promise<EnvDocument> {
+  const result = await readEnvDocument(file)
+  if (!result.success || !result.data) {
+    config.relayer.show.fatal(`Unable to read ${file}`, { error: result.error })
+  }
+  return result.data!
+}
+
+/**
+ * Get the env vars set by each enabled service in loadEnv
+ *
+ * @returns Map of env var name to the service name
+ */
+async function getServiceOverrides(config: Config): Promise<Map<string, string>> {
+  const overrides = new Map<string, string>()
+  for (const [_, service] of config.getEnabledServices()) {
+    // Record the vars the service sets on a copy of the env
+    const env = new Proxy({ ...config.env }, {
+      set: (target, prop, value) => {
+        target[prop as string] = value
+        overrides.set(prop as string, service.service)
+        return true
+      },
+    })
+    await service.loadEnv(env, { config })
+  }
+  return overrides
+}
+
+/**
+ * Get all the project env vars and where each value comes from
+ */
+async function getEnvVars(config: Config): Promise<IEnvVar[]> {
+  const fileKeys = new Set((await readEnvFile(config)).keys)
+  const overlay = config.envOverlayFile
+    ? (await readEnvFile(config, config.envOverlayFile)).toObject()
+    : {}
+  const overrides = await getServiceOverrides(config)
+  const generated = new Map<string, string>()
+  config.getEnabledServices().forEach((service) => {
+    Object.keys(service.getGeneratedSecrets()).forEach((key) => generated.set(key, service.service))
+  })
+  const secrets = config.secrets
+
+  return Object.entries(config.env).map(([key, value]) => ({
+    key,
+    value,
+    // Overlay values take precedence over secrets, blank overlay values are filled by secrets
+    origin: overrides.has(key)
+      ? 'service'
+      : overlay[key]
+      ? 'overlay'
+      : key in secrets
+      ? 'secrets'
+      : key in overlay
+      ? 'overlay'
+      : fileKeys.has(key)
+      ? 'env'
+      : 'config',
+    service: overrides.get(key) ?? null,
+    generated: generated.get(key) ?? null,
+    secret: config.isSecretKey(key),
+  }))
+}
+
+function displayValue(value: string | null, secret: boolean, reveal: boolean): string | null {
+  return value && secret && !reveal ? maskSecret(value) : value
+}
+
+function getOriginLabel(config: Config, envVar: IEnvVar): string {
+  const labels: Record<EnvVarOrigin, string> = {
+    env: path.relative(Deno.cwd(), config.envFile),
+    overlay: path.relative(Deno.cwd(), config.envOverlayFile || ''),
+    secrets: path.relative(Deno.cwd(), config.secretsFile),
+    service: `${envVar.service} loadEnv`,
+    config: path.relative(Deno.cwd(), config.configFile),
+  }
+  return [labels[envVar.origin], envVar.generated && `${envVar.generated} init.generate`]
+    .filter(Boolean).join(', ')
+}
+
+/**
+ * Show the env vars with their origin: .env, the environment's env overlay, the secrets file,
+ * a service loadEnv override or the project config.
+ * Secrets generated by init.generate are also marked.
+ */
+export async function envList(
+  config: Config,
+  { reveal = false }: { reveal?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  const envVars = (await getEnvVars(config)).map((envVar) => ({
+    ...envVar,
+    value: displayValue(envVar.value, envVar.secret, reveal) ?? '',
+  }))
+
+  const rows: RowType[] = envVars.map((envVar) => [
+    colors.yellow(envVar.key),
+    envVar.value,
+    colors.gray(getOriginLabel(config, envVar)),
+  ])
+  show.table(['Env Var', 'Value', 'Origin'], rows)
+  if (!reveal && envVars.some((envVar) => envVar.secret && envVar.value)) {
+    show.info(colors.gray(`Secrets are masked, use ${colors.yellow('--reveal')} to show them`))
+  }
+  show.result({ reveal, vars: envVars })
+}
+
+/**
+ * Show the value and origin of an env var, exits with 1 if the var is not set
+ */
+export async function envGet(
+  config: Config,
+  key: string,
+  { reveal = false }: { reveal?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  const envVar = (await getEnvVars(config)).find((envVar) => envVar.key === key)
+  if (!envVar) {
+    show.fatal(`${key} is not set`)
+  }
+  const value = displayValue(envVar!.value, envVar!.secret, reveal) ?? ''
+  show.info(value)
+  show.info(colors.gray(`Origin: ${getOriginLabel(config, envVar!)}`))
+  show.result({ ...envVar!, value })
+}
+
+/**
+ * Set an env var in .env, or the encrypted secrets file if enabled and the var is a secret
+ */
+export async function envSet(
+  config: Config,
+  key: string,
+  value: string,
+  { reveal = false }: { reveal?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  if (!RE_ENV_KEY.test(key)) {
+    show.fatal(`Invalid env var name: ${key}`)
+  }
+  if (!value) {
+    show.fatal(`Value is required, use ${colors.yellow(`llmn env unset ${key}`)} to remove a var`)
+  }
+
+  const overrides = await getServiceOverrides(config)
+  if (overrides.has(key)) {
+    show.warn(`${key} is set by ${overrides.get(key)} when llmn runs, the saved value is not used`)
+  }
+
+  if (config.envOverlayFile && (await readEnvFile(config, config.envOverlayFile)).has(key)) {
+    show.warn(`${key} is overridden in ${path.relative(Deno.cwd(), config.envOverlayFile)}`)
+  }
+
+  const result = await config.setEnvFileVars({ [key]: value })
+  show.logMessages(result.messages)
+  if (!result.success) {
+    show.fatal(`Unable to set ${key}`, { error: result.error })
+  }
+
+  const file = config.secretsEnabled && config.isSecretKey(key)
+    ? config.secretsFile
+    : config.envFile
+  show.action(`✔️ Set ${key} in ${path.relative(Deno.cwd(), file)}`)
+  show.result({ key, value: displayValue(value, config.isSecretKey(key), reveal), file })
+}
+
+/**
+ * Remove an env var from .env and the encrypted secrets file
+ */
+export async function envUnset(config: Config, key: string): Promise<void> {
+  const show = config.relayer.show
+  const doc = await readEnvFile(config)
+  if (!doc.has(key) && !(key in config.secrets)) {
+    show.info(`${key} is not set in ${path.relative(Deno.cwd(), config.envFile)}`)
+    show.result({ key, removed: false })
+    return
+  }
+
+  const result = await config.unsetEnvFileVars([key])
+  show.logMessages(result.messages)
+  if (!result.success) {
+    show.fatal(`Unable to unset ${key}`, { error: result.error })
+  }
+  show.action(`✔️ Removed ${key}`)
+  show.result({ key, removed: true })
+}
+
+/**
+ * Compare .env with the shipped .env.example and the .env.example of the enabled services
+ *
+ * Values are compared without expanding ${VAR} references.
+ * Keys from the .env.example of disabled services are not reported as extra.
+ */
+export async function envDiff(
+  config: Config,
+  { reveal = false }: { reveal?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  const env = (await readEnvFile(config)).toObject()
+
+  const exampleFiles = [
+    { source: '.env.example', file: path.join(config.installDir, '.env.example'), enabled: true },
+    ...config.getAllServices().toArray().map((service) => ({
+      source: `${service.service} .env.example`,
+      file: service.envExampleFile,
+      enabled: service.isEnabled(),
+    })),
+  ]
+  const example = new Map<string, { value: string; source: string }>()
+  const disabledKeys = new Set<string>() // Keys owned by disabled services
+  for (const { source, file, enabled } of exampleFiles) {
+    const result = await readTextFile(file)
+    if (!result.success) {
+      if (!(result.error instanceof Deno.errors.NotFound)) {
+        show.warn(`Unable to read ${file}`, { error: result.error })
+      }
+      continue
+    }
+    Object.entries(EnvDocument.parse(result.data || '').toObject()).forEach(([key, value]) => {
+      if (!enabled) {
+        disabledKeys.add(key)
+      } else if (!example.has(key)) {
+        example.set(key, { value, source })
+      }
+    })
+  }
+
+  const diffs: IEnvDiff[] = []
+  example.forEach(({ value, source }, key) => {
+    if (!(key in env)) {
+      diffs.push({ key, status: 'missing', value: null, example: value, source })
+    } else if (env[key] !== value) {
+      diffs.push({ key, status: 'changed', value: env[key], example: value, source })
+    }
+  })
+  for (const [key, value] of Object.entries(env)) {
+    if (example.has(key) || disabledKeys.has(key)) continue
+    diffs.push({ key, status: 'extra', value, example: null, source: null })
+  }
+  diffs.forEach((diff) => {
+    const secret = config.isSecretKey(diff.key)
+    diff.value = displayValue(diff.value, secret, reveal)
+    diff.example = displayValue(diff.example, secret, reveal)
+  })
+
+  show.result({ reveal, diffs })
+  if (!diffs.length) {
+    show.info('✔️ .env matches .env.example')
+    return
+  }
+
+  const statusColors = { missing: colors.red, extra: colors.blue, changed: colors.yellow }
+  const rows: RowType[] = diffs.map((diff) => [
+    statusColors[diff.status](diff.status),
+    diff.key,
+    diff.value ?? '',
+    diff.example ?? '',
+    colors.gray(diff.source ?? ''),
+  ])
+  show.table(['Status', 'Env Var', '.env', '.env.example', 'Source'], rows)
+  const missing = diffs.filter((diff) => diff.status === 'missing').length
+  if (missing) {
+    show.info(`Run ${colors.yellow('llmn update')} to add the missing env vars to .env`)
+  }
+}
+
+/**
+ * Get the strings in a parsed yaml value, recursively
+ */
+function getYamlStrings(value: unknown): string[] {
+  if (typeof value === 'string') {
+    return [value]
+  }
+  if (value && typeof value === 'object') {
+    return Object.values(value).flatMap(getYamlStrings)
+  }
+  return []
+}
+
+/**
+ * Check the ${VAR} references in the exposes credentials and compose file of the enabled
+ * services are set, exits with 1 if any are unset
+ */
+export async function envValidate(config: Config): Promise<void> {
+  const show = config.relayer.show
+  // Compose files are expanded with the process env and the docker env vars
+  const env = { ...Deno.env.toObject(), ...(await dockerEnv(config)), ...config.env }
+
+  const unset: IEnvReference[] = []
+  for (const [_, service] of config.getEnabledServices()) {
+    const references = new Map<string, string[]>()
+
+    const exposes = searchObjectPaths<ExposeHost>(service.config.exposes, '*.*')
+    references.set(
+      'llemonstack.yaml credentials',
+      exposes.flatMap((item) =>
+        typeof item.data === 'string' ? [] : Object.values(item.data.credentials || {}).map(String)
+      ),
+    )
+
+    // Malformed compose files are skipped so the other services are still checked
+    const composeFile = path.relative(Deno.cwd(), service.composeFile)
+    const composeResult = await readYaml<unknown>(service.composeFile)
+    if (!composeResult.success) {
+      show.warn(`Unable to read ${composeFile}`, { error: composeResult.error })
+    } else {
+      references.set(composeFile, getYamlStrings(composeResult.data))
+    }
+
+    references.forEach((values, file) => {
+      const keys = new Set(values.flatMap((value) => getUnsetEnvVars(value, env)))
+      keys.forEach((key) => unset.push({ service: service.service, key, file }))
+    })
+  }
+
+  show.result({ valid: unset.length === 0, unset })
+  if (!unset.length) {
+    show.info('✔️ All env vars used by the enabled services are set')
+    return
+  }
+
+  const rows: RowType[] = unset.map((ref) => [ref.service, colors.yellow(ref.key), ref.file])
+  show.table(['Service', 'Env Var', 'Referenced In'], rows, { maxColumnWidth: 0 })
+  show.fatal(`Found ${unset.length} unset env var(s), set them with llmn env set <key> <value>`)
+}
@@ -0,0 +1,99 @@
+/**
+ * Run a command or open a shell in a service container
+ */
+
+import { CommandError } from '@/lib/command.ts'
+import { dockerExec } from '@/lib/docker.ts'
+import { ServiceType } from '@/types'
+import { Config } from '../src/core/config/config.ts'
+
+// Use bash when the container has it, otherwise fall back to sh
+const DEFAULT_SHELL = ['sh', '-c', 'command -v bash >/dev/null 2>&1 && exec bash || exec sh']
+
+/**
+ * Resolve a service name, provides key, or container name to a single container
+ *
+ * Services with multiple containers use the first container in provides,
+ * e.g. `firecrawl` resolves to `firecrawl-api`.
+ *
+ * @param config - The config instance
+ * @param name - The service name, provides key, e.g. postgres, or container name
+ * @returns The service and container name, exits if not found
+ */
+function getContainer(config: Config, name: string): [ServiceType, string] {
+  const show = config.relayer.show
+  const serviceContainers = config.getServiceContainers(name)
+  if (!serviceContainers) {
+    show.fatal(
+      `Unknown service: ${name}\n` +
+        `Available services: ${config.getAllServices().map((s) => s.service).join(', ')}`,
+    )
+  }
+  const [service, containers] = serviceContainers!
+
+  if (containers.length === 0) {
+    show.fatal(`${service.name} is not running in a container with the current profiles`)
+  }
+
+  if (!service.isEnabled()) {
+    show.warn(`${service.name} is not enabled`)
+  }
+
+  if (containers.length > 1) {
+    show.info(
+      `Using ${containers[0]} container, also available: ${containers.slice(1).join(', ')}`,
+    )
+  }
+
+  return [service, containers[0]]
+}
+
+/**
+ * Run a command in a service container
+ *
+ * stdin and the terminal are passed through to the command.
+ * Exits with the exit code of the command.
+ *
+ * @param config - The config instance
+ * @param name - The service name, provides key, or container name
+ * @param cmd - The command and args to run, opens a shell if empty
+ * @param user - The user to run the command as
+ */
+export async function exec(
+  config: Config,
+  name: string,
+  { cmd = [], user }: { cmd?: string[]; user?: string } = {},
+): Promise<void> {
+  const [service, container] = getContainer(config, name)
+  const [command, ...args] = cmd.length > 0 ? cmd : DEFAULT_SHELL
+
+  try {
+    await dockerExec(config.projectName, container, command, {
+      composeFile: service.composeFile,
+      profiles: service.getProfiles(),
+      args,
+      user,
+      interactive: true,
+    })
+  } catch (error) {
+    if (error instanceof CommandError) {
+      Deno.exit(error.code)
+    }
+    config.relayer.show.fatal(`Unable to exec in ${container}`, { error })
+  }
+}
+
+/**
+ * Open a shell in a service container
+ *
+ * @param config - The config instance
+ * @param name - The service name, provides key, or container name
+ * @param root - Open the shell as the root user
+ */
+export async function shell(
+  config: Config,
+  name: string,
+  { root = false }: { root?: boolean } = {},
+): Promise<void> {
+  await exec(config, name, { user: root ? 'root' : undefined })
+}
@@ -4,12 +4,17 @@
 
 import { Config } from '@/core/config/config.ts'
 import { fs, path } from '@/lib/fs.ts'
+import { offsetHostUrl } from '@/lib/ports.ts'
 import { FlowiseService } from '@/services/flowise/service.ts'
 
 const FLOWISE_BASE_URL = 'http://localhost:3001'
 const FLOWISE_IMPORT_DIR_BASE = 'flowise'
 const ARCHIVE_BASE_DIR_BASE = `.imported`
 
+function getFlowiseBaseUrl(config: Config): string {
+  return offsetHostUrl(FLOWISE_BASE_URL, config.basePort, config.env, 'flowise')
+}
+
 async function resetFlowiseImportFolder(config: Config, importDir: string): Promise<void> {
   const show = config.relayer.show
   show.info(`Clearing import folder: ${importDir}`)
@@ -94,7 +99,7 @@ async function importFolder(
           flowData: fileContent, // flowData is a stringified JSON object
           type,
         }
-        const response = await fetch(`${FLOWISE_BASE_URL}/api/v1/chatflows`, {
+        const response = await fetch(`${getFlowiseBaseUrl(config)}/api/v1/chatflows`, {
           method: 'POST',
           headers: {
             'Content-Type': 'application/json',
@@ -149,14 +154,16 @@ async function importToFlowise(
     show.info('Checking if Flowise is running...')
     try {
       // TODO: move to flowise service
-      const response = await fetch(`${FLOWISE_BASE_URL}/api/v1/ping`)
+      const response = await fetch(`${getFlowiseBaseUrl(config)}/api/v1/ping`)
       if (!response.ok && (await response.text()) !== 'pong') {
         throw new Error(`Failed to ping Flowise API: ${response.status} ${response.statusText}`)
       }
       show.info('Flowise is running and appears ready for import')
     } catch (_error) {
-      show.error('Error connecting to Flowise API', { error: FLOWISE_BASE_URL })
-      show.info(`Please make sure Flowise is running and accessible at ${FLOWISE_BASE_URL}`)
+      show.error('Error connecting to Flowise API', { error: getFlowiseBaseUrl(config) })
+      show.info(
+        `Please make sure Flowise is running and accessible at ${getFlowiseBaseUrl(config)}`,
+      )
       show.info('Import cancelled')
       return
     }
@@ -164,9 +171,9 @@ async function importToFlowise(
     const apiResult = await flowise.getApiKey()
     if (!apiResult.success || !apiResult.data) {
       show.logMessages(apiResult.messages)
-      Deno.exit(1)
+      show.fatal('Unable to get the Flowise API key')
     }
-    const { apiKey } = apiResult.data
+    const { apiKey } = apiResult.data!
 
     await importFolder(config, 'CHATFLOW', { apiKey })
     await importFolder(config, 'MULTIAGENT', { apiKey })
@@ -179,8 +186,7 @@ async function importToFlowise(
       await archiveFlowiseImportFolder(config)
     }
   } catch (error) {
-    show.error('Error during import', { error })
-    Deno.exit(1)
+    show.fatal('Error during import', { error })
   }
 }
 
@@ -194,7 +200,7 @@ export async function runImport(
   const prepareEnvResult = await config.prepareEnv()
   if (!prepareEnvResult.success) {
     config.relayer.show.logMessages(prepareEnvResult.messages)
-    Deno.exit(1)
+    config.relayer.show.fatal('Failed to prepare the environment')
   }
   await importToFlowise(config, { skipPrompt, archiveAfterImport: archive })
 }
@@ -25,6 +25,16 @@ export async function checkHealth(config: Config) {
         `${container.Name}: (${container.Health || 'No health check'}) ${container.Status}`,
       )
     }
+    show.result({
+      project: config.projectName,
+      containers: containers.map(({ Name, Service, State, Health, Status }) => ({
+        name: Name,
+        service: Service,
+        state: State,
+        health: Health || null,
+        status: Status,
+      })),
+    })
   } catch (error) {
     show.error('Error checking container health', { error })
     throw error
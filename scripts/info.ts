@@ -3,8 +3,9 @@
  */
 import { Config } from '@/core/config/config.ts'
 import { path } from '@/lib/fs.ts'
+import { print } from '@/relayer/ui/output.ts'
 import { colors } from '@cliffy/ansi/colors'
-import { showServicesInfo } from './start.ts'
+import { getServicesEndpoints, showServicesInfo } from './start.ts'
 
 export async function info({
   config,
@@ -60,10 +61,60 @@ export async function info({
     show.info(`- ${service.name}`)
   })
 
+  // Additional info from enabled services, e.g. last backup status
+  const servicesInfo: Record<string, Record<string, unknown>> = {}
+  for (const service of config.getEnabledServices().values()) {
+    const serviceInfo = await service.getInfo()
+    if (serviceInfo) {
+      servicesInfo[service.service] = serviceInfo
+    }
+  }
+  if (Object.keys(servicesInfo).length > 0) {
+    show.header('Services Info')
+    Object.entries(servicesInfo).forEach(([serviceName, serviceInfo]) => {
+      show.info(`${colors.green(config.getServiceByName(serviceName)?.name || serviceName)}`)
+      Object.entries(serviceInfo).filter(([_, value]) => value !== null).forEach(
+        ([key, value]) => {
+          const color = value === 'failed' ? colors.red : colors.yellow
+          show.info(`- ${key}: ${color(String(value))}`)
+        },
+      )
+    })
+  }
+
   // Running Services
   const runningServices = await config.getAllServices().filterAsync(async (s) => {
     return await s.isRunning()
   })
+  show.result({
+    project: {
+      name: config.projectName,
+      configFile: config.configFile,
+      servicesDirs: dirs,
+    },
+    llemonstack: {
+      version: config.version,
+      installDir: config.installDir,
+      commitsBehind: remoteCommits.success ? remoteCommits.data : null,
+    },
+    services: {
+      enabled: Object.fromEntries(
+        Array.from(serviceGroups.entries()).map(([groupName, groupServices]) => [
+          groupName,
+          groupServices.getEnabled().map((service) => ({
+            service: service.service,
+            name: service.name,
+            profiles: service.getProfiles(),
+          })),
+        ]),
+      ),
+      disabled: config.getAllServices().getDisabled().map((service) => service.service),
+      running: runningServices.map((service) => service.service),
+      info: servicesInfo,
+    },
+    endpoints: getServicesEndpoints(runningServices, 'host.*', { hideCredentials }),
+  })
+
   if (runningServices.size > 0) {
     show.header('Running Services')
     showServicesInfo(runningServices, 'host.*', {
@@ -73,5 +124,5 @@ export async function info({
     })
   }
 
-  console.log('')
+  print('')
 }
@@ -1,8 +1,10 @@
 /**
  * Setup required env variables
  */
+import { ProjectRegistry } from '@/core/config/lib/registry.ts'
 import { runDockerCommand } from '@/lib/docker.ts'
 import { fileExists, path } from '@/lib/fs.ts'
+import { getAvailableBasePort, offsetHostPort } from '@/lib/ports.ts'
 import { Input, Secret, Select } from '@cliffy/prompt'
 import { Config } from '../src/core/config/config.ts'
 import { reset } from './reset.ts'
@@ -190,6 +192,13 @@ export async function init(
       }
     }
 
+    // Add the env vars of the enabled services from their .env.example
+    const mergeResult = await config.mergeServicesEnv()
+    show.logMessages(mergeResult.messages)
+    if (!mergeResult.success) {
+      show.fatal('Failed to add service env vars to .env', { error: mergeResult.error })
+    }
+
     show.info('.env file is ready to configure')
 
     let uniqueName = false
@@ -205,7 +214,15 @@ export async function init(
 
       show.info(`Checking if project name is unique: ${projectName}`)
 
-      // TODO: move existing project check to config
+      // Names used by other projects or with existing Docker volumes are not allowed,
+      // the project would share the volumes (data) of the other project
+      const nameResult = await config.checkProjectName(projectName)
+      if (!nameResult.success) {
+        show.logMessages(nameResult.messages)
+        show.warn('Please choose a different project name')
+        continue
+      }
+
       uniqueName = !(await isExistingProject(projectName))
 
       if (!uniqueName) {
@@ -223,9 +240,33 @@ export async function init(
       }
     }
 
+    // Assign a base port to offset all host ports so multiple projects can run at the same time
+    let basePort = 0
+    const registry = ProjectRegistry.getInstance()
+    await registry.load()
+    const basePortResult = await getAvailableBasePort(projectName, {
+      reserved: registry.projects.filter((p) => p.path !== Deno.cwd()).map((p) => p.ports.base),
+    })
+    if (!basePortResult.success) {
+      show.logMessages(basePortResult.messages)
+      show.warn('Unable to check the ports used by other projects, using the default ports')
+    } else if (basePortResult.data === null) {
+      show.warn('All base ports are used by other projects, using the default ports')
+    } else {
+      basePort = basePortResult.data
+    }
+    if (basePort > 0) {
+      show.info(
+        `Other projects are using the default ports, host ports will be offset by ${basePort}\n` +
+          `e.g. n8n will be available at http://localhost:${offsetHostPort(5678, basePort)}`,
+      )
+    }
+
     // Set project name & initialize
     // Initialize will save the config file
-    await config.setProjectName(projectName, { save: false })
+    // Name was checked above, force skips checking it again
+    await config.setProjectName(projectName, { save: false, force: true })
+    await config.setBasePort(basePort, { save: false })
     const result = await config.initializeProject()
 
     if (!result.success) {
@@ -10,6 +10,7 @@
  * .env file for OpenAI compatible models to be loaded.
  */
 import { Config } from '@/core/config/config.ts'
+import { offsetHostUrl } from '@/lib/ports.ts'
 import { InterfaceRelayer } from '@/relayer/ui/interface.ts'
 
 type LiteLLMModelList = Array<{
@@ -193,7 +194,8 @@ export async function loadModels(config: Config) {
   // Load environment variables from .env file
   const env = config.env
 
-  const LITELLM_API_BASE = env.LITELLM_API_BASE || 'http://localhost:3004'
+  const LITELLM_API_BASE = env.LITELLM_API_BASE ||
+    offsetHostUrl('http://localhost:3004', config.basePort, config.env, 'litellm')
   const LITELLM_API_KEY = env.LITELLM_API_KEY || env.LITELLM_MASTER_KEY
   const LOCAL_LLM_OPENAI_API_BASE_URL = env.LOCAL_LLM_OPENAI_API_BASE_URL
   const LOCAL_LLM_OPENAI_HOST_PORT = env.LOCAL_LLM_OPENAI_HOST_PORT || '1234' // default to LM Studio port
@@ -208,7 +210,10 @@ export async function loadModels(config: Config) {
   await addOllamaModelsToLiteLLM({
     litellm_api_base: LITELLM_API_BASE,
     litellm_api_key: LITELLM_API_KEY,
-    models_url: 'http://localhost:11434/api/tags', // Tags endpoint gives more data than /v1/models
+    // Tags endpoint gives more data than /v1/models
+    models_url: config.getServiceByName('ollama')?.getProfiles().includes('ollama-host')
+      ? 'http://localhost:11434/api/tags' // Host Ollama is not offset
+      : offsetHostUrl('http://localhost:11434/api/tags', config.basePort, config.env, 'ollama'),
     api_base: 'http://host.docker.internal:11434',
     models: liteLLMModels,
     show,
@@ -0,0 +1,165 @@
+/**
+ * Lock the images used by the enabled services to their digests
+ *
+ * Writes .llemonstack/images.lock. Start and update use the locked digests so everyone on
+ * a project runs the same images. Images already in the lock are kept until upgraded.
+ */
+import { Config } from '@/core/config/config.ts'
+import { getComposeImages } from '@/core/services/utils/mod.ts'
+import { getDockerImageDigests, pullDockerImage } from '@/lib/docker.ts'
+import { createImagesLock, getRepoDigest, writeImagesLock } from '@/lib/images-lock.ts'
+import { path } from '@/lib/fs.ts'
+import { ServiceType } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { RowType } from '@cliffy/table'
+
+type ImageLockStatus = 'unchanged' | 'added' | 'upgraded' | 'failed'
+
+/**
+ * Get the images of the services, keyed by image reference
+ *
+ * Images built from a Dockerfile are skipped, their base images are not locked.
+ */
+async function getServicesImages(
+  config: Config,
+  services: ServiceType[],
+): Promise<Map<string, string[]>> {
+  const show = config.relayer.show
+  const images = new Map<string, string[]>()
+  for (const service of services) {
+    const result = await getComposeImages(service, { projectName: config.projectName })
+    if (!result.success || !result.data) {
+      show.logMessages(result.messages)
+      show.fatal(`Unable to get the images for ${service.name}`)
+    }
+    for (const { image, build } of result.data!) {
+      if (build || image.includes('@')) continue
+      images.set(image, [...new Set([...(images.get(image) || []), service.service])])
+    }
+  }
+  return images
+}
+
+/**
+ * Get the digest of an image
+ *
+ * Uses the local image if it was pulled from a registry, otherwise pulls the image.
+ *
+ * @param pull - Always pull the image to get the latest digest for the tag
+ */
+async function resolveImageDigest(
+  image: string,
+  { pull = false }: { pull?: boolean } = {},
+): Promise<string | null> {
+  if (!pull) {
+    const digests = await getDockerImageDigests(image)
+    const digest = digests.success && getRepoDigest(image, digests.data || [])
+    if (digest) {
+      return digest
+    }
+  }
+  const pullResult = await pullDockerImage(image)
+  if (!pullResult.success) {
+    return null
+  }
+  const digests = await getDockerImageDigests(image)
+  return getRepoDigest(image, digests.data || [])
+}
+
+/**
+ * Create or update the images lock
+ *
+ * @param upgrade - Upgrade all locked images, or only the images of a service
+ */
+export async function lock(
+  config: Config,
+  { upgrade = false }: { upgrade?: boolean | string } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  if (config.offline) {
+    show.fatal('Project is offline, images can only be locked on a machine with internet access')
+  }
+
+  const upgradeService = typeof upgrade === 'string' ? config.getServiceByName(upgrade) : null
+  if (typeof upgrade === 'string' && !upgradeService) {
+    show.fatal(`Unknown service: ${upgrade}`)
+  }
+
+  // Repos need to be cloned to resolve compose files from repos
+  const prepareResult = await config.prepareEnv({ silent: true })
+  if (!prepareResult.success) {
+    show.logMessages(prepareResult.messages)
+    show.fatal('Failed to prepare the environment')
+  }
+
+  const services = upgradeService ? [upgradeService] : config.getEnabledServices().toArray()
+  show.action(
+    upgrade
+      ? `Upgrading images for ${upgradeService?.name || 'all enabled services'}...`
+      : `Locking images for ${config.projectName}...`,
+  )
+
+  // Images of services that aren't enabled are kept, teammates may enable other services
+  const imagesLock = config.imagesLock
+    ? { ...config.imagesLock, images: { ...config.imagesLock.images } }
+    : createImagesLock()
+  const images = await getServicesImages(config, services)
+
+  const rows: RowType[] = []
+  const statuses: Array<{ image: string; digest: string | null; status: ImageLockStatus }> = []
+  for (const [image, imageServices] of images) {
+    const locked = imagesLock.images[image]
+    let status: ImageLockStatus = locked ? 'unchanged' : 'added'
+    let digest = locked?.digest || null
+
+    if (!locked || upgrade) {
+      show.info(`Resolving ${image}...`)
+      const resolved = await resolveImageDigest(image, { pull: !!upgrade })
+      if (!resolved) {
+        status = 'failed'
+      } else if (locked && resolved !== locked.digest) {
+        status = 'upgraded'
+      }
+      digest = resolved || digest
+    }
+
+    if (digest) {
+      imagesLock.images[image] = {
+        digest,
+        services: [...new Set([...(locked?.services || []), ...imageServices])],
+      }
+    }
+    statuses.push({ image, digest, status })
+    rows.push([
+      imageServices.join(', '),
+      image,
+      digest ? colors.gray(digest.replace('sha256:', '').slice(0, 12)) : '',
+      status === 'failed'
+        ? colors.red(status)
+        : status === 'unchanged'
+        ? colors.gray(status)
+        : colors.green(status),
+    ])
+  }
+
+  const writeResult = await writeImagesLock(config.imagesLockFile, imagesLock)
+  if (!writeResult.success) {
+    show.fatal(`Failed to write ${config.imagesLockFile}`, { error: writeResult.error })
+  }
+
+  show.result({ project: config.projectName, file: config.imagesLockFile, images: statuses })
+  show.table(['Service', 'Image', 'Digest', 'Status'], rows, { maxColumnWidth: 0 })
+
+  const failed = statuses.filter(({ status }) => status === 'failed')
+  if (failed.length) {
+    show.warn(
+      `Unable to resolve digests for ${failed.length} images, they are not pinned.\n` +
+        'Images that were not pulled from a registry have no digest.',
+    )
+  }
+  show.info(`\n✔️ Images locked in ${path.relative(Deno.cwd(), config.imagesLockFile)}`)
+  show.userAction('Commit the lockfile to share it, start and update use the locked digests')
+  if (statuses.some(({ status }) => status === 'upgraded')) {
+    show.userAction('Run `llmn update` to use the upgraded images')
+  }
+}
@@ -0,0 +1,194 @@
+/**
+ * Show and follow the logs of service containers in a single stream
+ */
+
+import { dockerComposeLogs } from '@/lib/docker.ts'
+import { InterfaceRelayer } from '@/relayer/ui/interface.ts'
+import { ServiceType } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { Config } from '../src/core/config/config.ts'
+
+type ContainerLogLevel = 'debug' | 'info' | 'warning' | 'error'
+
+interface LogTarget {
+  service: ServiceType
+  container: string
+  label: string
+}
+
+// Colors to rotate through for each container prefix
+const PREFIX_COLORS = [
+  colors.cyan,
+  colors.magenta,
+  colors.blue,
+  colors.yellow,
+  colors.green,
+  colors.brightCyan,
+  colors.brightMagenta,
+  colors.brightBlue,
+]
+
+// Matches the RFC3339 timestamp docker adds to each line with --timestamps
+const TIMESTAMP_REGEX = /^(\d{4}-\d{2}-\d{2}T\S+)\s(.*)$/
+
+/**
+ * Detect the log level of a container log line
+ *
+ * Containers use many different log formats, so this is a best effort match on common
+ * level keywords. Lines without a level keyword are treated as info.
+ *
+ * @param line - The log line
+ * @returns The detected log level
+ */
+export function detectLogLevel(line: string): ContainerLogLevel {
+  if (/\b(fatal|panic|crit(ical)?|err(or)?|exception)\b/i.test(line)) {
+    return 'error'
+  }
+  if (/\bwarn(ing)?\b/i.test(line)) {
+    return 'warning'
+  }
+  if (/\b(debug|trace)\b/i.test(line)) {
+    return 'debug'
+  }
+  return 'info'
+}
+
+/**
+ * Resolve service names to the containers to get logs for
+ *
+ * Names can be service names, keys a service provides, e.g. `postgres`, or container names.
+ *
+ * @param config - The config instance
+ * @param names - The names to resolve, all enabled services if empty
+ * @returns The containers to get logs for and any names that were not found
+ */
+function getLogTargets(
+  config: Config,
+  names: string[],
+): { targets: LogTarget[]; missing: string[] } {
+  const targets: LogTarget[] = []
+  const missing: string[] = []
+
+  const addService = (service: ServiceType, containers = service.containerNames) => {
+    containers.forEach((container) => {
+      if (!targets.some((t) => t.service.id === service.id && t.container === container)) {
+        targets.push({ service, container, label: `${service.service}/${container}` })
+      }
+    })
+  }
+
+  if (names.length === 0) {
+    config.getEnabledServices().forEach((service) => addService(service))
+    return { targets, missing }
+  }
+
+  names.forEach((name) => {
+    const [service, containers] = config.getServiceContainers(name) || []
+    if (service && containers) {
+      addService(service, containers)
+    } else {
+      missing.push(name)
+    }
+  })
+
+  return { targets, missing }
+}
+
+export async function logs(
+  config: Config,
+  {
+    services: names = [],
+    follow = true,
+    since,
+    tail,
+    grep,
+    timestamps = false,
+  }: {
+    services?: string[]
+    follow?: boolean
+    since?: string
+    tail?: string
+    grep?: string
+    timestamps?: boolean
+  } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  // Output each line as a JSON object when the output format is json or yaml
+  const json = InterfaceRelayer.isStructuredOutput()
+
+  const { targets, missing } = getLogTargets(config, names)
+
+  if (missing.length > 0) {
+    show.fatal(
+      `Unknown services: ${missing.join(', ')}\n` +
+        `Available services: ${config.getAllServices().map((s) => s.service).join(', ')}`,
+    )
+    return
+  }
+
+  if (targets.length === 0) {
+    show.warn('No containers found to show logs for')
+    return
+  }
+
+  targets
+    .map((target) => target.service)
+    .filter((service, index, all) => all.indexOf(service) === index && !service.isEnabled())
+    .forEach((service) => show.warn(`${service.name} is not enabled, logs may be empty`))
+
+  let pattern: RegExp | undefined
+  if (grep) {
+    try {
+      pattern = new RegExp(grep, 'i')
+    } catch (error) {
+      show.fatal(`Invalid grep pattern: ${grep}`, { error })
+      return
+    }
+  }
+
+  const width = Math.max(...targets.map((t) => t.label.length))
+
+  const streams = targets.map((target, index) => {
+    const color = PREFIX_COLORS[index % PREFIX_COLORS.length]
+    const prefix = color(`${target.label.padEnd(width)} |`)
+
+    const onLine = (line: string) => {
+      const match = line.match(TIMESTAMP_REGEX)
+      const timestamp = match ? match[1] : null
+      const text = match ? match[2] : line
+
+      if (pattern && !pattern.test(text)) {
+        return
+      }
+
+      const level = detectLogLevel(text)
+      const data = {
+        service: target.service.service,
+        container: target.container,
+        timestamp,
+      }
+
+      const message = json
+        ? JSON.stringify({ ...data, level, message: text })
+        : [prefix, timestamps && timestamp ? colors.gray(timestamp) : '', text]
+          .filter(Boolean)
+          .join(' ')
+
+      show.containerLog(level, message, data)
+    }
+
+    return dockerComposeLogs(config.projectName, target.container, {
+      composeFile: target.service.composeFile,
+      profiles: target.service.getProfiles(),
+      follow,
+      since,
+      tail,
+      timestamps: true,
+      onLine,
+    }).catch((error) => {
+      show.error(`Unable to get logs for ${target.label}`, { error })
+    })
+  })
+
+  await Promise.all(streams)
+}
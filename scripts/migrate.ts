@@ -0,0 +1,56 @@
+/**
+ * Migrate the project config.json and .env to the current LLemonStack version
+ *
+ * See src/core/config/lib/migrate.ts for the migration steps.
+ */
+
+import { migrateProject } from '@/core/config/lib/migrate.ts'
+import { path } from '@/lib/fs.ts'
+import { InterfaceRelayerInstance } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+
+/**
+ * Run the pending migrations for a project config
+ *
+ * @param configFile - The project config file
+ * @param check - Show the pending migrations without changing any files, exits with 1 if the
+ *   config needs to be migrated
+ */
+export async function migrate(
+  show: InterfaceRelayerInstance,
+  configFile: string,
+  { check = false }: { check?: boolean } = {},
+): Promise<void> {
+  const file = path.resolve(Deno.cwd(), configFile)
+  const result = await migrateProject(file, { check })
+  const data = result.data
+
+  // Show the completed steps before any error
+  for (const step of data?.steps || []) {
+    show.header(`${step.version}: ${step.description}`)
+    if (!step.changes.length) {
+      show.info(colors.gray('No changes'))
+    }
+    step.changes.forEach((change) => show.info(`- ${change}`))
+    if (step.backupDir) {
+      show.info(colors.gray(`Backup: ${path.relative(Deno.cwd(), step.backupDir)}`))
+    }
+  }
+
+  if (!result.success || !data) {
+    show.logMessages(result.messages)
+    show.fatal('Failed to migrate config', { error: result.error })
+  }
+
+  show.result({ file, check, ...data })
+
+  if (!data!.steps.length) {
+    show.info(`✔️ Config is up to date: ${data!.to}`)
+    return
+  }
+  if (check) {
+    show.userAction(`\nRun \`llmn migrate\` to migrate the config to ${data!.to}`)
+    Deno.exit(1)
+  }
+  show.info(`\n✔️ Config migrated from ${data!.from} to ${data!.to}`)
+}
@@ -167,8 +167,7 @@ async function importToN8n(
       await archiveN8nImportFolder(config)
     }
   } catch (error) {
-    show.error('Error during import', { error })
-    Deno.exit(1)
+    show.fatal('Error during import', { error })
   }
 }
 
@@ -182,7 +181,7 @@ export async function runImport(
   const prepareEnvResult = await config.prepareEnv()
   if (!prepareEnvResult.success) {
     config.relayer.show.logMessages(prepareEnvResult.messages)
-    Deno.exit(1)
+    config.relayer.show.fatal('Failed to prepare the environment')
   }
   await importToN8n(config, { skipPrompt, archiveAfterImport: archive })
 }
@@ -0,0 +1,272 @@
+/**
+ * Plan the effect of start, stop, update and config without running them
+ *
+ * Used by the --dry-run option of each command.
+ */
+
+import { Config } from '@/core/config/config.ts'
+import { ServicesMap } from '@/core/services/mod.ts'
+import { inspectDockerNetworks } from '@/lib/docker.ts'
+import { path } from '@/lib/fs.ts'
+import type { IServiceHostPort, IServicePlan, ServicePlanAction, ServiceType } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { RowType } from '@cliffy/table'
+
+export type PlanCommand = 'start' | 'stop' | 'update' | 'config'
+
+export interface IStackPlan {
+  project: string
+  command: PlanCommand
+  services: IServicePlan[]
+  order: string[][] // Services started together in each wave
+  network: { name: string; action: 'create' | 'remove' | 'none' }
+  ports: IServiceHostPort[] // Host ports already in use
+  notes: string[]
+}
+
+// Enabled state and profiles of each service, used to compare config changes
+export type ServicesSnapshot = Map<string, { enabled: boolean; profiles: string[] }>
+
+const ACTION_COLORS: Record<ServicePlanAction, (str: string) => string> = {
+  start: colors.green,
+  stop: colors.yellow,
+  update: colors.cyan,
+  enable: colors.green,
+  disable: colors.red,
+  none: colors.gray,
+}
+
+function relative(file: string): string {
+  return path.relative(Deno.cwd(), file)
+}
+
+async function getServicePlans(
+  services: ServicesMap,
+  action: ServicePlanAction,
+): Promise<IServicePlan[]> {
+  const plans = await Promise.all(services.map((service) => service.getPlan(action)))
+  return plans.sort((a, b) => a.name.localeCompare(b.name))
+}
+
+/**
+ * Check if the project network exists, docker may not be running during a dry run
+ */
+async function networkExists(config: Config): Promise<boolean> {
+  const networksResult = await inspectDockerNetworks(config.dockerNetworkName)
+  return !!networksResult.data?.length
+}
+
+/**
+ * Get the enabled state and profiles of all services
+ */
+export function getServicesSnapshot(config: Config): ServicesSnapshot {
+  return new Map(
+    config.getAllServices().map((service) => [
+      service.id,
+      { enabled: service.isEnabled(), profiles: [...service.getProfiles()] },
+    ]),
+  )
+}
+
+/**
+ * Plan `llmn start`
+ *
+ * @param service - Start a single service instead of all enabled services
+ */
+export async function planStart(
+  config: Config,
+  { service }: { service?: ServiceType | null } = {},
+): Promise<IStackPlan> {
+  const services = service ? new ServicesMap([service]) : config.getEnabledServices()
+  const order = service ? [] : (config.getStartOrder(services).data || [])
+  const conflicts = await config.getPortConflicts(services)
+  return {
+    project: config.projectName,
+    command: 'start',
+    services: await getServicePlans(services, 'start'),
+    order: order.map((wave) => wave.map((s) => s.service)),
+    network: {
+      name: config.dockerNetworkName,
+      action: (await networkExists(config)) ? 'none' : 'create',
+    },
+    ports: conflicts.data || [],
+    notes: [],
+  }
+}
+
+/**
+ * Plan `llmn stop`
+ *
+ * @param all - Stop all services, including disabled services
+ * @param service - Stop a single service
+ */
+export async function planStop(
+  config: Config,
+  { all = false, service }: { all?: boolean; service?: ServiceType | null } = {},
+): Promise<IStackPlan> {
+  const stopAll = all && !service
+  const services = service
+    ? new ServicesMap([service])
+    : stopAll
+    ? config.getAllServices()
+    : config.getEnabledServices()
+  return {
+    project: config.projectName,
+    command: 'stop',
+    services: await getServicePlans(services, 'stop'),
+    order: [],
+    network: {
+      name: config.dockerNetworkName,
+      action: stopAll && (await networkExists(config)) ? 'remove' : 'none',
+    },
+    ports: [],
+    notes: [],
+  }
+}
+
+/**
+ * Plan `llmn update`
+ *
+ * @param skipStop - Don't stop the services before updating
+ * @param service - Update a single service
+ */
+export async function planUpdate(
+  config: Config,
+  { skipStop = false, service }: { skipStop?: boolean; service?: ServiceType | null } = {},
+): Promise<IStackPlan> {
+  const services = service ? new ServicesMap([service]) : config.getEnabledServices()
+  const locked = (await config.loadImagesLock()).data
+  return {
+    project: config.projectName,
+    command: 'update',
+    services: await getServicePlans(services, 'update'),
+    order: [],
+    network: {
+      name: config.dockerNetworkName,
+      action: !skipStop && (await networkExists(config)) ? 'remove' : 'none',
+    },
+    ports: [],
+    notes: [
+      ...(skipStop ? [] : ['All services are stopped before updating']),
+      'Images are pulled and custom images are rebuilt without the build cache',
+      ...(locked ? ['Images are pinned to the digests in images.lock'] : []),
+    ],
+  }
+}
+
+/**
+ * Plan the changes made by `llmn config`
+ *
+ * Resolves the auto enabled services so services enabled or disabled by their dependents
+ * are included in the plan.
+ *
+ * @param before - Snapshot of the services before the changes, see getServicesSnapshot
+ */
+export async function planConfig(config: Config, before: ServicesSnapshot): Promise<IStackPlan> {
+  config.updateAutoEnabledServices()
+
+  const changed = config.getAllServices().filter((service) => {
+    const previous = before.get(service.id)
+    return !previous || previous.enabled !== service.isEnabled() ||
+      previous.profiles.join(',') !== service.getProfiles().join(',')
+  })
+  const plans = await Promise.all(
+    changed.map((service) => {
+      const wasEnabled = before.get(service.id)?.enabled ?? false
+      const action: ServicePlanAction = wasEnabled === service.isEnabled()
+        ? 'none'
+        : service.isEnabled()
+        ? 'enable'
+        : 'disable'
+      return service.getPlan(action)
+    }),
+  )
+
+  const enabled = config.getEnabledServices()
+  return {
+    project: config.projectName,
+    command: 'config',
+    services: plans.sort((a, b) => a.name.localeCompare(b.name)),
+    order: (config.getStartOrder(enabled).data || []).map((wave) => wave.map((s) => s.service)),
+    network: { name: config.dockerNetworkName, action: 'none' },
+    ports: [],
+    notes: plans.some((plan) => plan.enabled && plan.env.length)
+      ? ['Run `llmn init` to generate the env vars of newly enabled services']
+      : [],
+  }
+}
+
+/**
+ * Show a plan, nothing is changed
+ */
+export function showPlan(config: Config, plan: IStackPlan): void {
+  const show = config.relayer.show
+  show.result({ dry_run: true, ...plan })
+
+  show.header(`Plan for llmn ${plan.command}: ${plan.project}`)
+  if (plan.services.length === 0) {
+    show.info(plan.command === 'config' ? 'No changes to services' : 'No services to run')
+  } else {
+    const rows: RowType[] = plan.services.map((service) => [
+      service.name,
+      ACTION_COLORS[service.action](service.action) +
+      (service.auto ? colors.gray(' (auto)') : ''),
+      relative(service.composeFile),
+      service.profiles.join(', ') || colors.gray('none'),
+    ])
+    show.table(['Service', 'Action', 'Compose File', 'Profiles'], rows, { maxColumnWidth: 0 })
+  }
+
+  if (plan.order.length) {
+    show.info('\nStart order:')
+    plan.order.forEach((wave, i) => show.info(`  ${i + 1}. ${wave.join(', ')}`))
+  }
+
+  // Only show the setup steps for services that will run
+  const active = plan.services.filter((service) =>
+    ['start', 'update', 'enable'].includes(service.action) ||
+    (plan.command === 'config' && service.enabled)
+  )
+  const overrides = active.map((service) => relative(service.overrideFile))
+  if (overrides.length) {
+    show.info('\nCompose overrides generated:')
+    overrides.forEach((file) => show.info(`  ${file}`))
+  }
+
+  const env = active.flatMap((service) =>
+    service.env.map((key) =>
+      `  ${key} (${service.name})${service.secrets.includes(key) ? ' → secrets.enc' : ''}`
+    )
+  )
+  if (env.length) {
+    show.info('\nEnv vars generated by `llmn init`:')
+    env.forEach((line) => show.info(line))
+  }
+
+  const repos = active.filter((service) => service.repo?.action === 'clone')
+  if (repos.length) {
+    show.info('\nRepos cloned:')
+    repos.forEach((service) =>
+      show.info(`  ${service.repo!.url} → ${relative(service.repo!.dir)}`)
+    )
+  }
+
+  const volumes = active.flatMap((service) =>
+    service.volumes.map((volume) => `  ${volume} (${service.name})`)
+  )
+  if (volumes.length) {
+    show.info(`\nVolumes created in ${relative(config.volumesDir)}:`)
+    volumes.forEach((line) => show.info(line))
+  }
+
+  if (plan.network.action !== 'none') {
+    show.info(`\nNetwork ${plan.network.name} is ${plan.network.action}d`)
+  }
+
+  plan.ports.forEach((port) => {
+    show.warn(`${port.name} port ${port.hostPort} is already in use`)
+  })
+  plan.notes.forEach((note) => show.info(`\n${note}`))
+
+  show.info(colors.gray('\nDry run, nothing was changed'))
+}
@@ -0,0 +1,123 @@
+/**
+ * Manage the global registry of LLemonStack projects
+ */
+
+import { ProjectRegistry } from '@/core/config/lib/registry.ts'
+import { fileExists, path } from '@/lib/fs.ts'
+import { InterfaceRelayerInstance } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { RowType } from '@cliffy/table'
+
+async function loadRegistry(show: InterfaceRelayerInstance): Promise<ProjectRegistry> {
+  const registry = ProjectRegistry.getInstance()
+  const result = await registry.load()
+  if (!result.success) {
+    show.logMessages(result.messages)
+    show.fatal('Unable to load the project registry')
+  }
+  return registry
+}
+
+/**
+ * Show all registered projects
+ */
+export async function listProjects(show: InterfaceRelayerInstance): Promise<void> {
+  const registry = await loadRegistry(show)
+  const cwd = Deno.cwd()
+
+  const projects = await Promise.all(registry.projects.map(async (project) => ({
+    ...project,
+    current: project.path === registry.current?.path,
+    active: project.path === cwd, // Project in the current dir
+    missing: !(await fileExists(project.configFile)).data,
+  })))
+
+  show.result({ registry: registry.file, projects })
+
+  if (projects.length === 0) {
+    show.info('No projects registered, run `llmn init` in a project dir to create a project')
+    return
+  }
+
+  const rows: RowType[] = projects.map((project) => [
+    `${project.current ? colors.green('*') : ' '} ${
+      project.missing ? colors.gray(project.projectName) : colors.yellow(project.projectName)
+    }`,
+    project.missing ? colors.red(`${project.path} (missing)`) : project.path,
+    `${project.ports.range[0]}-${project.ports.range[1]}`,
+    project.services.length.toString(),
+    project.lastStarted ? new Date(project.lastStarted).toLocaleString() : colors.gray('never'),
+  ])
+  show.table(['Project', 'Path', 'Ports', 'Services', 'Last Started'], rows, {
+    maxColumnWidth: 0,
+  })
+  show.info(colors.gray(`\n* Current project, used when llmn is run outside of a project dir`))
+  show.info(colors.gray(`Registry: ${registry.file}`))
+}
+
+/**
+ * Set the project used when llmn is run outside of a project dir
+ *
+ * @param nameOrPath - Project name or path to the project dir
+ */
+export async function switchProject(
+  show: InterfaceRelayerInstance,
+  nameOrPath: string,
+): Promise<void> {
+  const registry = await loadRegistry(show)
+  const project = registry.get(nameOrPath) || registry.get(path.resolve(nameOrPath))
+  if (!project) {
+    show.fatal(
+      `Project not found: ${nameOrPath}\n` + 'Run `llmn projects list` to see all projects',
+    )
+    return
+  }
+  if (!(await fileExists(project.configFile)).data) {
+    show.fatal(
+      `Project config file not found: ${project.configFile}\n` +
+        'Run `llmn projects prune` to remove missing projects',
+    )
+  }
+
+  const result = await registry.setCurrent(project.path)
+  if (!result.success) {
+    show.logMessages(result.messages)
+    show.fatal('Unable to save the project registry')
+  }
+
+  show.action(`Switched to project: ${colors.yellow(project.projectName)}`)
+  show.info(`llmn commands run outside of a project dir will use ${colors.yellow(project.path)}`)
+  show.result({ current: project })
+}
+
+/**
+ * Remove projects whose config file no longer exists from the registry
+ */
+export async function pruneProjects(
+  show: InterfaceRelayerInstance,
+  { skipPrompt = false }: { skipPrompt?: boolean } = {},
+): Promise<void> {
+  const registry = await loadRegistry(show)
+
+  const missingResult = await registry.prune({ dryRun: true })
+  const missing = missingResult.data || []
+  if (missing.length === 0) {
+    show.info('No missing projects to prune')
+    show.result({ pruned: [] })
+    return
+  }
+
+  show.info('Projects with missing config files:')
+  missing.forEach((project) => show.info(`- ${colors.yellow(project.projectName)}: ${project.path}`))
+  if (!skipPrompt && !show.confirm('Remove these projects from the registry?', true)) {
+    return
+  }
+
+  const result = await registry.prune()
+  if (!result.success) {
+    show.logMessages(result.messages)
+    show.fatal('Unable to prune the project registry')
+  }
+  show.action(`Removed ${result.data?.length ?? 0} projects from the registry`)
+  show.result({ pruned: result.data })
+}
@@ -67,9 +67,10 @@ async function cleanDir(config: Config, dir: string): Promise<void> {
     const normalizedDir = path.normalize(dir)
     const normalizedCwd = path.normalize(Deno.cwd())
     if (!normalizedDir.startsWith(normalizedCwd)) {
-      show.error(`Security error: Cannot clean directory outside of project: ${dir}`)
-      show.userAction('Please delete the directory and try again.')
-      Deno.exit(1)
+      show.fatal(
+        `Security error: Cannot clean directory outside of project: ${dir}\n` +
+          'Please delete the directory and try again.',
+      )
     }
 
     await Deno.remove(dir, { recursive: true })
@@ -14,7 +14,6 @@ export async function restart(
     await stop(config, { all: true, service }) // Stop all services
     await start(config, { service, skipOutput }) // Restart services
   } catch (error) {
-    show.error('Failed to restart services', { error })
-    Deno.exit(1)
+    show.fatal('Failed to restart services', { error })
   }
 }
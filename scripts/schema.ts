@@ -11,12 +11,10 @@ async function isSupabaseStarted(config: Config) {
 export async function schema(config: Config, action: string, service: string) {
   const show = config.relayer.show
   if (action !== 'create' && action !== 'remove') {
-    show.error('First argument must be either "create" or "remove"')
-    Deno.exit(1)
+    show.fatal('First argument must be either "create" or "remove"')
   }
   if (!service) {
-    show.error('Service name is required')
-    Deno.exit(1)
+    show.fatal('Service name is required')
   }
 
   // Make sure it's a valid service
@@ -34,16 +32,18 @@ export async function schema(config: Config, action: string, service: string) {
   let supabaseService: ServiceType | null = null
 
   if (!await isSupabaseStarted(config)) {
-    if (show.confirm(`Supabase is not running. Shall we start it?`, true)) {
-      supabaseService = await startService(config, 'supabase')
-      supabaseStarted = true
+    if (!show.confirm(`Supabase is not running. Shall we start it?`, true)) {
+      show.fatal('Supabase is not running, unable to manage schema')
     }
-    // Wait a few seconds for supabase to fully initialize
+    supabaseService = await startService(config, 'supabase')
+    supabaseStarted = true
+
+    // Wait for the supabase database to pass its healthcheck
     show.action('Waiting for Supabase to initialize...')
-    await new Promise((resolve) => setTimeout(resolve, 3000))
-    if (!await isSupabaseStarted(config)) {
-      show.error('Supabase failed to start, unable to create schema')
-      Deno.exit(1)
+    const readyResult = await supabaseService.waitForCondition('service_healthy')
+    show.logMessages(readyResult.messages)
+    if (!readyResult.success) {
+      show.fatal('Supabase failed to start, unable to create schema')
     }
   }
 
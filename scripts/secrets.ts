@@ -0,0 +1,488 @@
+/**
+ * Manage the secrets generated for services and the encrypted secrets store
+ */
+import { Config } from '@/core/config/config.ts'
+import { ServicesMap } from '@/core/services/services-map.ts'
+import { tryRunCommand } from '@/lib/command.ts'
+import {
+  clearEnvValues,
+  EnvDocument,
+  formatEnvValue,
+  parseWithoutExpand,
+  updateEnv,
+} from '@/lib/env.ts'
+import { ensureDir, fileExists, path, readTextFile } from '@/lib/fs.ts'
+import { createSecretsKeyFile, maskSecret, SECRETS_PASSPHRASE_ENV } from '@/lib/secrets.ts'
+import { ServiceType } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { Secret, Select } from '@cliffy/prompt'
+import { RowType } from '@cliffy/table'
+
+interface ISecretRotation {
+  service: ServiceType
+  keys: string[]
+}
+
+/**
+ * Get the services and keys to rotate
+ *
+ * @param serviceName - Service to rotate secrets for, all enabled services if not set
+ * @param keys - Env vars to rotate, all rotatable generated secrets of the services if empty
+ */
+function getRotations(
+  config: Config,
+  serviceName: string | undefined,
+  keys: string[],
+): ISecretRotation[] {
+  const show = config.relayer.show
+
+  let services: ServiceType[]
+  if (serviceName) {
+    const service = config.getServiceByName(serviceName)
+    if (!service) {
+      show.fatal(`Unknown service: ${serviceName}`)
+    }
+    services = [service!]
+  } else {
+    services = config.getEnabledServices().toArray()
+  }
+
+  const rotations = services.map((service) => {
+    const secrets = Object.keys(service.getGeneratedSecrets())
+    return {
+      service,
+      keys: keys.length
+        ? keys.filter((key) => secrets.includes(key))
+        : service.getRotatableSecrets(),
+    }
+  }).filter((rotation) => rotation.keys.length > 0)
+
+  const found = rotations.flatMap((rotation) => rotation.keys)
+  const unknown = keys.filter((key) => !found.includes(key))
+  if (unknown.length) {
+    show.fatal(
+      `Not generated secrets of ${serviceName || 'the enabled services'}: ${unknown.join(', ')}`,
+    )
+  }
+  return rotations
+}
+
+/**
+ * Get the services and all of their dependents, recursively
+ */
+function getServicesWithDependents(config: Config, services: ServiceType[]): ServicesMap {
+  const affected = new ServicesMap()
+  const queue = [...services]
+  while (queue.length) {
+    const service = queue.shift()!
+    if (affected.addService(service)) {
+      queue.push(...config.getServiceDependents(service).toArray())
+    }
+  }
+  return affected
+}
+
+/**
+ * Restart the running services in dependency order
+ */
+async function restartServices(config: Config, services: ServicesMap): Promise<void> {
+  const show = config.relayer.show
+
+  const orderResult = config.getStartOrder(services)
+  if (!orderResult.success || !orderResult.data) {
+    show.logMessages(orderResult.messages)
+    show.fatal('Unable to get the restart order of the services')
+    return
+  }
+  const waves = orderResult.data
+
+  // Stop dependents before the services they depend on
+  for (const wave of [...waves].reverse()) {
+    await Promise.all(wave.toArray().map(async (service) => {
+      show.info(`Stopping ${service.name}...`)
+      const result = await service.stop()
+      show.logMessages(result.messages)
+    }))
+  }
+
+  for (const wave of waves) {
+    await Promise.all(wave.toArray().map(async (service) => {
+      show.info(`Starting ${service.name}...`)
+      const result = await service.start()
+      show.logMessages(result.messages)
+      if (!result.success) {
+        show.error(`Failed to restart ${service.name}`, { error: result.error })
+      }
+    }))
+  }
+}
+
+/**
+ * Regenerate generated secrets with the same method, length and prefix
+ *
+ * Postgres schema passwords are updated in the database. Running services that use the
+ * secrets, and their dependents, are restarted to pick up the new values.
+ * Secrets with rotatable: false are only rotated when named and confirmed.
+ *
+ * @param serviceName - Service to rotate secrets for, or the first key to rotate
+ * @param keys - Env vars to rotate, all rotatable generated secrets of the service if empty
+ */
+export async function rotateSecrets(
+  config: Config,
+  serviceName?: string,
+  keys: string[] = [],
+  { dryRun = false, skipPrompt = false, restart = true }: {
+    dryRun?: boolean
+    skipPrompt?: boolean
+    restart?: boolean
+  } = {},
+): Promise<void> {
+  const show = config.relayer.show
+
+  // Allow keys without a service, e.g. llmn secrets rotate FLOWISE_PASSWORD
+  if (serviceName && !config.getServiceByName(serviceName) && /^[A-Z0-9_]+$/.test(serviceName)) {
+    keys = [serviceName, ...keys]
+    serviceName = undefined
+  }
+
+  const rotations = getRotations(config, serviceName, keys)
+  if (rotations.length === 0) {
+    show.info('No generated secrets to rotate')
+    show.result({ dryRun, rotated: [], restarted: [] })
+    return
+  }
+
+  // Only running services need to be restarted
+  const affected = getServicesWithDependents(config, rotations.map((r) => r.service))
+  const running = await affected.filterAsync((service) => service.isRunning())
+
+  const storeName = config.secretsEnabled ? 'secrets.enc' : '.env'
+  const rows: RowType[] = rotations.flatMap(({ service, keys }) => {
+    const secrets = service.getGeneratedSecrets()
+    return keys.map((key) => [
+      service.name,
+      colors.yellow(key),
+      `${secrets[key].method}${secrets[key].length ? `(${secrets[key].length})` : ''}`,
+      secrets[key].postgresUserKey
+        ? `${storeName}, ALTER ROLE ${config.env[secrets[key].postgresUserKey!] || '?'}`
+        : storeName,
+    ])
+  })
+
+  show.header(dryRun ? 'Secrets that would be rotated' : 'Secrets to rotate')
+  show.table(['Service', 'Env Var', 'Method', 'Updates'], rows)
+  const restartNames = running.toArray().map((service) => service.name)
+  if (restart && restartNames.length) {
+    show.info(`Services to restart: ${colors.yellow(restartNames.join(', '))}`)
+  }
+
+  const result = {
+    dryRun,
+    rotated: rotations.map(({ service, keys }) => ({ service: service.service, keys })),
+    restarted: restart ? running.toArray().map((service) => service.service) : [],
+  }
+
+  if (dryRun) {
+    show.info('Dry run, no changes made')
+    show.result(result)
+    return
+  }
+
+  show.warn(
+    'Services that store secrets in their data volumes may need to be updated manually, ' +
+      'e.g. admin passwords set on first start',
+  )
+  if (!skipPrompt && !show.confirm('Rotate these secrets?', false)) {
+    show.info('Secrets not rotated')
+    return
+  }
+
+  // Secrets stored in the service data can only be rotated by name
+  const unsafe = rotations.flatMap(({ service, keys }) => {
+    const secrets = service.getGeneratedSecrets()
+    return keys.filter((key) => secrets[key].rotatable === false)
+  })
+  if (unsafe.length) {
+    show.warn(
+      `${unsafe.join(', ')} ${unsafe.length > 1 ? 'are' : 'is'} stored in the service data. ` +
+        'Existing data may become unreadable and services that use the old value will fail',
+    )
+    if (!skipPrompt && !show.confirm(`Rotate ${unsafe.join(', ')} anyway?`, false)) {
+      show.info('Secrets not rotated')
+      return
+    }
+  }
+
+  // Generate the new values, postgres passwords are updated in the database
+  const envVars: Record<string, string> = {}
+  for (const { service, keys } of rotations) {
+    const rotateResult = await service.rotateSecrets(keys)
+    show.logMessages(rotateResult.messages)
+    if (!rotateResult.success || !rotateResult.data) {
+      // Save any secrets that were already updated in the database
+      Object.keys(envVars).length && await config.setEnvFileVars(envVars)
+      show.fatal(`Failed to rotate secrets for ${service.name}`, { error: rotateResult.error })
+      return
+    }
+    Object.assign(envVars, rotateResult.data)
+  }
+
+  const envResult = await config.setEnvFileVars(envVars)
+  show.logMessages(envResult.messages)
+  if (!envResult.success) {
+    show.fatal(`Failed to update ${storeName}`, { error: envResult.error })
+  }
+  show.action(
+    `✔️ Rotated ${Object.keys(envVars).length} secrets in ` +
+      (config.secretsEnabled ? config.secretsFile : config.envFile),
+  )
+
+  if (restart && running.size > 0) {
+    show.action('Restarting services to use the new secrets...')
+    await restartServices(config, running)
+  } else if (!restart && running.size > 0) {
+    show.userAction(`Restart the services to use the new secrets: ${restartNames.join(', ')}`)
+  }
+
+  show.result(result)
+}
+
+/**
+ * Get the passphrase for a new secrets file
+ *
+ * Uses the passphrase env var or an existing key file, otherwise creates a new random key file
+ * or prompts for a passphrase.
+ */
+async function getNewPassphrase(config: Config, skipPrompt: boolean): Promise<string | undefined> {
+  const show = config.relayer.show
+  if (Deno.env.get(SECRETS_PASSPHRASE_ENV) || (await fileExists(config.secretsKeyFile)).data) {
+    return undefined // Use the existing passphrase
+  }
+
+  const method = skipPrompt ? 'keyfile' : await Select.prompt({
+    message: 'How should the secrets be encrypted?',
+    options: [
+      { name: `Generate a key file: ${config.secretsKeyFile}`, value: 'keyfile' },
+      { name: 'Enter a passphrase, prompted for on every command', value: 'passphrase' },
+    ],
+  })
+
+  if (method === 'passphrase') {
+    const passphrase = await Secret.prompt({ message: 'Secrets passphrase', minLength: 8 })
+    const confirm = await Secret.prompt({ message: 'Confirm passphrase' })
+    if (passphrase !== confirm) {
+      show.fatal('Passphrases do not match')
+    }
+    show.userAction(`Set ${SECRETS_PASSPHRASE_ENV} to use llmn in non-interactive shells`)
+    return passphrase
+  }
+
+  const keyResult = await createSecretsKeyFile(config.secretsKeyFile)
+  if (!keyResult.success || !keyResult.data) {
+    show.logMessages(keyResult.messages)
+    show.fatal('Unable to create the secrets key file')
+  }
+  show.info(`Created key file: ${colors.yellow(config.secretsKeyFile)}`)
+  show.userAction('Back up the key file, the secrets can not be decrypted without it')
+  return keyResult.data!
+}
+
+/**
+ * Move the secrets in .env to the encrypted secrets file
+ *
+ * The values are removed from .env, the keys are kept so the file still documents the env vars.
+ *
+ * @param keys - Env vars to move, all secrets in .env if empty
+ */
+export async function importSecrets(
+  config: Config,
+  keys: string[] = [],
+  { skipPrompt = false, dryRun = false }: { skipPrompt?: boolean; dryRun?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+
+  const envResult = await readTextFile(config.envFile)
+  if (!envResult.success) {
+    show.fatal(`Unable to read ${config.envFile}`, { error: envResult.error })
+  }
+  const envFileVars = parseWithoutExpand(envResult.data || '')
+
+  // References to other env vars are not secrets, e.g. KEY=${OTHER_KEY}
+  const candidates = Object.entries(envFileVars).filter(([key, value]) =>
+    value && !value.includes('${') && (keys.length ? keys.includes(key) : config.isSecretKey(key))
+  )
+  const unknown = keys.filter((key) => !envFileVars[key])
+  if (unknown.length) {
+    show.fatal(`Env vars not set in ${config.envFile}: ${unknown.join(', ')}`)
+  }
+  if (candidates.length === 0) {
+    show.info(`No secrets found in ${config.envFile}`)
+    show.result({ dryRun, imported: [] })
+    return
+  }
+
+  show.header(dryRun ? 'Secrets that would be encrypted' : 'Secrets to encrypt')
+  show.table(['Env Var', 'Value'], candidates.map(([key, value]) => [key, maskSecret(value)]))
+  const result = { dryRun, imported: candidates.map(([key]) => key), file: config.secretsFile }
+  if (dryRun) {
+    show.info('Dry run, no changes made')
+    show.result(result)
+    return
+  }
+  if (!skipPrompt && !show.confirm(`Move these secrets out of ${config.envFile}?`, true)) {
+    show.info('Secrets not imported')
+    return
+  }
+
+  const passphrase = await getNewPassphrase(config, skipPrompt)
+  const secrets = { ...config.secrets, ...Object.fromEntries(candidates) }
+  const saveResult = await config.saveSecrets(secrets, { passphrase })
+  if (!saveResult.success) {
+    show.logMessages(saveResult.messages)
+    show.fatal('Unable to save the secrets file')
+  }
+
+  // Make sure the secrets can be decrypted before removing them from .env
+  const verifyResult = await config.loadSecrets({ prompt: false })
+  if (
+    !verifyResult.success ||
+    candidates.some(([key, value]) => verifyResult.data?.[key] !== value)
+  ) {
+    show.logMessages(verifyResult.messages)
+    show.fatal(`Unable to verify the secrets file, ${config.envFile} was not changed`)
+  }
+
+  const clearResult = await clearEnvValues(config.envFile, candidates.map(([key]) => key))
+  show.logMessages(clearResult.messages)
+  if (!clearResult.success) {
+    show.fatal(`Unable to remove the secrets from ${config.envFile}`, { error: clearResult.error })
+  }
+
+  config.setSecretsStore('encrypted')
+  const configResult = await config.save()
+  if (!configResult.success) {
+    show.fatal('Unable to save the project config', { error: configResult.error })
+  }
+
+  show.action(`✔️ Moved ${candidates.length} secrets to ${config.secretsFile}`)
+  show.result(result)
+}
+
+/**
+ * Print the decrypted secrets as .env lines, or move them back into .env
+ */
+export async function exportSecrets(
+  config: Config,
+  { toEnv = false, skipPrompt = false }: { toEnv?: boolean; skipPrompt?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+
+  if (!(await fileExists(config.secretsFile)).data) {
+    show.fatal(`Secrets file not found: ${config.secretsFile}`)
+  }
+  // Secrets are already decrypted when the store is enabled
+  const secretsResult = config.secretsEnabled ? null : await config.loadSecrets()
+  if (secretsResult && !secretsResult.success) {
+    show.logMessages(secretsResult.messages)
+    show.fatal('Unable to decrypt the secrets file')
+  }
+  const secrets = config.secrets
+
+  if (!toEnv) {
+    // Print to stdout so the output can be redirected to a file
+    console.log(
+      Object.entries(secrets).map(([key, value]) => `${key}=${formatEnvValue(value)}`).join('\n'),
+    )
+    return
+  }
+
+  if (
+    !skipPrompt &&
+    !show.confirm(`Write ${Object.keys(secrets).length} secrets to ${config.envFile}?`, false)
+  ) {
+    show.info('Secrets not exported')
+    return
+  }
+
+  const envResult = await updateEnv(config.envFile, secrets)
+  show.logMessages(envResult.messages)
+  if (!envResult.success) {
+    show.fatal(`Unable to update ${config.envFile}`, { error: envResult.error })
+  }
+
+  config.setSecretsStore('env')
+  const configResult = await config.save()
+  if (!configResult.success) {
+    show.fatal('Unable to save the project config', { error: configResult.error })
+  }
+  await Deno.remove(config.secretsFile)
+
+  show.action(`✔️ Moved ${Object.keys(secrets).length} secrets to ${config.envFile}`)
+  show.result({ exported: Object.keys(secrets), file: config.envFile })
+}
+
+/**
+ * Edit the decrypted secrets in $EDITOR and re-encrypt them
+ *
+ * The decrypted secrets are written to a temp file only readable by the current user,
+ * the file is removed when the editor exits.
+ */
+export async function editSecrets(config: Config): Promise<void> {
+  const show = config.relayer.show
+
+  if (!config.secretsEnabled) {
+    show.fatal(`Encrypted secrets are not enabled, run ${colors.yellow('llmn secrets import')}`)
+  }
+  if (!Deno.stdin.isTerminal()) {
+    show.fatal('llmn secrets edit requires an interactive terminal')
+  }
+
+  const before = config.secrets
+  const tmpDir = path.join(config.configDir, 'tmp')
+  await ensureDir(tmpDir)
+  const tmpFile = await Deno.makeTempFile({ dir: tmpDir, prefix: 'secrets-', suffix: '.env' })
+
+  try {
+    await Deno.chmod(tmpFile, 0o600)
+    const doc = EnvDocument.parse(
+      '# Edit the project secrets, the file is encrypted when the editor exits\n' +
+        '# Remove a line to delete the secret\n',
+    )
+    Object.entries(before).forEach(([key, value]) => doc.set(key, value))
+    await Deno.writeTextFile(tmpFile, doc.toString())
+
+    const [editor, ...editorArgs] = (Deno.env.get('VISUAL') || Deno.env.get('EDITOR') || 'vi')
+      .split(/\s+/)
+    const editResult = await tryRunCommand(editor, {
+      args: [...editorArgs, tmpFile],
+      interactive: true,
+      autoLoadEnv: false,
+    })
+    // show.fatal exits without running finally, use show.error to remove the temp file
+    if (!editResult.success) {
+      show.error(`Editor exited with an error: ${editor}`, { error: editResult.error })
+      return
+    }
+
+    const after = parseWithoutExpand(await Deno.readTextFile(tmpFile))
+    const changed = Object.keys(after).filter((key) => before[key] !== after[key])
+    const removed = Object.keys(before).filter((key) => !(key in after))
+    if (changed.length === 0 && removed.length === 0) {
+      show.info('No changes to secrets')
+      return
+    }
+
+    const saveResult = await config.saveSecrets(after)
+    if (!saveResult.success) {
+      show.logMessages(saveResult.messages)
+      show.error('Unable to save the secrets file, changes were not saved')
+      return
+    }
+    show.action(`✔️ Updated ${changed.length} and removed ${removed.length} secrets`)
+    show.userAction('Restart the services that use the changed secrets')
+    show.result({ changed, removed })
+  } finally {
+    await Deno.remove(tmpFile).catch(() => {})
+  }
+}
+
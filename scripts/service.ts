@@ -0,0 +1,284 @@
+/**
+ * Scaffold new custom services for a project
+ */
+import { Config } from '@/core/config/config.ts'
+import { validateServiceYaml } from '@/core/config/lib/schema.ts'
+import {
+  getComposeScaffoldYaml,
+  getDefaultHealthcheck,
+  getServiceScaffoldTs,
+  getServiceScaffoldYaml,
+  IServiceScaffold,
+  parseDependsOn,
+  parsePort,
+  parseSecret,
+  SERVICE_GROUPS,
+} from '@/core/services/utils/scaffold.ts'
+import { dirExists, ensureDir, path } from '@/lib/fs.ts'
+import { offsetHostPort } from '@/lib/ports.ts'
+import { colors } from '@cliffy/ansi/colors'
+import { Checkbox, Confirm, Input, List, Select } from '@cliffy/prompt'
+
+// Default dir for custom services, relative to the project dir
+const DEFAULT_SERVICES_DIR = 'services'
+
+export interface INewServiceOptions {
+  name?: string
+  description?: string
+  group?: string
+  image?: string
+  port?: string[]
+  provides?: string[]
+  dependsOn?: string[]
+  dashboardUrl?: string
+  apiUrl?: string
+  secret?: string[]
+  healthcheck?: string
+  serviceTs?: boolean
+  dir?: string
+  enable?: boolean
+  skipPrompt?: boolean
+}
+
+const serviceNameValidator = (value: string) =>
+  /^[a-z0-9][a-z0-9_-]*$/.test(value) ||
+  'Only lowercase letters, numbers, hyphens and underscores are allowed'
+
+/**
+ * Get the dir to create the new service in
+ *
+ * Uses the first custom services dir from the project config, or ./services
+ */
+function getTargetServicesDir(config: Config, dir?: string): string {
+  if (dir) {
+    return path.resolve(Deno.cwd(), dir)
+  }
+  const builtinDir = path.join(config.installDir, 'services')
+  const customDir = config.servicesDirs.find((servicesDir) => servicesDir !== builtinDir)
+  return customDir || path.resolve(Deno.cwd(), DEFAULT_SERVICES_DIR)
+}
+
+/**
+ * Collect the settings for the new service from the options, prompting for any missing values
+ */
+async function getServiceScaffold(
+  config: Config,
+  service: string,
+  options: INewServiceOptions,
+): Promise<IServiceScaffold> {
+  const prompt = !options.skipPrompt
+  const friendlyName = service.split(/[-_]/).map((part) =>
+    part.charAt(0).toUpperCase() + part.slice(1)
+  ).join(' ')
+
+  const name = options.name ||
+    (prompt ? await Input.prompt({ message: 'Name', default: friendlyName }) : friendlyName)
+
+  const description = options.description ??
+    (prompt ? await Input.prompt({ message: 'Description', default: `${name} service` }) : '')
+
+  const serviceGroup = options.group || (prompt
+    ? await Select.prompt({
+      message: 'Service group',
+      hint: 'Groups are started in order: databases, middleware, apps',
+      options: [...SERVICE_GROUPS],
+      default: 'apps',
+    })
+    : 'apps')
+
+  const image = options.image || (prompt
+    ? await Input.prompt({
+      message: 'Docker image',
+      hint: 'e.g. nginx:latest',
+      minLength: 1,
+    })
+    : '')
+  if (!image) {
+    throw new Error('Docker image is required, use --image')
+  }
+
+  const ports = (options.port?.length ? options.port : prompt
+    ? await List.prompt({
+      message: 'Host ports',
+      hint: 'Comma separated host:container ports, e.g. 8080:80',
+    })
+    : []).map(parsePort)
+
+  const provides = options.provides?.length ? options.provides : prompt
+    ? await List.prompt({
+      message: 'Provides',
+      hint: 'Names other services can use in depends_on',
+      default: [service],
+    })
+    : [service]
+
+  // Names provided by the loaded services, used to select dependencies
+  const available = config.getAllServices().toArray()
+    .flatMap((s) => s.provides.map(([providesName]) => providesName))
+    .sort()
+  const dependsOn = Object.fromEntries((options.dependsOn?.length ? options.dependsOn : prompt
+    ? await Checkbox.prompt({
+      message: 'Depends on',
+      hint: `Services are started after their dependencies are healthy`,
+      options: available,
+    })
+    : []).map(parseDependsOn))
+
+  // Default urls use the default host port, ports are offset by the project base port at runtime
+  const defaultDashboard = ports[0] ? `http://localhost:${ports[0].host}` : ''
+  const defaultApi = ports[0] ? `http://${service}:${ports[0].container}` : ''
+  const dashboardUrl = options.dashboardUrl ?? (prompt
+    ? await Input.prompt({
+      message: 'Dashboard url on the host',
+      hint: 'Leave blank to skip',
+      default: defaultDashboard,
+    })
+    : defaultDashboard)
+  const apiUrl = options.apiUrl ?? (prompt
+    ? await Input.prompt({
+      message: 'API url inside the stack network',
+      hint: 'Leave blank to skip',
+      default: defaultApi,
+    })
+    : defaultApi)
+
+  const secrets = Object.fromEntries((options.secret?.length ? options.secret : prompt
+    ? await List.prompt({
+      message: 'Generated secrets',
+      hint: 'Comma separated env vars to generate during init, e.g. MY_APP_KEY:generateSecretKey:32',
+    })
+    : []).map(parseSecret))
+
+  return {
+    service,
+    name,
+    description,
+    serviceGroup,
+    image,
+    ports,
+    provides,
+    dependsOn,
+    exposes: {
+      host: dashboardUrl || undefined,
+      internal: apiUrl || undefined,
+    },
+    secrets,
+    healthcheck: options.healthcheck || getDefaultHealthcheck(ports),
+  }
+}
+
+/**
+ * Create a new custom service and register it in the project config
+ *
+ * @param service - The service name, also used for the service dir and container name
+ */
+export async function newService(
+  config: Config,
+  service?: string,
+  options: INewServiceOptions = {},
+): Promise<void> {
+  const show = config.relayer.show
+
+  if (!service) {
+    if (options.skipPrompt) {
+      show.fatal('Service name is required: llmn service new <name>')
+    }
+    service = await Input.prompt({
+      message: 'Service name',
+      hint: 'Used for the service dir and container name',
+      transform: (value?: string) => value?.toLowerCase(),
+      validate: serviceNameValidator,
+    })
+  }
+  const nameValid = serviceNameValidator(service)
+  if (nameValid !== true) {
+    show.fatal(`Invalid service name: ${service}. ${nameValid}`)
+  }
+
+  const existing = config.getServiceByName(service)
+  if (existing) {
+    show.warn(`A service named ${service} already exists: ${existing.name}`)
+    if (options.skipPrompt || !show.confirm('Create a custom service that overrides it?', false)) {
+      show.fatal('Service not created, choose a different name')
+    }
+  }
+
+  const servicesDir = getTargetServicesDir(config, options.dir)
+  const serviceDir = path.join(servicesDir, service)
+  if ((await dirExists(serviceDir)).data) {
+    show.fatal(`Service dir already exists: ${path.relative(Deno.cwd(), serviceDir)}`)
+  }
+
+  let spec: IServiceScaffold
+  try {
+    spec = await getServiceScaffold(config, service, options)
+    // Check the ports can be offset by the project base port
+    spec.ports.forEach((port) => offsetHostPort(port.host, config.basePort))
+  } catch (error) {
+    show.fatal((error as Error).message)
+    return
+  }
+
+  const createServiceTs = options.serviceTs ??
+    (!options.skipPrompt &&
+      await Confirm.prompt({ message: 'Create a service.ts file to customize the service?' }))
+
+  // Make sure the generated config is valid before writing any files
+  const serviceYaml = getServiceScaffoldYaml(spec)
+  const yamlResult = validateServiceYaml(serviceYaml, `${service}/llemonstack.yaml`)
+  if (!yamlResult.success) {
+    show.logMessages(yamlResult.messages)
+    show.fatal('Generated llemonstack.yaml is invalid, check the service options')
+  }
+
+  const files: Record<string, string> = {
+    'llemonstack.yaml': serviceYaml,
+    'docker-compose.yaml': getComposeScaffoldYaml(spec),
+  }
+  if (createServiceTs) {
+    files['service.ts'] = getServiceScaffoldTs(spec)
+  }
+
+  await ensureDir(serviceDir)
+  for (const [file, contents] of Object.entries(files)) {
+    await Deno.writeTextFile(path.join(serviceDir, file), contents)
+    show.info(`Created ${colors.yellow(path.relative(Deno.cwd(), path.join(serviceDir, file)))}`)
+  }
+
+  // Register the services dir and the new service in config.json
+  if (config.addServicesDir(servicesDir)) {
+    show.info(`Added ${path.relative(Deno.cwd(), servicesDir) || '.'} to dirs.services`)
+  }
+  const loadResult = await config.loadServices()
+  const createdService = config.getServiceByName(service)
+  if (!loadResult.success || !createdService) {
+    show.logMessages(loadResult.messages)
+    show.fatal(`Unable to load the new service: ${service}`)
+    return
+  }
+  config.updateServiceEnabledState(createdService, options.enable !== false)
+
+  const saveResult = await config.save()
+  if (!saveResult.success) {
+    show.logMessages(saveResult.messages)
+    show.fatal('Unable to save the project config')
+  }
+
+  // Generate the secrets in .env
+  if (Object.keys(spec.secrets).length) {
+    const initResult = await createdService.init()
+    show.logMessages(initResult.messages)
+    if (!initResult.success) {
+      show.warn(`Unable to generate secrets, run ${colors.yellow(`llmn init ${service}`)}`)
+    }
+  }
+
+  show.action(`✔️ Created ${spec.name} service in ${path.relative(Deno.cwd(), serviceDir)}`)
+  show.result({
+    service,
+    dir: serviceDir,
+    files: Object.keys(files),
+    enabled: createdService.isEnabled(),
+  })
+  show.userAction(`Review the generated files, then start the service with: llmn start ${service}`)
+}
@@ -3,9 +3,18 @@
  */
 
 import { Config } from '@/core/config/config.ts'
+import { getConditionLabel } from '@/core/services/service.ts'
 import { ServicesMap } from '@/core/services/services-map.ts'
+import { print } from '@/relayer/ui/output.ts'
 import { Cell, colors, Column, Row, RowType, showTable } from '@/relayer/ui/show.ts'
-import { EnvVars, ExposeHost, IServiceStartOptions, ServicesMapType, ServiceType } from '@/types'
+import {
+  EnvVars,
+  ExposeHost,
+  IServiceStartOptions,
+  ServicesMapType,
+  ServiceType,
+  TryCatchResult,
+} from '@/types'
 
 /*******************************************************************************
  * FUNCTIONS
@@ -38,20 +47,63 @@ export async function startService(
   return service
 }
 
+/**
+ * Wait for a service's dependencies to meet their depends_on conditions
+ *
+ * Readiness checks are shared across services so each provider is only polled once
+ * per condition, e.g. n8n and flowise both wait on the same supabase healthcheck.
+ *
+ * @param config - The config instance
+ * @param service - The service about to be started
+ * @param readiness - Map of in progress readiness checks, keyed by provider id and condition
+ */
+async function waitForDependencies(
+  config: Config,
+  service: ServiceType,
+  readiness: Map<string, Promise<TryCatchResult<boolean>>>,
+): Promise<void> {
+  const show = config.relayer.show
+
+  const checks = service.dependsOnConditions.map(([dependency, condition]) => {
+    const provider = config.getServiceByProvides(dependency)
+    // Skip dependencies that are not part of this start
+    if (!provider || provider.id === service.id || !provider.isEnabled()) {
+      return null
+    }
+    const key = `${provider.id}:${condition}`
+    if (!readiness.has(key)) {
+      show.info(`Waiting for ${provider.name} to be ${getConditionLabel(condition)}...`)
+      readiness.set(key, provider.waitForCondition(condition))
+    }
+    return readiness.get(key)!
+  }).filter(Boolean) as Promise<TryCatchResult<boolean>>[]
+
+  const results = await Promise.all(checks)
+  const failed = results.filter((result) => !result.success)
+  if (failed.length > 0) {
+    failed.forEach((result) => show.logMessages(result.messages))
+    throw new Error(`Dependencies are not ready for ${service.name}`)
+  }
+}
+
 /**
  * Start multiple services at the same time
  *
- * @param projectName - The project name
+ * Each service waits for its dependencies to meet their depends_on conditions before starting.
+ *
+ * @param config - The config instance
  * @param services - The services to start
  * @param envVars - The environment variables
- * @param composeFiles - The compose files to use
+ * @param build - Whether to rebuild the services
+ * @param readiness - Map of in progress readiness checks shared across waves
  */
 async function startServices(
   config: Config,
   services: ServicesMapType,
-  { envVars = {}, build = false }: {
+  { envVars = {}, build = false, readiness = new Map() }: {
     envVars?: EnvVars
     build?: boolean
+    readiness?: Map<string, Promise<TryCatchResult<boolean>>>
   } = {},
 ) {
   const show = config.relayer.show
@@ -59,6 +111,7 @@ async function startServices(
   // Start all services in parallel
   await Promise.all(services.filterMap(async (service) => {
     try {
+      await waitForDependencies(config, service, readiness)
       await startService(config, service, { envVars, build })
     } catch (error) {
       show.error(`Failed to start service ${service}:`, { error })
@@ -128,7 +181,37 @@ export function showServicesInfo(
   table
     .column(0, new Column().align('right'))
     .column(2, new Column().align('right'))
-    .render()
+  print(table.toString())
+}
+
+/**
+ * Get the endpoints for services as data for structured output
+ *
+ * @param services - The services to get endpoints for
+ * @param hostContext - The exposes context, e.g. 'host.*' or 'internal.*'
+ * @param hideCredentials - Mask credential values
+ * @returns The endpoints for each service that exposes any
+ */
+export function getServicesEndpoints(
+  services: ServicesMapType,
+  hostContext: string,
+  { hideCredentials = false }: { hideCredentials?: boolean } = {},
+): Array<{ service: string; name: string; endpoints: ExposeHost[] }> {
+  return services.toArray()
+    .sort((a, b) => a.name.localeCompare(b.name))
+    .map((service) => ({
+      service: service.service,
+      name: service.name,
+      endpoints: service.getEndpoints(hostContext).map((host) => ({
+        name: host.name,
+        url: host.url,
+        info: host.info,
+        credentials: host.credentials && Object.fromEntries(
+          Object.entries(host.credentials).map(([k, v]) => [k, hideCredentials ? '********' : v]),
+        ),
+      })),
+    }))
+    .filter((service) => service.endpoints.length > 0)
 }
 
 function outputServicesInfo({
@@ -163,16 +246,25 @@ function outputServicesInfo({
     await service.showStartInfo()
   })
 
-  console.log('\n')
+  print('\n')
 }
 
 export async function start(
   config: Config,
-  { service: serviceOrName, skipOutput = false, hideCredentials = true, build = false }: {
+  {
+    service: serviceOrName,
+    skipOutput = false,
+    hideCredentials = true,
+    build = false,
+    remapPorts = false,
+    dryRun = false,
+  }: {
     service?: string | ServiceType
     skipOutput?: boolean
     hideCredentials?: boolean
     build?: boolean
+    remapPorts?: boolean // Remap host ports that are in use instead of failing
+    dryRun?: boolean // Show what would be started without starting anything
   } = {},
 ): Promise<void> {
   const show = config.relayer.show
@@ -194,20 +286,25 @@ export async function start(
     show.warn(`${service.name} is not enabled`)
     return
   }
+
+  if (dryRun) {
+    const { planStart, showPlan } = await import('./plan.ts')
+    showPlan(config, await planStart(config, { service }))
+    return
+  }
+
   try {
     if (!config.isProjectInitialized()) {
-      show.warn('Project not initialized', { emoji: '❌' })
-      show.userAction('\nPlease run the init script first: llmn init')
-      Deno.exit(1)
+      show.fatal('Project not initialized, please run the init script first: llmn init')
     }
 
     await config.checkPrerequisites()
 
     show.action('Setting up environment...')
-    const prepareEnvResult = await config.prepareEnv()
+    const prepareEnvResult = await config.prepareEnv({ checkPorts: true, remapPorts })
     show.logMessages(prepareEnvResult.messages)
     if (!prepareEnvResult.success) {
-      Deno.exit(1)
+      show.fatal('Failed to prepare the environment')
     }
 
     // Start services
@@ -215,15 +312,17 @@ export async function start(
       // Start a single service
       await startService(config, service, { build })
     } else {
-      // Start all services by service group
-      for (const [groupName, groupServices] of config.getServicesGroups()) {
-        const enabledGroupServices = groupServices.filter((service: ServiceType) =>
-          service.isEnabled()
-        )
-        if (enabledGroupServices.size > 0) {
-          show.action(`\nStarting ${groupName} services...`)
-          await startServices(config, enabledGroupServices, { build })
-        }
+      // Start all services in dependency order
+      // Each wave only depends on services started in previous waves
+      const orderResult = config.getStartOrder()
+      show.logMessages(orderResult.messages)
+      if (!orderResult.success || !orderResult.data) {
+        show.fatal('Unable to determine service start order', { error: orderResult.error })
+      }
+      const readiness = new Map<string, Promise<TryCatchResult<boolean>>>()
+      for (const wave of orderResult.data!) {
+        show.action(`\nStarting ${wave.map((s) => s.name).join(', ')}...`)
+        await startServices(config, wave, { build, readiness })
       }
     }
 
@@ -233,15 +332,25 @@ export async function start(
       show.action('\nAll services started successfully!')
     }
 
+    // Record the start time in the global project registry
+    const registryResult = await config.updateProjectRegistry({ started: true })
+    if (!registryResult.success) {
+      show.logMessages(registryResult.messages)
+    }
+
     if (!skipOutput) {
-      await outputServicesInfo({
-        config,
-        hideCredentials,
-        services: service ? new ServicesMap([service]) : config.getEnabledServices(),
+      const services = service ? new ServicesMap([service]) : config.getEnabledServices()
+      show.result({
+        project: config.projectName,
+        started: services.map((s) => s.service),
+        endpoints: {
+          host: getServicesEndpoints(services, 'host.*', { hideCredentials }),
+          internal: getServicesEndpoints(services, 'internal.*', { hideCredentials }),
+        },
       })
+      await outputServicesInfo({ config, hideCredentials, services })
     }
   } catch (error) {
-    show.error('Failed to start services', { error })
-    Deno.exit(1)
+    show.fatal('Failed to start services', { error })
   }
 }
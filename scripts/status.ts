@@ -0,0 +1,175 @@
+/**
+ * Show the status of services, optionally as a live dashboard
+ */
+
+import { ServiceStatusMonitor } from '@/core/services/mod.ts'
+import { InterfaceRelayer } from '@/relayer/ui/interface.ts'
+import { ServicesMapType, ServiceStatusType, ServiceType } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { cursorHide, cursorShow, cursorTo, eraseScreen } from '@cliffy/ansi/ansi-escapes'
+import { RowType } from '@cliffy/table'
+import { Config } from '../src/core/config/config.ts'
+
+const STATUS_COLORS: Record<ServiceStatusType, (str: string) => string> = {
+  disabled: colors.gray,
+  loaded: colors.gray,
+  ready: colors.gray,
+  starting: colors.yellow,
+  started: colors.green,
+  running: colors.green,
+  unhealthy: colors.red,
+  stopped: colors.gray,
+  error: colors.red,
+}
+
+interface ServiceStatusInfo {
+  service: string
+  name: string
+  group: string
+  status: ServiceStatusType
+  state: string | null
+  healthy: boolean | null
+  uptime: string | null
+  ports: string[]
+  last_checked: Date | null
+}
+
+/**
+ * Get the uptime from the Docker Compose status string
+ *
+ * @example
+ * getUptime('Up 2 hours (healthy)') // '2 hours'
+ *
+ * @param status - The Docker Compose ps status string
+ * @returns The uptime or null if the container is not up
+ */
+function getUptime(status: string | null): string | null {
+  const match = status?.match(/^Up\s+(.+?)(\s+\(.*\))?$/)
+  return match ? match[1] : null
+}
+
+async function getStatusInfo(service: ServiceType): Promise<ServiceStatusInfo> {
+  return {
+    service: service.service,
+    name: service.name,
+    group: service.serviceGroup,
+    status: await service.getStatus({ refresh: false }),
+    state: service.getState('state') as string | null,
+    healthy: service.getState('healthy') as boolean | null,
+    uptime: getUptime(service.getState('status') as string | null),
+    ports: service.getState('ports') as string[],
+    last_checked: service.getState('last_checked') as Date | null,
+  }
+}
+
+function healthLabel(info: ServiceStatusInfo): string {
+  if (info.state !== 'running') {
+    return ''
+  }
+  return info.healthy === true
+    ? colors.green('healthy')
+    : info.healthy === false
+    ? colors.red('unhealthy')
+    : colors.yellow('starting')
+}
+
+/**
+ * Render the status table grouped by service group
+ */
+async function renderStatus(
+  config: Config,
+  services: ServicesMapType,
+  { clear = false }: { clear?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  const rows: RowType[] = []
+
+  for (const [groupName, groupServices] of config.getServicesGroups()) {
+    const infos = await Promise.all(
+      groupServices.filter((s) => services.has(s.id)).map((s) => getStatusInfo(s)),
+    )
+    if (infos.length === 0) {
+      continue
+    }
+    rows.push([colors.cyan.bold(groupName)])
+    infos.forEach((info) => {
+      rows.push([
+        info.name,
+        STATUS_COLORS[info.status](info.status),
+        healthLabel(info),
+        info.uptime || '',
+        info.ports.join(', '),
+      ])
+    })
+  }
+
+  if (clear) {
+    Deno.stdout.writeSync(new TextEncoder().encode(cursorTo(0, 0) + eraseScreen))
+    show.info(
+      `${colors.bold(config.projectName)} - ${colors.gray(new Date().toLocaleTimeString())}` +
+        colors.gray(' (Ctrl+C to exit)'),
+    )
+  }
+
+  show.table(['Service', 'Status', 'Health', 'Uptime', 'Ports'], rows, { maxColumnWidth: 0 })
+}
+
+export async function status(
+  config: Config,
+  { watch = false, interval = 2 }: {
+    watch?: boolean
+    interval?: number // Seconds between refreshes in watch mode
+  } = {},
+): Promise<void> {
+  const show = config.relayer.show
+
+  const monitor = new ServiceStatusMonitor(config, { interval: interval * 1000 })
+  const result = await monitor.refresh()
+  if (!result.success) {
+    show.logMessages(result.messages)
+    show.fatal('Failed to get the services status')
+  }
+  // Show enabled services and any disabled services that still have containers
+  const services = config.getAllServices().filter((s) =>
+    s.isEnabled() || !!s.getState('state')
+  )
+
+  // Structured output is always a one-shot status
+  if (InterfaceRelayer.isStructuredOutput()) {
+    const infos = await Promise.all(services.map((s) => getStatusInfo(s)))
+    show.result({ project: config.projectName, services: infos })
+    return
+  }
+
+  if (!watch) {
+    await renderStatus(config, services)
+    return
+  }
+
+  // Restore the cursor when exiting the dashboard
+  Deno.addSignalListener('SIGINT', () => {
+    monitor.stop()
+    Deno.stdout.writeSync(new TextEncoder().encode(cursorShow))
+    Deno.exit(0)
+  })
+  Deno.stdout.writeSync(new TextEncoder().encode(cursorHide))
+
+  // Re-render when any service state changes, and on every tick to update the clock
+  let rendering = false
+  const render = async () => {
+    if (rendering) {
+      return
+    }
+    rendering = true
+    await renderStatus(config, services, { clear: true })
+    rendering = false
+  }
+  monitor.onChange(render)
+  monitor.start((result) => show.logMessages(result.messages))
+  await render()
+
+  // Keep the process alive until the user exits
+  await new Promise<void>(() => {
+    setInterval(render, interval * 1000)
+  })
+}
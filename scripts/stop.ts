@@ -48,8 +48,7 @@ async function stopServices(
     // Check for invalid services
     const missingServices = services.missingServices(servicesOrNames)
     if (missingServices.length > 0) {
-      show.error(`Unknown services: ${missingServices.join(', ')}`)
-      Deno.exit(1)
+      show.fatal(`Unknown services: ${missingServices.join(', ')}`)
     }
   }
 
@@ -104,7 +103,11 @@ async function stopService(
 
 export async function stop(
   config: Config,
-  { all = false, service: serviceName }: { all?: boolean; service?: string } = {},
+  { all = false, service: serviceName, dryRun = false }: {
+    all?: boolean
+    service?: string
+    dryRun?: boolean
+  } = {},
 ): Promise<void> {
   const show = config.relayer.show
   let stopAll = all
@@ -114,7 +117,6 @@ export async function stop(
   if (serviceName && !service) {
     stopAll = false
     if (!service) {
-      show.error(`Unknown service: '${serviceName}'`)
       show.action('\nAvailable services:\n')
 
       const rows = config.getAllServices().toArray().map((_service) => {
@@ -122,7 +124,7 @@ export async function stop(
       }).filter(Boolean) as RowType[]
 
       showTable(['Service', 'Description'], rows, { maxColumnWidth: 100 })
-      Deno.exit(1)
+      show.fatal(`Unknown service: '${serviceName}'`)
     }
   }
 
@@ -130,6 +132,12 @@ export async function stop(
     stopAll = false
   }
 
+  if (dryRun) {
+    const { planStop, showPlan } = await import('./plan.ts')
+    showPlan(config, await planStop(config, { all: stopAll, service }))
+    return
+  }
+
   if (stopAll) {
     show.action(`Stopping all services for project: ${config.projectName}...`)
   } else {
@@ -140,7 +148,7 @@ export async function stop(
   const prepareResult = await config.prepareEnv()
   if (!prepareResult.success) {
     show.logMessages(prepareResult.messages)
-    Deno.exit(1)
+    show.fatal('Failed to prepare the environment')
   }
 
   if (service) {
@@ -3,6 +3,7 @@
  *
  * Pulls and builds the latest changes for docker images.
  */
+import { fileExists } from '@/lib/fs.ts'
 import { Config } from '../src/core/config/config.ts'
 import { stop } from './stop.ts'
 import { versions } from './versions.ts'
@@ -37,16 +38,36 @@ export async function update(
     skipStop = false,
     skipPrompt = false,
     service: serviceName,
-  }: { skipStop?: boolean; skipPrompt?: boolean; service?: string } = {},
+    dryRun = false,
+  }: { skipStop?: boolean; skipPrompt?: boolean; service?: string; dryRun?: boolean } = {},
 ): Promise<void> {
   const show = config.relayer.show
+  if (config.offline) {
+    show.fatal('Project is offline, load a new bundle with `llmn bundle load` to update')
+  }
   try {
+    if (dryRun) {
+      const service = serviceName ? config.getServiceByName(serviceName) : null
+      if (serviceName && !service) {
+        show.fatal(`Service ${serviceName} not found`)
+      }
+      const { planUpdate, showPlan } = await import('./plan.ts')
+      showPlan(config, await planUpdate(config, { skipStop, service }))
+      return
+    }
+
     if (!skipPrompt) {
       show.info(
         '\nUpdate repos, pull the latest Docker images, and rebuild custom Docker images.\n' +
           'Only services enabled in your .env file will be updated.\n' +
           'This can take a while.',
       )
+      if (await fileExists(config.imagesLockFile).then((result) => result.data)) {
+        show.info(
+          'Images are pinned to the digests in images.lock, ' +
+            'run `llmn lock --upgrade` first to update them.',
+        )
+      }
       if (!show.confirm('Are you sure you want to continue?')) {
         show.info('Update cancelled')
         return
@@ -59,31 +80,40 @@ export async function update(
       const prepareResult = await config.prepareEnv()
       if (!prepareResult.success) {
         show.logMessages(prepareResult.messages)
-        Deno.exit(1)
+        show.fatal('Failed to prepare the environment')
       }
     }
 
     if (serviceName) {
       const service = config.getServiceByName(serviceName)
       if (!service) {
-        show.error(`Service ${serviceName} not found`)
-        Deno.exit(1)
+        show.fatal(`Service ${serviceName} not found`)
       }
-      show.action(`\nPulling latest docker image for ${service.name}...`)
-      await service.update()
+      show.action(`\nPulling latest docker image for ${service!.name}...`)
+      await service!.update()
     } else {
       // Pull latest images
       show.action('Pulling latest docker images...')
       await pullImages(config)
     }
 
+    // Add env vars from updated .env.example files, existing values are kept
+    const mergeResult = await config.mergeServicesEnv(
+      serviceName ? config.getServicesByNames([serviceName]) : config.getEnabledServices(),
+    )
+    show.logMessages(mergeResult.messages)
+    if (!mergeResult.success) {
+      show.warn(`Failed to add new env vars to .env: ${mergeResult.error?.message}`)
+    } else if (mergeResult.data?.length) {
+      show.info(`Added new env vars to .env: ${mergeResult.data.join(', ')}`)
+    }
+
     // Show the software versions for images that support it
     show.action('\n------ VERSIONS ------')
     await versions(config)
 
     show.action('Update successfully completed!')
   } catch (error) {
-    show.error('Update failed', { error })
-    Deno.exit(1)
+    show.fatal('Update failed', { error })
   }
 }
@@ -0,0 +1,153 @@
+/**
+ * Validate service llemonstack.yaml files against the service schema
+ */
+import { Config } from '@/core/config/config.ts'
+import { SERVICE_CONFIG_FILE_NAME } from '@/core/config/lib/load.ts'
+import { validateServiceYaml } from '@/core/config/lib/schema.ts'
+import { fileExists, path, readDir, readTextFile } from '@/lib/fs.ts'
+import { TryCatchResult } from '@/lib/try-catch.ts'
+import { LogMessage, ServiceYaml } from '@/types'
+import { colors } from '@cliffy/ansi/colors'
+import { RowType } from '@cliffy/table'
+
+interface IValidateFileResult {
+  service: string
+  file: string
+  errors: string[]
+  warnings: string[]
+}
+
+/**
+ * Validate a single llemonstack.yaml file
+ *
+ * Checks the schema, the compose file path and that all depends_on services are provided
+ * by a loaded service.
+ */
+async function validateServiceFile(
+  config: Config,
+  serviceDir: string,
+): Promise<IValidateFileResult> {
+  const file = path.join(serviceDir, SERVICE_CONFIG_FILE_NAME)
+  const relativeFile = path.relative(Deno.cwd(), file)
+  const messages: LogMessage[] = []
+
+  const textResult = await readTextFile(file)
+  let yamlResult: TryCatchResult<ServiceYaml> | null = null
+  if (!textResult.success || textResult.data === null) {
+    messages.push({
+      level: 'error',
+      message: `${relativeFile}: Unable to read file`,
+      error: textResult.error,
+    })
+  } else {
+    yamlResult = validateServiceYaml(textResult.data, relativeFile)
+    messages.push(...yamlResult.messages)
+  }
+
+  const serviceYaml = yamlResult?.data
+  if (serviceYaml) {
+    if (serviceYaml.service !== path.basename(serviceDir)) {
+      messages.push({
+        level: 'error',
+        message: `${relativeFile}: service "${serviceYaml.service}" must match the dir name "${
+          path.basename(serviceDir)
+        }"`,
+        error: null,
+      })
+    }
+    const composeFile = path.join(serviceDir, serviceYaml.compose_file)
+    if (!(await fileExists(composeFile)).data) {
+      messages.push({
+        level: 'error',
+        message: `${relativeFile}: compose_file not found: ${serviceYaml.compose_file}`,
+        error: null,
+      })
+    }
+    Object.keys(serviceYaml.depends_on || {}).forEach((dependency) => {
+      if (!config.getServiceByProvides(dependency)) {
+        messages.push({
+          level: 'warning',
+          message: `${relativeFile}: depends_on ${dependency} is not provided by any service`,
+        })
+      }
+    })
+  }
+
+  return {
+    service: serviceYaml?.service || path.basename(serviceDir),
+    file: relativeFile,
+    errors: messages.filter((m) => m.level === 'error').map((m) => m.message),
+    warnings: messages.filter((m) => m.level === 'warning').map((m) => m.message),
+  }
+}
+
+/**
+ * Lint the custom service dirs listed in dirs.services in the project config
+ *
+ * @param all - Also validate the built-in LLemonStack services
+ */
+export async function validate(
+  config: Config,
+  { all = false }: { all?: boolean } = {},
+): Promise<void> {
+  const show = config.relayer.show
+  const builtinDir = path.join(config.installDir, 'services')
+  const servicesDirs = config.servicesDirs.filter((dir) => all || dir !== builtinDir)
+
+  if (servicesDirs.length === 0) {
+    show.info('No custom services dirs found in dirs.services of the project config')
+    show.info(`Run ${colors.yellow('llmn validate --all')} to validate the built-in services`)
+    show.result({ valid: true, files: [] })
+    return
+  }
+
+  const results: IValidateFileResult[] = []
+  for (const servicesDir of servicesDirs) {
+    const dirResult = await readDir(servicesDir)
+    if (!dirResult.success || !dirResult.data) {
+      results.push({
+        service: '',
+        file: path.relative(Deno.cwd(), servicesDir),
+        errors: [`Unable to read services dir: ${servicesDir}`],
+        warnings: [],
+      })
+      continue
+    }
+    for await (const entry of dirResult.data) {
+      // Dirs that start with an underscore are skipped by the service loader
+      if (!entry.isDirectory || entry.name.startsWith('_')) {
+        continue
+      }
+      const serviceDir = path.join(servicesDir, entry.name)
+      if (!(await fileExists(path.join(serviceDir, SERVICE_CONFIG_FILE_NAME))).data) {
+        continue
+      }
+      results.push(await validateServiceFile(config, serviceDir))
+    }
+  }
+
+  results.sort((a, b) => a.file.localeCompare(b.file))
+  const valid = results.every((result) => result.errors.length === 0)
+  show.result({ valid, files: results })
+
+  const rows: RowType[] = results.map((result) => [
+    result.service,
+    result.file,
+    result.errors.length
+      ? colors.red('invalid')
+      : result.warnings.length
+      ? colors.yellow('warnings')
+      : colors.green('valid'),
+  ])
+  show.table(['Service', 'File', 'Status'], rows, { maxColumnWidth: 0 })
+
+  results.forEach((result) => {
+    result.errors.forEach((message) => show.error(message))
+    result.warnings.forEach((message) => show.warn(message))
+  })
+
+  if (!valid) {
+    show.fatal(`Found errors in ${results.filter((r) => r.errors.length).length} service config(s)`)
+  }
+  show.info(`✔️ ${results.length} service config(s) validated`)
+}
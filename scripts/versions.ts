@@ -2,17 +2,11 @@
  * Show the versions of the services that support it
  */
 
-import { tryRunCommand } from '@/lib/command.ts'
 import { getImageFromCompose, getImagesFromComposeYaml } from '@/lib/compose.ts'
-import { dockerComposeRun } from '@/lib/docker.ts'
+import { dockerComposeRun, getDockerImageVersion } from '@/lib/docker.ts'
 import { InterfaceRelayer } from '@/relayer/ui/interface.ts'
-import {
-  IServiceImage,
-  RunCommandOutput,
-  ServicesMapType,
-  ServiceType,
-  TryCatchResult,
-} from '@/types'
+import { print } from '@/relayer/ui/output.ts'
+import { IServiceImage, ServicesMapType, ServiceType } from '@/types'
 import { colors } from '@cliffy/ansi/colors'
 import { Column, Row, RowType } from '@cliffy/table'
 import packageJson from '@packageJson' with { type: 'json' }
@@ -35,7 +29,7 @@ function showVersionsTable(
   const table = show.table(header, rows, { ...options, render: false })
   table.column(0, new Column().align('right'))
   table.column(3, new Column().align('right'))
-  table.render()
+  print(table.toString())
 }
 
 /**
@@ -84,7 +78,10 @@ async function getAppVersion(
   return serviceImage
 }
 
-async function getAppVersions(config: Config, services: ServicesMapType): Promise<string[][]> {
+async function getAppVersions(
+  config: Config,
+  services: ServicesMapType,
+): Promise<IServiceImage[]> {
   const show = config.relayer.show
 
   // Get enabled services and process them in parallel
@@ -110,15 +107,12 @@ async function getAppVersions(config: Config, services: ServicesMapType): Promis
       return serviceImage
     }),
   )
-  const rows = results.map((serviceImage) => [
-    colors.yellow(serviceImage.service),
-    colors.green.bold(serviceImage.version || 'not available'),
-    colors.gray(serviceImage.image || serviceImage.build || ''),
-  ])
-  return rows
+  return results
 }
 
-async function showImageVersions(config: Config): Promise<RowType[]> {
+async function showImageVersions(
+  config: Config,
+): Promise<{ rows: RowType[]; images: Array<IServiceImage & { composeFile: string }> }> {
   const relayer = config.relayer
   const show = relayer.show
 
@@ -143,6 +137,7 @@ async function showImageVersions(config: Config): Promise<RowType[]> {
 
   // Collect all rows for a single table
   const allRows: RowType[] = []
+  const allImages: Array<IServiceImage & { composeFile: string }> = []
 
   // Process each valid result
   for (const { composeFile, images, error } of composeResults) {
@@ -196,40 +191,17 @@ async function showImageVersions(config: Config): Promise<RowType[]> {
         continue
       }
       if (!serviceImage.version || /latest|main/i.test(serviceImage.version || '')) {
-        try {
-          const results = await config.relayer.withLevel<TryCatchResult<RunCommandOutput>>(
-            'silent',
-            () => {
-              return tryRunCommand('docker', {
-                args: [
-                  'inspect',
-                  '--format',
-                  '{{index .Config.Labels "org.opencontainers.image.version"}}',
-                  serviceImage.image,
-                ],
-                captureOutput: true,
-                silent: true,
-              })
-            },
-          )
-
-          let version = ''
-          if (!results.success) {
-            relayer.debug('Error getting version from docker inspect for {image}', serviceImage)
-          } else {
-            version = results.toString().trim()
-          }
-
-          serviceImage.version = version
-        } catch (error) {
-          // Ignore the error but log it for debugging
-          relayer.debug('Error getting version from docker inspect', error as Error)
+        const results = await getDockerImageVersion(serviceImage.image)
+        if (!results.success) {
+          relayer.debug('Error getting version from docker inspect for {image}', serviceImage)
         }
+        serviceImage.version = results.data || ''
       }
     }
 
     // Build table rows with colors
     for (const serviceImage of images) {
+      allImages.push({ ...serviceImage, composeFile })
       allRows.push([
         colors.yellow(serviceImage.service),
         /n\/a|custom/i.test(serviceImage.version || '')
@@ -251,7 +223,7 @@ async function showImageVersions(config: Config): Promise<RowType[]> {
     ).border(false))
   }
 
-  return allRows
+  return { rows: allRows, images: allImages }
 }
 
 export async function versions(config: Config): Promise<void> {
@@ -270,7 +242,7 @@ export async function versions(config: Config): Promise<void> {
   const prepareResult = await config.prepareEnv()
   if (!prepareResult.success) {
     show.logMessages(prepareResult.messages)
-    Deno.exit(1)
+    show.fatal('Failed to prepare the environment')
   }
 
   try {
@@ -280,7 +252,7 @@ export async function versions(config: Config): Promise<void> {
       ? getAppVersions(config, services)
       : Promise.resolve([])
 
-    const imageVersionRows = await showImageVersions(config)
+    const { rows: imageVersionRows, images } = await showImageVersions(config)
 
     if (imageVersionRows.length > 0) {
       showVersionsTable(
@@ -298,7 +270,12 @@ export async function versions(config: Config): Promise<void> {
     }
 
     show.header('Service App Versions')
-    const appVersionRows = await appVersionsPromise
+    const appVersions = await appVersionsPromise
+    const appVersionRows = appVersions.map((serviceImage) => [
+      colors.yellow(serviceImage.service),
+      colors.green.bold(serviceImage.version || 'not available'),
+      colors.gray(serviceImage.image || serviceImage.build || ''),
+    ])
 
     // Sort app version rows by service name (first column)
     appVersionRows.sort((a, b) => {
@@ -315,7 +292,25 @@ export async function versions(config: Config): Promise<void> {
     } else {
       show.info('No app versions found')
     }
-    console.log('\n')
+    print('\n')
+
+    show.result({
+      llemonstack: packageJson.version,
+      project: config.projectName,
+      images: images.map(({ service, containerName, image, build, version, composeFile }) => ({
+        service,
+        containerName,
+        image,
+        build,
+        version,
+        composeFile,
+      })),
+      apps: appVersions.map(({ service, version, image, build }) => ({
+        service,
+        version: version || null,
+        image: image || build || null,
+      })),
+    })
   } catch (error) {
     show.error(error as Error)
   }
@@ -1,5 +1,6 @@
-// supabase-backup.ts - A script to backup Supabase databases
-// Usage: bun run supabase-backup.ts
+// backup.ts - Backup the LLemonStack postgres database
+// Usage: bun run backup.ts
+// Run on a schedule by the pgbackup-scheduler container, see docker-compose.yaml
 // @ts-nocheck
 // deno-lint-ignore-file
 
@@ -11,10 +12,14 @@ import { promisify } from 'util'
 // Promisify exec for cleaner async/await usage
 const execAsync = promisify(exec)
 
+// Backup files are named <db>_<timestamp>.dump
+const BACKUP_FILE_EXT = '.dump'
+
+// Status of the last backup, read by `llmn info`
+const LAST_BACKUP_FILE = 'last-backup.json'
+
 // Domain model for our backup process
 interface BackupConfig {
-  supabaseUrl: string
-  supabaseServiceKey: string
   pgHost: string
   pgPort: string
   pgDatabase: string
@@ -37,10 +42,7 @@ interface BackupResult {
 function loadConfig(): BackupConfig {
   // Validate required environment variables
   const requiredEnvVars = [
-    'SUPABASE_URL',
-    'SUPABASE_SERVICE_ROLE_KEY',
     'POSTGRES_HOST',
-    'POSTGRES_PORT',
     'POSTGRES_DB',
     'POSTGRES_USER',
     'POSTGRES_PASSWORD',
@@ -52,16 +54,16 @@ function loadConfig(): BackupConfig {
     }
   }
 
+  const retentionDays = parseInt(process.env.RETENTION_DAYS || '7', 10)
+
   return {
-    supabaseUrl: process.env.SUPABASE_URL!,
-    supabaseServiceKey: process.env.SUPABASE_SERVICE_ROLE_KEY!,
     pgHost: process.env.POSTGRES_HOST!,
     pgPort: process.env.POSTGRES_PORT || '5432',
     pgDatabase: process.env.POSTGRES_DB!,
     pgUser: process.env.POSTGRES_USER!,
     pgPassword: process.env.POSTGRES_PASSWORD!,
     backupDir: process.env.BACKUP_DIR || '/backups',
-    retentionDays: parseInt(process.env.RETENTION_DAYS || '7', 10),
+    retentionDays: isNaN(retentionDays) ? 7 : retentionDays,
   }
 }
 
@@ -77,7 +79,7 @@ async function ensureBackupDir(dir: string): Promise<void> {
 function generateBackupFilename(dbName: string): string {
   const now = new Date()
   const timestamp = now.toISOString().replace(/[:.]/g, '-')
-  return `${dbName}_${timestamp}.sql.gz`
+  return `${dbName}_${timestamp}${BACKUP_FILE_EXT}`
 }
 
 // Perform database backup using pg_dump
@@ -87,12 +89,12 @@ async function backupDatabase(config: BackupConfig): Promise<BackupResult> {
   const backupPath = path.join(config.backupDir, filename)
 
   try {
-    // Use pg_dump to create a compressed backup file
+    // Use pg_dump custom format, it's compressed and can be restored with pg_restore
     const cmd =
-      `PGPASSWORD="${config.pgPassword}" pg_dump -h ${config.pgHost} -p ${config.pgPort} -U ${config.pgUser} -d ${config.pgDatabase} -F c | gzip > ${backupPath}`
+      `pg_dump -h ${config.pgHost} -p ${config.pgPort} -U ${config.pgUser} -d ${config.pgDatabase} -F c -f ${backupPath}`
 
     console.log(`Starting backup: ${filename}`)
-    await execAsync(cmd)
+    await execAsync(cmd, { env: { ...process.env, PGPASSWORD: config.pgPassword } })
 
     // Get file size
     const stats = fs.statSync(backupPath)
@@ -106,6 +108,8 @@ async function backupDatabase(config: BackupConfig): Promise<BackupResult> {
     }
   } catch (error) {
     console.error('Backup failed:', error)
+    // Remove partial backup files so they are not mistaken for valid backups
+    fs.rmSync(backupPath, { force: true })
     return {
       filename,
       timestamp,
@@ -118,14 +122,21 @@ async function backupDatabase(config: BackupConfig): Promise<BackupResult> {
 }
 
 // Clean up old backups beyond retention period
-async function cleanupOldBackups(config: BackupConfig): Promise<void> {
+// Returns the names of the removed files
+async function cleanupOldBackups(config: BackupConfig): Promise<string[]> {
+  if (config.retentionDays <= 0) {
+    console.log('Retention is disabled, keeping all backups')
+    return []
+  }
+
   console.log(`Cleaning up backups older than ${config.retentionDays} days`)
 
   const files = fs.readdirSync(config.backupDir)
   const now = new Date()
+  const removed: string[] = []
 
   for (const file of files) {
-    if (!file.endsWith('.sql.gz')) continue
+    if (!file.endsWith(BACKUP_FILE_EXT)) continue
 
     const filePath = path.join(config.backupDir, file)
     const stats = fs.statSync(filePath)
@@ -137,21 +148,34 @@ async function cleanupOldBackups(config: BackupConfig): Promise<void> {
     if (ageInDays > config.retentionDays) {
       console.log(`Removing old backup: ${file} (${Math.floor(ageInDays)} days old)`)
       fs.unlinkSync(filePath)
+      removed.push(file)
     }
   }
+
+  return removed
 }
 
-// Optional: Upload backup to S3 or other storage
-async function uploadToS3(result: BackupResult): Promise<void> {
-  // This would be implemented with AWS SDK or similar
-  // Left as an exercise for specific implementation
-  console.log(`Would upload ${result.filename} to S3 here`)
+// Save the status of the backup for `llmn info`
+function saveLastBackup(config: BackupConfig, result: BackupResult, pruned: string[]): void {
+  const status = {
+    timestamp: result.timestamp,
+    filename: result.filename,
+    size: result.size,
+    success: result.success,
+    error: result.error ?? null,
+    retentionDays: config.retentionDays,
+    pruned,
+  }
+  fs.writeFileSync(
+    path.join(config.backupDir, LAST_BACKUP_FILE),
+    JSON.stringify(status, null, 2),
+  )
 }
 
 // Main backup function
 async function main() {
   try {
-    console.log('Starting Supabase backup process')
+    console.log('Starting postgres backup process')
 
     // Load configuration
     const config = loadConfig()
@@ -169,12 +193,11 @@ async function main() {
         } MB)`,
       )
 
-      // Cleanup old backups
-      await cleanupOldBackups(config)
-
-      // Uncomment to enable S3 uploads
-      // await uploadToS3(result);
+      // Only prune after a successful backup so there's always a recent backup to restore
+      const pruned = await cleanupOldBackups(config)
+      saveLastBackup(config, result, pruned)
     } else {
+      saveLastBackup(config, result, [])
       console.error('Backup failed!')
       process.exit(1)
     }
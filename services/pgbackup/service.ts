@@ -0,0 +1,85 @@
+import { Service } from '@/core/services/mod.ts'
+import { ensureDir, fileExists, path, readJson } from '@/lib/fs.ts'
+import { TryCatchResult } from '@/lib/try-catch.ts'
+
+// Status file written by build/backup.ts after each backup
+const LAST_BACKUP_FILE = 'last-backup.json'
+
+const DEFAULT_SCHEDULE = '@daily'
+const DEFAULT_RETENTION_DAYS = 7
+
+interface LastBackupStatus {
+  timestamp: string
+  filename: string
+  size: number
+  success: boolean
+  error: string | null
+  retentionDays: number
+  pruned: string[]
+}
+
+export class PgBackupService extends Service {
+  /**
+   * Dir the backups are saved to, mounted into the container as /backups
+   */
+  public get backupDir(): string {
+    return path.join(this._configInstance.sharedDir, 'backups', 'postgres')
+  }
+
+  /**
+   * Backup schedule from PGBACKUP_SCHEDULE in .env
+   */
+  public get schedule(): string {
+    return this._configInstance.env['PGBACKUP_SCHEDULE'] || DEFAULT_SCHEDULE
+  }
+
+  /**
+   * Days to keep backups from PGBACKUP_RETENTION_DAYS in .env
+   */
+  public get retentionDays(): number {
+    const days = parseInt(this._configInstance.env['PGBACKUP_RETENTION_DAYS'] || '', 10)
+    return isNaN(days) ? DEFAULT_RETENTION_DAYS : days
+  }
+
+  /**
+   * Create the backups dir before the container mounts it
+   *
+   * Otherwise docker creates the dir as root on Linux hosts.
+   */
+  override async prepareVolumes(
+    { silent = true }: { silent?: boolean } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    const results = await super.prepareVolumes({ silent })
+    await ensureDir(this.backupDir)
+    return results
+  }
+
+  /**
+   * Get the status of the last backup
+   *
+   * @returns {Promise<LastBackupStatus | null>} - The status or null if no backup has run yet
+   */
+  public async getLastBackup(): Promise<LastBackupStatus | null> {
+    const statusFile = path.join(this.backupDir, LAST_BACKUP_FILE)
+    if (!(await fileExists(statusFile)).data) {
+      return null
+    }
+    const result = await readJson<LastBackupStatus>(statusFile)
+    return result.success ? result.data ?? null : null
+  }
+
+  override async getInfo(): Promise<Record<string, unknown> | null> {
+    const lastBackup = await this.getLastBackup()
+    return {
+      schedule: this.schedule,
+      retentionDays: this.retentionDays,
+      backupDir: path.relative(Deno.cwd(), this.backupDir),
+      lastBackup: lastBackup?.timestamp ?? 'never',
+      lastBackupStatus: lastBackup ? (lastBackup.success ? 'success' : 'failed') : null,
+      lastBackupFile: lastBackup?.success ? lastBackup.filename : null,
+      lastBackupError: lastBackup?.error ?? null,
+    }
+  }
+}
+
+export default PgBackupService
@@ -0,0 +1,103 @@
+import { assert, assertEquals, assertStringIncludes } from 'jsr:@std/assert'
+import { describe, it } from 'jsr:@std/testing/bdd'
+import {
+  applyMigration,
+  checkConfigVersion,
+  CONFIG_VERSION,
+  getPendingMigrations,
+  IMigrationFiles,
+  migrateProject,
+} from '../lib/migrate.ts'
+import { MIGRATIONS } from '../migrations/mod.ts'
+import { migration as migration_0_3_0 } from '../migrations/0.3.0.ts'
+import { LLemonStackConfig } from '@/types'
+
+const CONFIG_0_1_0 = {
+  initialized: '',
+  timestamp: '2025-03-01T00:00:00.000Z',
+  version: '0.1.0',
+  projectName: 'old-project',
+  envFile: '.env',
+  dirs: {
+    config: '.llemonstack',
+    repos: '.llemonstack/repos',
+    import: 'import',
+    shared: 'shared',
+    volumes: 'volumes',
+  },
+} as unknown as LLemonStackConfig
+
+const ENV_0_1_0 = [
+  '# Services',
+  'ENABLE_N8N=false',
+  'export ENABLE_BROWSER_USE="true" # comment',
+  'ENABLE_OLLAMA=gpu-nvidia',
+  'ENABLE_UNKNOWN=true',
+  'OPENAI_API_KEY=sk-test',
+  '',
+].join('\n')
+
+describe('config migrations', () => {
+  it('ends with the config template version', () => {
+    assertEquals(MIGRATIONS.at(-1)?.version, CONFIG_VERSION)
+    const versions = MIGRATIONS.map((m) => m.version)
+    assertEquals(getPendingMigrations('0.0.0').map((m) => m.version), versions)
+  })
+
+  it('gets the pending migrations for a version', () => {
+    assertEquals(getPendingMigrations('0.2.0', [migration_0_3_0]), [migration_0_3_0])
+    assertEquals(getPendingMigrations('0.3.0', [migration_0_3_0]), [])
+  })
+
+  it('checks the config version', () => {
+    assert(checkConfigVersion('0.3.0', '0.3.0').success)
+    assertStringIncludes(checkConfigVersion('0.1.0', '0.3.0').error!.message, 'llmn migrate')
+    assertStringIncludes(checkConfigVersion('0.4.0', '0.3.0').error!.message, 'upgrade')
+  })
+
+  it('migrates 0.1.0 config and ENABLE_* vars to 0.3.0', () => {
+    const files: IMigrationFiles = { config: structuredClone(CONFIG_0_1_0), env: ENV_0_1_0 }
+    const changes = applyMigration(files, migration_0_3_0)
+
+    assertEquals(files.config.version, '0.3.0')
+    assertEquals(files.config.initialized, '2025-03-01T00:00:00.000Z')
+    assert(!('timestamp' in files.config))
+    assertEquals(files.config.dirs.services, [])
+    assertEquals(files.config.ports, { base: 0 })
+    assertEquals(files.config.services.n8n, { enabled: false, profiles: ['n8n'] })
+    assertEquals(files.config.services['browser-use'], { enabled: true })
+    assertEquals(files.config.services.ollama, { enabled: true, profiles: ['ollama-gpu-nvidia'] })
+    assertEquals(files.env, '# Services\nENABLE_UNKNOWN=true\nOPENAI_API_KEY=sk-test\n')
+    assert(changes.some((change) => change.includes('ENABLE_OLLAMA')))
+  })
+
+  it('backs up and saves the migrated files', async () => {
+    const dir = await Deno.makeTempDir()
+    const cwd = Deno.cwd()
+    try {
+      Deno.chdir(dir)
+      const configFile = `${dir}/.llemonstack/config.json`
+      await Deno.mkdir(`${dir}/.llemonstack`)
+      await Deno.writeTextFile(configFile, JSON.stringify(CONFIG_0_1_0))
+      await Deno.writeTextFile(`${dir}/.env`, ENV_0_1_0)
+
+      const check = await migrateProject(configFile, { check: true })
+      assert(check.success)
+      assertEquals(check.data?.steps.length, 1)
+      assertEquals(JSON.parse(await Deno.readTextFile(configFile)).version, '0.1.0')
+
+      const result = await migrateProject(configFile)
+      assert(result.success)
+      const backupDir = result.data!.steps[0].backupDir!
+      assertEquals(await Deno.readTextFile(`${backupDir}/.env`), ENV_0_1_0)
+      assertEquals(JSON.parse(await Deno.readTextFile(`${backupDir}/config.json`)).version, '0.1.0')
+      assertEquals(JSON.parse(await Deno.readTextFile(configFile)).version, CONFIG_VERSION)
+      assert(!(await Deno.readTextFile(`${dir}/.env`)).includes('ENABLE_N8N'))
+
+      assertEquals((await migrateProject(configFile)).data?.steps, [])
+    } finally {
+      Deno.chdir(cwd)
+      await Deno.remove(dir, { recursive: true })
+    }
+  })
+})
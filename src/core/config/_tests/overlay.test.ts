@@ -0,0 +1,123 @@
+import { assert, assertEquals } from 'jsr:@std/assert'
+import { describe, it } from 'jsr:@std/testing/bdd'
+import { Config } from '../config.ts'
+import {
+  checkEnvironmentName,
+  getConfigOverlayFile,
+  getEnvOverlayFile,
+  mergeConfigOverlay,
+  stripConfigOverlay,
+} from '../lib/overlay.ts'
+import { isValidServicesConfig } from '../lib/valid.ts'
+
+const BASE = {
+  projectName: 'project',
+  envFile: '.env',
+  services: {
+    n8n: { enabled: true, profiles: ['n8n'] },
+    ollama: { enabled: true, profiles: ['ollama-cpu'] },
+    flowise: { enabled: true },
+  },
+}
+
+const CI_OVERLAY = {
+  services: {
+    n8n: { enabled: false },
+    ollama: { profiles: ['ollama-host'] },
+    zep: { enabled: true },
+  },
+}
+
+describe('environment overlays', () => {
+  it('gets the overlay file names', () => {
+    assertEquals(
+      getConfigOverlayFile('/p/.llemonstack/config.json', 'ci'),
+      '/p/.llemonstack/config.ci.json',
+    )
+    assertEquals(getEnvOverlayFile('/p/.env', 'ci'), '/p/.env.ci')
+  })
+
+  it('checks the environment name', () => {
+    assert(checkEnvironmentName('ci').success)
+    assert(checkEnvironmentName('demo_2-eu').success)
+    assert(!checkEnvironmentName('../ci').success)
+    assert(!checkEnvironmentName('').success)
+  })
+
+  it('merges services and profiles without copying the whole config', () => {
+    const merged = mergeConfigOverlay(BASE, CI_OVERLAY)
+    assertEquals(merged.services, {
+      n8n: { enabled: false, profiles: ['n8n'] },
+      ollama: { enabled: true, profiles: ['ollama-host'] },
+      flowise: { enabled: true },
+      zep: { enabled: true },
+    })
+    assertEquals(merged.projectName, 'project')
+    assertEquals(BASE.services.n8n.enabled, true)
+  })
+
+  it('strips the overlay values and keeps other changes', () => {
+    const merged = mergeConfigOverlay(BASE, CI_OVERLAY)
+    merged.services.flowise.enabled = false
+    merged.services.n8n.profiles = ['n8n', 'n8n-worker']
+    merged.projectName = 'renamed'
+
+    assertEquals(stripConfigOverlay(merged, CI_OVERLAY, BASE), {
+      ...BASE,
+      projectName: 'renamed',
+      services: {
+        n8n: { enabled: true, profiles: ['n8n', 'n8n-worker'] },
+        ollama: { enabled: true, profiles: ['ollama-cpu'] },
+        flowise: { enabled: false },
+      },
+    })
+  })
+
+  it('rejects overlays with invalid services', () => {
+    assert(isValidServicesConfig(mergeConfigOverlay(BASE, CI_OVERLAY).services).success)
+    for (const overlay of [
+      { services: 'x' },
+      { services: { n8n: 'x' } },
+      { services: { n8n: { enabled: 'yes' } } },
+      { services: { ollama: { profiles: 'ollama-host' } } },
+    ]) {
+      const merged = mergeConfigOverlay(BASE, overlay)
+      assert(!isValidServicesConfig(merged.services).success, JSON.stringify(overlay))
+    }
+  })
+
+  it('uses env overlay values over encrypted secrets', async () => {
+    const dir = await Deno.makeTempDir()
+    const keys = ['OVERLAY_TEST_PASSWORD', 'OVERLAY_TEST_API_KEY', 'OVERLAY_TEST_TOKEN']
+    try {
+      await Deno.writeTextFile(`${dir}/.env`, 'OVERLAY_TEST_PASSWORD=\nOVERLAY_TEST_API_KEY=\n')
+      await Deno.writeTextFile(
+        `${dir}/.env.demo`,
+        'OVERLAY_TEST_PASSWORD=demo\nOVERLAY_TEST_TOKEN=\n',
+      )
+      // @ts-ignore - temporarily override for testing
+      delete Config.instance
+      const config = Config.getInstance()
+      // @ts-ignore - set the project state without initializing
+      config._config = { ...config._config, envFile: `${dir}/.env` }
+      // @ts-ignore - set the project state without initializing
+      config._environment = 'demo'
+      // @ts-ignore - set the project state without initializing
+      config._secrets = {
+        OVERLAY_TEST_PASSWORD: 'secret',
+        OVERLAY_TEST_API_KEY: 'secret-key',
+        OVERLAY_TEST_TOKEN: 'secret-token',
+      }
+
+      const env = await config.loadEnv()
+      assertEquals(env.OVERLAY_TEST_PASSWORD, 'demo')
+      assertEquals(env.OVERLAY_TEST_API_KEY, 'secret-key')
+      assertEquals(env.OVERLAY_TEST_TOKEN, 'secret-token')
+    } finally {
+      keys.forEach((key) => Deno.env.delete(key))
+      // @ts-ignore - reset the singleton for other tests
+      delete Config.instance
+      await Deno.remove(dir, { recursive: true })
+    }
+  })
+})
@@ -0,0 +1,114 @@
+import { assert, assertEquals, assertStringIncludes } from 'jsr:@std/assert'
+import { describe, it } from 'jsr:@std/testing/bdd'
+import { getYamlLine, getYamlLines, validateServiceYaml } from '../lib/schema.ts'
+
+const FILE = 'services/example/llemonstack.yaml'
+
+const VALID_YAML = `version: 0.2.0
+
+service: example
+name: Example
+description: Example service
+
+compose_file: docker-compose.yaml
+service_group: apps
+
+provides:
+  example: example
+
+depends_on:
+  postgres:
+    condition: service_healthy
+
+volumes_seeds:
+  - source: data
+    destination: example/data
+    from_repo: true
+
+exposes:
+  host:
+    dashboard:
+      name: Example
+      url: http://localhost:8080
+      info: |
+        Multiline info
+        with: a colon
+  internal:
+    api: http://example:8080
+
+init:
+  generate:
+    EXAMPLE_SECRET:
+      method: generateSecretKey
+      length: 32
+`
+
+describe('validateServiceYaml', () => {
+  it('accepts a valid service config', () => {
+    const result = validateServiceYaml(VALID_YAML, FILE)
+    assert(result.success)
+    assertEquals(result.data?.service, 'example')
+    assertEquals(result.messages.length, 0)
+  })
+
+  it('returns errors with the file and line for invalid values', () => {
+    const yaml = VALID_YAML.replace('method: generateSecretKey', 'method: generateSecret')
+    const result = validateServiceYaml(yaml, FILE)
+    assert(!result.success)
+    assertEquals(result.data, null)
+    const errors = result.messages.filter((message) => message.level === 'error')
+    assertEquals(errors.length, 1)
+    assertStringIncludes(errors[0].message, `${FILE}:36 init.generate.EXAMPLE_SECRET.method:`)
+  })
+
+  it('returns errors for invalid depends_on conditions', () => {
+    const yaml = VALID_YAML.replace('condition: service_healthy', 'condition: healthy')
+    const result = validateServiceYaml(yaml, FILE)
+    assert(!result.success)
+    assertStringIncludes(result.messages[0].message, `${FILE}:15 depends_on.postgres.condition:`)
+  })
+
+  it('returns warnings for unknown keys', () => {
+    const yaml = VALID_YAML.replace('  - source: data', '  - sourc: data\n    source: data')
+    const result = validateServiceYaml(yaml, FILE)
+    assert(result.success)
+    assertEquals(result.messages.length, 1)
+    assertEquals(result.messages[0].level, 'warning')
+    assertStringIncludes(result.messages[0].message, `${FILE}:18 volumes_seeds.0.sourc:`)
+  })
+
+  it('returns errors for missing required keys', () => {
+    const result = validateServiceYaml(VALID_YAML.replace('compose_file:', 'composefile:'), FILE)
+    assert(!result.success)
+    const messages = result.messages.map((message) => message.message)
+    assert(messages.some((message) => message.includes('Missing required key: compose_file')))
+    assert(messages.some((message) => message.includes('Unknown key: composefile')))
+  })
+
+  it('fails on invalid YAML', () => {
+    const result = validateServiceYaml('service: [example', FILE)
+    assert(!result.success)
+  })
+})
+
+describe('getYamlLines', () => {
+  it('maps keys and list items to line numbers', () => {
+    const lines = getYamlLines(VALID_YAML)
+    assertEquals(lines.get('service'), 3)
+    assertEquals(lines.get('volumes_seeds.0'), 18)
+    assertEquals(lines.get('volumes_seeds.0.destination'), 19)
+    assertEquals(lines.get('exposes.internal.api'), 31)
+  })
+
+  it('skips block scalars', () => {
+    const lines = getYamlLines(VALID_YAML)
+    assertEquals(lines.get('exposes.host.dashboard.info.with'), undefined)
+    assertEquals(lines.get('exposes.internal'), 30)
+  })
+
+  it('falls back to the closest parent line', () => {
+    const lines = getYamlLines(VALID_YAML)
+    assertEquals(getYamlLine(lines, ['exposes', 'host', 'dashboard', 'missing']), 24)
+    assertEquals(getYamlLine(lines, ['missing']), null)
+  })
+})
@@ -1,7 +1,21 @@
 import { tryRunCommand } from '@/lib/command.ts'
-import { getDockerNetworks, prepareDockerNetwork, removeDockerNetwork } from '@/lib/docker.ts'
-import { loadEnv, updateEnv } from '@/lib/env.ts'
+import {
+  getDockerNetworks,
+  getDockerVolumes,
+  prepareDockerNetwork,
+  removeDockerNetwork,
+} from '@/lib/docker.ts'
+import { OFFLINE_ENV } from '@/lib/bundle.ts'
+import { clearEnvValues, loadEnv, readEnvDocument, unsetEnv, updateEnv } from '@/lib/env.ts'
 import * as fs from '@/lib/fs.ts'
+import { CONTAINER_RUNTIME_ENV, CONTAINER_RUNTIMES, type ContainerRuntimeName } from '@/lib/runtime.ts'
+import {
+  getSecretsKeyFile,
+  getSecretsPassphrase,
+  readSecretsFile,
+  SECRET_KEY_PATTERN,
+  writeSecretsFile,
+} from '@/lib/secrets.ts'
 import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
 import { isTruthy } from '@/lib/utils/compare.ts'
 import { LogLevel } from '@/relayer/logger.ts'
@@ -9,7 +23,18 @@ import { LLemonStackConfig } from '@/types'
 import packageJson from '@packageJson' with { type: 'json' }
 import configTemplate from '@templateConfig' with { type: 'json' }
 import Host from './lib/host.ts'
-import { isValidConfig } from './lib/valid.ts'
+import { checkConfigVersion } from './lib/migrate.ts'
+import {
+  checkEnvironmentName,
+  ConfigOverlay,
+  ENVIRONMENT_ENV,
+  getConfigOverlayFile,
+  getEnvOverlayFile,
+  mergeConfigOverlay,
+  stripConfigOverlay,
+} from './lib/overlay.ts'
+import { ProjectRegistry } from './lib/registry.ts'
+import { isValidConfig, isValidServicesConfig } from './lib/valid.ts'
 
 // Absolute path to root of install dir
 const INSTALL_DIR = fs.path.join(
@@ -37,6 +62,7 @@ export class ConfigBase {
   //
 
   protected _envPrepared: boolean = false
+  protected _dryRun: boolean = false // When true, config.json and .env changes are not saved
 
   protected _host: Host = Host.getInstance()
 
@@ -46,12 +72,19 @@ export class ConfigBase {
 
   // Caches
   protected _env: Record<string, string> = {}
+  protected _secrets: Record<string, string> = {} // Decrypted secrets, only kept in memory
+  protected _secretsPassphrase: string | null = null
   protected _initializeResult: TryCatchResult<boolean, Error> | null = null
 
   // Base configuration
   protected _configDir: string = ''
   protected _configFile: string = ''
 
+  // Environment overlay, see lib/overlay.ts
+  protected _environment: string | null = null
+  protected _configOverlay: ConfigOverlay | null = null
+  protected _baseConfig: LLemonStackConfig | null = null // Config before the overlay was merged
+
   readonly installDir = INSTALL_DIR
 
   protected constructor() {
@@ -96,6 +129,112 @@ export class ConfigBase {
     return fs.path.resolve(Deno.cwd(), this._config.envFile)
   }
 
+  /**
+   * Get the environment the project is running in, set with --env or LLEMONSTACK_ENV
+   * @returns {string | null} Null if no environment overlay is used
+   */
+  get environment(): string | null {
+    return this._environment
+  }
+
+  /**
+   * Get the overlay config file of the environment, e.g. .llemonstack/config.ci.json
+   * @returns {string | null} Null if no environment overlay is used
+   */
+  get configOverlayFile(): string | null {
+    return this._environment ? getConfigOverlayFile(this.configFile, this._environment) : null
+  }
+
+  /**
+   * Get the overlay env file of the environment, e.g. .env.ci
+   * @returns {string | null} Null if no environment overlay is used
+   */
+  get envOverlayFile(): string | null {
+    return this._environment ? getEnvOverlayFile(this.envFile, this._environment) : null
+  }
+
+  /**
+   * Get the project base port, added to all host ports exposed by services
+   *
+   * 0 uses the default ports in the services compose files.
+   * @returns {number}
+   */
+  get basePort(): number {
+    return this._config.ports?.base ?? 0
+  }
+
+  /**
+   * Get the path to the encrypted secrets file
+   * @returns {string}
+   */
+  get secretsFile(): string {
+    return fs.path.join(this.configDir, 'secrets.enc')
+  }
+
+  /**
+   * Get the path to the image digests lockfile
+   * @returns {string}
+   */
+  get imagesLockFile(): string {
+    return fs.path.join(this.configDir, 'images.lock')
+  }
+
+  /**
+   * Get the path to the key file used to decrypt the secrets file
+   *
+   * Stored in the global LLemonStack dir, outside of the project.
+   * @returns {string}
+   */
+  get secretsKeyFile(): string {
+    return getSecretsKeyFile(ProjectRegistry.globalDir, this.projectName)
+  }
+
+  /**
+   * Check if secrets are stored in the encrypted secrets file instead of .env
+   * @returns {boolean}
+   */
+  get secretsEnabled(): boolean {
+    return this._config.secrets?.store === 'encrypted'
+  }
+
+  get secrets(): Record<string, string> {
+    return { ...this._secrets }
+  }
+
+  /**
+   * Get the container runtime to use from the LLEMONSTACK_CONTAINER_RUNTIME env var or config.json
+   *
+   * @returns {ContainerRuntimeName | 'auto'} auto detects the first installed runtime
+   */
+  get containerRuntime(): ContainerRuntimeName | 'auto' {
+    const runtime = Deno.env.get(CONTAINER_RUNTIME_ENV) || this._config.runtime || 'auto'
+    return CONTAINER_RUNTIMES.includes(runtime as ContainerRuntimeName)
+      ? runtime as ContainerRuntimeName
+      : 'auto'
+  }
+
+  /**
+   * Check if the project runs offline from a loaded bundle
+   *
+   * Set with the LLEMONSTACK_OFFLINE env var or the `offline` key in config.json.
+   * @returns {boolean}
+   */
+  get offline(): boolean {
+    const env = Deno.env.get(OFFLINE_ENV)
+    return env !== undefined ? isTruthy(env) : this._config.offline === true
+  }
+
+  public async setOffline(
+    offline: boolean,
+    { save = true }: { save?: boolean } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    this._config.offline = offline
+    if (save) {
+      return await this.save()
+    }
+    return success<boolean>(true)
+  }
+
   get dockerNetworkName(): string {
     return `${this.projectName}_network`
   }
@@ -125,6 +264,25 @@ export class ConfigBase {
     return dirs
   }
 
+  /**
+   * Add a custom services dir to the project config
+   *
+   * Call save() to update the config file.
+   * @param {string} dir - The services dir, saved relative to the project dir
+   * @returns {boolean} True if the dir was added, false if it's already in the config
+   */
+  public addServicesDir(dir: string): boolean {
+    const absDir = fs.path.resolve(Deno.cwd(), dir)
+    if (this.servicesDirs.includes(absDir)) {
+      return false
+    }
+    const configDirs = this._config.dirs.services
+    const dirs = !configDirs ? [] : Array.isArray(configDirs) ? configDirs : [configDirs]
+    // Custom dirs take priority over the core services dir
+    this._config.dirs.services = [...dirs, fs.path.relative(Deno.cwd(), absDir) || '.']
+    return true
+  }
+
   get importDir(): string {
     return fs.path.resolve(Deno.cwd(), this._config.dirs.import)
   }
@@ -184,9 +342,10 @@ export class ConfigBase {
    */
   public async initialize(
     configFile?: string,
-    { logLevel = 'info', init = false }: {
+    { logLevel = 'info', init = false, environment }: {
       logLevel?: LogLevel // TODO: move logLevel to Relayer
       init?: boolean // If false, return error if config file is invalid
+      environment?: string // Environment overlay to use, defaults to LLEMONSTACK_ENV
     } = {},
   ): Promise<TryCatchResult<boolean, Error>> {
     // If previously cached initialize result, return it
@@ -229,6 +388,15 @@ export class ConfigBase {
       result.error = readResult.error
     }
 
+    // Configs from other versions need to be migrated with `llmn migrate` first
+    if (readResult.data) {
+      const versionResult = checkConfigVersion(this._config.version, this._configTemplate.version)
+      if (!versionResult.success) {
+        result.collect([versionResult])
+        return failure(`Config version mismatch: ${this.configFile}`, result, false)
+      }
+    }
+
     // Check if project config is valid
     const isValidResult = this.isValidConfig()
     if (!isValidResult.success) {
@@ -242,6 +410,24 @@ export class ConfigBase {
       result.addMessage('info', 'Successfully updated config.json')
     }
 
+    // Layer the environment overlay on top of the project config
+    environment = environment || Deno.env.get(ENVIRONMENT_ENV)
+    if (environment) {
+      result.collect([await this.loadEnvironment(environment)])
+      if (!result.success) {
+        return failure(`Unable to load environment: ${environment}`, result, false)
+      }
+    }
+
+    // Decrypt secrets before loading .env so .env values can reference them
+    if (this.secretsEnabled) {
+      const secretsResult = await this.loadSecrets()
+      result.collect([secretsResult])
+      if (!secretsResult.success) {
+        return failure(`Unable to decrypt secrets: ${this.secretsFile}`, result, false)
+      }
+    }
+
     // Load .env file
     await this.loadEnv()
 
@@ -306,6 +492,51 @@ export class ConfigBase {
     return result
   }
 
+  /**
+   * Layer the config overlay of an environment on top of the project config
+   *
+   * The env overlay is loaded by loadEnv. The config overlay is optional if the environment
+   * only has an env overlay.
+   * @param {string} name - The environment name, e.g. ci
+   * @returns {Promise<TryCatchResult<boolean>>}
+   */
+  protected async loadEnvironment(name: string): Promise<TryCatchResult<boolean>> {
+    const nameResult = checkEnvironmentName(name)
+    if (!nameResult.success) {
+      return nameResult
+    }
+    this._environment = name
+    const overlayFile = this.configOverlayFile!
+
+    const readResult = await fs.readJson<ConfigOverlay>(overlayFile)
+    if (readResult.data) {
+      // The overlay isn't patched from the template, check the merged config before using it
+      const merged = mergeConfigOverlay(this._config, readResult.data)
+      const invalidResult = [this.isValidConfig(merged), isValidServicesConfig(merged.services)]
+        .find((result) => !result.success)
+      if (invalidResult) {
+        return failure<boolean>(
+          `Invalid config overlay: ${fs.path.relative(Deno.cwd(), overlayFile)}`,
+          invalidResult,
+          false,
+        )
+      }
+      this._baseConfig = this._config
+      this._configOverlay = readResult.data
+      this._config = merged
+    } else if (!(readResult.error instanceof Deno.errors.NotFound)) {
+      return failure<boolean>(`Unable to read config overlay: ${overlayFile}`, readResult, false)
+    } else if (!(await fs.fileExists(this.envOverlayFile!)).data) {
+      return failure<boolean>(
+        `Environment "${name}" not found, create ${fs.path.relative(Deno.cwd(), overlayFile)} ` +
+          `or ${fs.path.relative(Deno.cwd(), this.envOverlayFile!)}`,
+        success(null),
+        false,
+      )
+    }
+    return success<boolean>(true).addMessage('info', `Using environment: ${name}`)
+  }
+
   /**
    * Load services
    * @returns {Promise<TryCatchResult<Record<string, Service>>>}
@@ -337,8 +568,33 @@ export class ConfigBase {
       expand?: boolean
     } = {},
   ): Promise<Record<string, string>> {
+    // The env overlay of the environment overrides the project .env file
+    const overlayPath = envPath === this.envFile ? this.envOverlayFile : null
+
+    // Values set in the env overlay take precedence over secrets, e.g. fixed demo credentials
+    const overlay = overlayPath ? (await readEnvDocument(overlayPath)).data?.toObject() || {} : {}
+    const secrets = Object.fromEntries(
+      Object.entries(this._secrets).filter(([key]) => !overlay[key]),
+    )
+
+    // Export decrypted secrets to Deno.env so ${VAR} references in .env are expanded
+    if (envPath) {
+      Object.entries(secrets).forEach(([key, value]) => {
+        if (reload || !Deno.env.get(key)) {
+          Deno.env.set(key, value)
+        }
+      })
+    }
+
     // Load .env file or use a clone of the current env vars
-    const env = (!envPath) ? { ...this._env } : await loadEnv({ envPath, reload, expand })
+    const env = (!envPath)
+      ? { ...this._env }
+      : await loadEnv({ envPath, overlayPath, reload, expand })
+
+    // Secrets take precedence over blank or stale values in .env
+    if (envPath) {
+      Object.assign(env, secrets)
+    }
 
     // Populate project name from config for services & docker to use
     env.LLEMONSTACK_PROJECT_NAME = this.projectName
@@ -346,17 +602,76 @@ export class ConfigBase {
     return env
   }
 
+  /**
+   * Check if a project name is available for the project in the current dir
+   *
+   * A name is not available if another project in the global registry uses it, or if Docker
+   * volumes already exist for the name. Projects with the same name share Docker volumes.
+   *
+   * @param name - The project name to check
+   * @returns {Promise<TryCatchResult<boolean>>} Failure if the name is not available
+   */
+  public async checkProjectName(name: string): Promise<TryCatchResult<boolean>> {
+    const result = success<boolean>(true)
+    const projectDir = Deno.cwd()
+
+    const registry = ProjectRegistry.getInstance()
+    const registryResult = await registry.load()
+    if (!registryResult.success) {
+      result.addMessage('warning', 'Unable to load the global project registry')
+    }
+
+    const otherProjects = registry.getByProjectName(name, { excludePath: projectDir })
+    if (otherProjects.length > 0) {
+      return failure<boolean>(
+        `Project name '${name}' is used by another project: ${otherProjects[0].path}`,
+        result,
+        false,
+      )
+    }
+
+    // Volumes for the project's own name are expected, e.g. when reinitializing a project
+    const ownName = registry.get(projectDir)?.projectName === name ||
+      (this.isProjectInitialized() && this.projectName === name)
+    if (!ownName) {
+      const volumesResult = await getDockerVolumes(name)
+      if (!volumesResult.success) {
+        result.addMessage('warning', 'Unable to check Docker volumes for the project name')
+      } else if (volumesResult.data?.length) {
+        return failure<boolean>(
+          `Docker volumes already exist for project name '${name}', ` +
+            `e.g. ${volumesResult.data[0]}`,
+          result,
+          false,
+        )
+      }
+    }
+
+    return result
+  }
+
   /**
    * Set the project name
    * @param name - The new project name
    * @param {Object} options - Options object
    * @param {boolean} options.save - Save the config after updating the project name
    * @param {boolean} options.updateEnv - Update the LLEMONSTACK_PROJECT_NAME environment variable
+   * @param {boolean} options.force - Skip checking if the name is used by another project
    */
   public async setProjectName(
     name: string,
-    { save = true, updateEnv = true }: { save?: boolean; updateEnv?: boolean } = {},
+    { save = true, updateEnv = true, force = false }: {
+      save?: boolean
+      updateEnv?: boolean
+      force?: boolean
+    } = {},
   ): Promise<TryCatchResult<boolean>> {
+    if (!force) {
+      const checkResult = await this.checkProjectName(name)
+      if (!checkResult.success) {
+        return checkResult
+      }
+    }
     const envKey = 'LLEMONSTACK_PROJECT_NAME'
     if (updateEnv && this._env[envKey]) {
       this.setEnvKey(envKey, name)
@@ -368,11 +683,29 @@ export class ConfigBase {
     return success<boolean>(true)
   }
 
+  /**
+   * Set the project base port
+   * @param {number} port - The base port, see BASE_PORT_STEP in lib/ports.ts
+   */
+  public async setBasePort(
+    port: number,
+    { save = true }: { save?: boolean } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    this._config.ports = { ...this._config.ports, base: port }
+    if (save) {
+      return await this.save()
+    }
+    return success<boolean>(true)
+  }
+
   /**
    * Save the project config to the config file
    * @returns {Promise<TryCatchResult<boolean>>}
    */
   public async save(): Promise<TryCatchResult<boolean>> {
+    if (this._dryRun) {
+      return success<boolean>(true).addMessage('debug', 'Dry run, config not saved')
+    }
     if (!fs.isInsideCwd(this.configFile)) {
       return new TryCatchResult<boolean, Error>({
         data: false,
@@ -383,11 +716,32 @@ export class ConfigBase {
       })
     }
 
-    return await fs.saveJson(this.configFile, this._config)
+    // Environment overlay values are not saved to config.json
+    const config = this._configOverlay && this._baseConfig
+      ? stripConfigOverlay(this._config, this._configOverlay, this._baseConfig)
+      : this._config
+    return await fs.saveJson(this.configFile, config)
+  }
+
+  /**
+   * Dry run mode, changes to config.json, .env and the secrets file are kept in memory
+   *
+   * Used to plan the effect of a command before running it, see scripts/plan.ts
+   */
+  get dryRun(): boolean {
+    return this._dryRun
+  }
+
+  public setDryRun(dryRun: boolean): void {
+    this._dryRun = dryRun
   }
 
+  /**
+   * Check if the project config needs to be migrated with `llmn migrate`
+   * @returns {boolean}
+   */
   public isOutdatedConfig(): boolean {
-    return this._config.version !== ConfigBase.llemonstackVersion
+    return !checkConfigVersion(this._config.version, this._configTemplate.version).success
   }
 
   /**
@@ -413,7 +767,137 @@ export class ConfigBase {
     })
     this._setEnv(env) // Update the in memory env object
 
-    return await updateEnv(this.envFile, vars) // Update the .env file
+    if (this._dryRun) {
+      return success<boolean>(true).addMessage('debug', 'Dry run, .env not saved')
+    }
+
+    if (!this.secretsEnabled) {
+      return await updateEnv(this.envFile, vars) // Update the .env file
+    }
+
+    // Save secrets to the encrypted secrets file, everything else to .env
+    const secretVars = Object.fromEntries(
+      Object.entries(vars).filter(([key, value]) => value && this.isSecretKey(key)),
+    )
+    const envVars = Object.fromEntries(
+      Object.entries(vars).filter(([key]) => !(key in secretVars)),
+    )
+    const result = success<boolean>(true)
+    if (Object.keys(secretVars).length) {
+      result.collect([await this.saveSecrets({ ...this._secrets, ...secretVars })])
+      // Blank out any plaintext values left in .env
+      result.collect([await clearEnvValues(this.envFile, Object.keys(secretVars))])
+    }
+    if (Object.keys(envVars).length) {
+      result.collect([await updateEnv(this.envFile, envVars)])
+    }
+    return result
+  }
+
+  /**
+   * Remove vars from the .env file and the encrypted secrets file
+   *
+   * Also removes the vars from the in memory env object and Deno.env.
+   * @param keys - The vars to remove
+   * @returns {Promise<TryCatchResult<boolean>>}
+   */
+  public async unsetEnvFileVars(keys: string[]): Promise<TryCatchResult<boolean>> {
+    const env = { ...this._env } // Clone _env object to remove immutability
+    keys.forEach((key) => {
+      delete env[key]
+      Deno.env.delete(key)
+    })
+    this._setEnv(env) // Update the in memory env object
+
+    if (this._dryRun) {
+      return success<boolean>(true).addMessage('debug', 'Dry run, .env not saved')
+    }
+
+    const result = success<boolean>(true)
+    if (keys.some((key) => key in this._secrets)) {
+      const secrets = Object.fromEntries(
+        Object.entries(this._secrets).filter(([key]) => !keys.includes(key)),
+      )
+      result.collect([await this.saveSecrets(secrets)])
+    }
+    result.collect([await unsetEnv(this.envFile, keys)])
+    return result
+  }
+
+  /**
+   * Check if an env var should be stored in the encrypted secrets file
+   *
+   * Override in subclass to include the secrets generated by services.
+   * @param {string} key - The env var name
+   * @returns {boolean}
+   */
+  public isSecretKey(key: string): boolean {
+    return key in this._secrets || SECRET_KEY_PATTERN.test(key)
+  }
+
+  /**
+   * Decrypt the secrets file into memory
+   *
+   * Prompts for the passphrase in interactive shells if no key file or env var is set.
+   * @returns {Promise<TryCatchResult<Record<string, string>>>}
+   */
+  public async loadSecrets(
+    { prompt = Deno.stdin.isTerminal() }: { prompt?: boolean } = {},
+  ): Promise<TryCatchResult<Record<string, string>>> {
+    if (!this._secretsPassphrase) {
+      const passphraseResult = await getSecretsPassphrase(this.secretsKeyFile, { prompt })
+      if (!passphraseResult.success || !passphraseResult.data) {
+        return failure<Record<string, string>>('Secrets passphrase not found', passphraseResult)
+      }
+      this._secretsPassphrase = passphraseResult.data
+    }
+    const result = await readSecretsFile(this.secretsFile, this._secretsPassphrase)
+    if (!result.success || !result.data) {
+      // Clear the passphrase so it can be re-entered
+      this._secretsPassphrase = null
+      return result
+    }
+    this._secrets = result.data
+    return result
+  }
+
+  /**
+   * Encrypt and save secrets to the secrets file, replacing all existing secrets
+   *
+   * @param {Record<string, string>} secrets - All of the project secrets
+   * @param {Object} options - Options object
+   * @param {string} options.passphrase - Set a new passphrase for the secrets file
+   * @returns {Promise<TryCatchResult<boolean>>}
+   */
+  public async saveSecrets(
+    secrets: Record<string, string>,
+    { passphrase }: { passphrase?: string } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    if (passphrase) {
+      this._secretsPassphrase = passphrase
+    }
+    if (!this._secretsPassphrase) {
+      const passphraseResult = await getSecretsPassphrase(this.secretsKeyFile, {
+        prompt: Deno.stdin.isTerminal(),
+      })
+      if (!passphraseResult.success || !passphraseResult.data) {
+        return failure<boolean>('Secrets passphrase not found', passphraseResult, false)
+      }
+      this._secretsPassphrase = passphraseResult.data
+    }
+    const result = await writeSecretsFile(this.secretsFile, secrets, this._secretsPassphrase)
+    if (result.success) {
+      this._secrets = { ...secrets }
+    }
+    return result
+  }
+
+  /**
+   * Set where secrets are stored, call save() to update the config file
+   * @param {'env' | 'encrypted'} store - Store secrets in .env or the encrypted secrets file
+   */
+  public setSecretsStore(store: 'env' | 'encrypted'): void {
+    this._config.secrets = { ...this._config.secrets, store }
   }
 
   /**
@@ -460,9 +944,7 @@ export class ConfigBase {
 
     // Create the docker network
     const networkResult = await prepareDockerNetwork(this.dockerNetworkName)
-    if (!networkResult.success && networkResult.data?.stderr.includes('already exists')) {
-      results.addMessage('info', 'Docker network already exists, skipping')
-    } else if (!networkResult.success) {
+    if (!networkResult.success) {
       results.addMessage('error', 'Failed to prepare docker network')
       this._envPrepared = false
     }
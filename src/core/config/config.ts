@@ -1,10 +1,29 @@
 import { Service, ServicesMap } from '@/core/services/mod.ts'
+import { getDependencyWaves } from '@/core/services/utils/mod.ts'
 import { runCommand } from '@/lib/command.ts'
-import { success, TryCatchResult } from '@/lib/try-catch.ts'
+import { getPortsFromComposeYaml } from '@/lib/compose.ts'
+import { dockerComposePs, type DockerComposePsResult, dockerEnv } from '@/lib/docker.ts'
+import { mergeEnvFragments } from '@/lib/env.ts'
+import { readTextFile } from '@/lib/fs.ts'
+import { getPinnedImages, type IImagesLock, readImagesLock } from '@/lib/images-lock.ts'
+import {
+  findAvailableHostPort,
+  getHostPort,
+  getHostPortEnvVar,
+  isHostPortAvailable,
+} from '@/lib/ports.ts'
+import { tryGetContainerRuntime } from '@/lib/runtime.ts'
+import { failure, success, tryCatch, TryCatchResult } from '@/lib/try-catch.ts'
 import { LogLevel } from '@/relayer/logger.ts'
 import { Relayer } from '@/relayer/relayer.ts'
-import { InterfaceRelayerInstance, IServiceConfigState, IServicesGroups } from '@/types'
+import {
+  InterfaceRelayerInstance,
+  IServiceHostPort,
+  IServiceConfigState,
+  IServicesGroups,
+} from '@/types'
 import { ConfigBase } from './base.ts'
+import { ProjectRegistry } from './lib/registry.ts'
 import { loadServices } from './lib/load.ts'
 
 /**
@@ -57,6 +76,9 @@ export class Config extends ConfigBase {
   // Services that are enabled when a dependent service is enabled
   private _autoEnabledServices: ServicesMap = new ServicesMap()
 
+  // Image digests from .llemonstack/images.lock, loaded in prepareEnv
+  private _imagesLock: IImagesLock | null = null
+
   //
   // Public Properties
   //
@@ -88,36 +110,71 @@ export class Config extends ConfigBase {
    */
   override async initialize(
     configFile?: string,
-    { logLevel = 'info', init = false, relayer }: {
+    { logLevel = 'info', init = false, relayer, environment }: {
       logLevel?: LogLevel // TODO: move logLevel to Relayer
       init?: boolean // If false, return error if config file is invalid
       relayer?: InstanceType<typeof Relayer>
+      environment?: string // Environment overlay to use, defaults to LLEMONSTACK_ENV
     } = {},
   ): Promise<TryCatchResult<boolean, Error>> {
     if (relayer) {
       this._relayer = relayer
     }
 
-    return await super.initialize(configFile, { logLevel, init })
+    return await super.initialize(configFile, { logLevel, init, environment })
   }
 
   /**
    * Check if all prerequisites are installed
    */
   public async checkPrerequisites(): Promise<void> {
-    // Commands will throw an error if the prerequisite is not installed
-    try {
-      await runCommand('docker --version', { silent: true })
-      await runCommand('docker compose version', { silent: true })
-      await runCommand('git --version', { silent: true })
-    } catch (error) {
-      this.show.error(
-        error as Error,
-      )
+    const runtimeResult = await tryGetContainerRuntime(this.containerRuntime)
+    if (!runtimeResult.success || !runtimeResult.data) {
+      this.show.logMessages(runtimeResult.messages)
       this.show.fatal(
-        'Prerequisites not met, please install the required dependencies and try again.',
+        'Prerequisites not met, please install Docker, Podman or nerdctl and try again.',
+      )
+      return
+    }
+    const runtime = runtimeResult.data
+    this.show.info(
+      `Using ${runtime.name} ${runtime.version} with ${runtime.compose.join(' ')} ` +
+        runtime.composeVersion,
+    )
+    if (!runtime.features.profiles) {
+      this.show.warn(
+        `${runtime.compose.join(' ')} doesn't support --profile, ` +
+          'upgrade it if services fail to start',
+      )
+    }
+    if (!runtime.features.overrideTags && this.basePort) {
+      this.show.warn(
+        `${runtime.compose.join(' ')} doesn't support !override tags, base ports won't work`,
       )
     }
+
+    if (this.offline) {
+      // Offline projects use the repos and images from the loaded bundle, git isn't needed
+      this.show.info('Running offline, repos are not cloned and images are not pulled')
+      if (!runtime.features.pullPolicy) {
+        this.show.warn(
+          `${runtime.compose.join(' ')} doesn't support --pull never, ` +
+            'missing images may be pulled',
+        )
+      }
+    } else {
+      // Commands will throw an error if the prerequisite is not installed
+      try {
+        await runCommand('git --version', { silent: true })
+      } catch (error) {
+        this.show.error(
+          error as Error,
+        )
+        this.show.fatal(
+          'Prerequisites not met, please install the required dependencies and try again.',
+        )
+      }
+    }
     this.show.info('✔️ All prerequisites are installed')
   }
 
@@ -139,6 +196,17 @@ export class Config extends ConfigBase {
       // After all services are loaded, update the dependencies maps
       this.updateDependencies()
       this.updateAutoEnabledServices()
+
+      // Warn about depends_on keys that aren't provided by any service, usually a typo
+      for (const [_, service] of this.getAllServices()) {
+        service.depends_on.filter((dependency) => !this._providers.has(dependency))
+          .forEach((dependency) => {
+            loadResults.addMessage(
+              'warning',
+              `${service.name} depends on ${dependency}, but no service provides it`,
+            )
+          })
+      }
     }
     result.data = loadResults.success
     result.collect([loadResults])
@@ -195,6 +263,40 @@ export class Config extends ConfigBase {
     }
   }
 
+  /**
+   * Get the services a service depends on
+   *
+   * Resolves each depends_on key in llemonstack.yaml to the service that provides it.
+   * e.g. n8n depends on postgres, which is provided by supabase
+   *
+   * @param {Service} service - The service to get the dependencies for
+   * @returns {ServicesMap} The services that provide the dependencies
+   */
+  public getServiceDependencies(service: Service): ServicesMap {
+    const dependencies = new ServicesMap()
+    service.depends_on.forEach((dependency) => {
+      const provider = this.getServiceByProvides(dependency)
+      if (provider && provider.id !== service.id) {
+        dependencies.addService(provider)
+      }
+    })
+    return dependencies
+  }
+
+  /**
+   * Get the order to start services in based on the dependency graph
+   *
+   * Each wave only depends on services in previous waves.
+   *
+   * @param {ServicesMap} services - The services to sort, defaults to enabled services
+   * @returns {TryCatchResult<ServicesMap[]>} The services grouped into waves in start order
+   */
+  public getStartOrder(
+    services: ServicesMap = this.getEnabledServices(),
+  ): TryCatchResult<ServicesMap[]> {
+    return getDependencyWaves(services, (service) => this.getServiceDependencies(service))
+  }
+
   public getServiceDependents(service: Service): ServicesMap {
     let dependents = this._dependencies.get(service.id)
     if (!dependents) {
@@ -298,6 +400,18 @@ export class Config extends ConfigBase {
     return this.getServiceByName(serviceName)?.isEnabled() || null
   }
 
+  /**
+   * Check if an env var should be stored in the encrypted secrets file
+   *
+   * Includes the secrets generated by any service, e.g. init.generate env vars.
+   * @param {string} key - The env var name
+   * @returns {boolean}
+   */
+  override isSecretKey(key: string): boolean {
+    return super.isSecretKey(key) ||
+      this._services.toArray().some((service) => key in service.getGeneratedSecrets())
+  }
+
   public getAllServices(): ServicesMap {
     return this._services
   }
@@ -329,6 +443,53 @@ export class Config extends ConfigBase {
     return this._providers.get(provides)?.[0] || null
   }
 
+  /**
+   * Get a service and its containers by service name, provides key, or container name
+   *
+   * A provides key resolves to the container that provides it. Services that use profiles
+   * for container names, e.g. ollama, resolve to their active containers instead.
+   *
+   * @param {string} name - The service name, provides key, e.g. postgres, or container name
+   * @returns {[Service, string[]] | null} The service and container names or null if not found
+   */
+  public getServiceContainers(name: string): [Service, string[]] | null {
+    const service = this.getServiceByName(name)
+    if (service) {
+      return [service, service.containerNames]
+    }
+
+    const [provider, container] = this._providers.get(name) || []
+    if (provider && container) {
+      const containers = provider.containerNames
+      return [provider, containers.includes(container) ? [container] : containers]
+    }
+
+    const owner = this._services.toArray().find((s) => s.containerNames.includes(name))
+    return owner ? [owner, [name]] : null
+  }
+
+  /**
+   * Get the generated compose override file for a service compose file
+   *
+   * Overrides namespace container names and offset host ports by the project base port.
+   * See prepareComposeOverride in services/utils.
+   *
+   * @param {string} composeFile - The service compose file
+   * @returns {string | null} The override file or null if it hasn't been generated
+   */
+  public getComposeOverrideFile(composeFile: string): string | null {
+    const service = this._services.toArray().find((s) => s.composeFile === composeFile)
+    if (!service) {
+      return null
+    }
+    try {
+      Deno.statSync(service.composeOverrideFile)
+      return service.composeOverrideFile
+    } catch (_error) {
+      return null
+    }
+  }
+
   /**
    * Get a service by service identifier
    * @param {string} service - The service key in llemonstack.yaml, or service.id
@@ -378,6 +539,35 @@ export class Config extends ConfigBase {
     }
   }
 
+  /**
+   * Add the env vars of enabled services to the .env file
+   *
+   * Merges the .env.example of each service into .env. Only keys missing from .env are added,
+   * values already set in .env are never changed.
+   *
+   * @param {ServicesMap} services - The services to merge, defaults to the enabled services
+   * @returns {Promise<TryCatchResult<string[]>>} The keys that were added
+   */
+  public async mergeServicesEnv(
+    services: ServicesMap = this.getEnabledServices(),
+  ): Promise<TryCatchResult<string[]>> {
+    const fragments: { content: string; source: string }[] = []
+    for (const service of services.toArray()) {
+      const fragmentResult = await readTextFile(service.envExampleFile)
+      if (!fragmentResult.success || !fragmentResult.data) continue
+      fragments.push({ content: fragmentResult.data, source: `${service.service} .env.example` })
+    }
+
+    const result = await mergeEnvFragments(this.envFile, fragments, { dryRun: this._dryRun })
+    if (!result.success || !result.data?.length || this._dryRun) {
+      return result
+    }
+
+    // Load the new vars, values already in the env are kept
+    await this.loadEnv()
+    return result
+  }
+
   /**
    * Save the project config to the config file
    * @returns {Promise<TryCatchResult<boolean>>}
@@ -397,7 +587,46 @@ export class Config extends ConfigBase {
       this._config.services[service.service] = serviceConfig
     })
 
-    return await super.save()
+    if (this.dryRun) {
+      return success<boolean>(true).addMessage('debug', 'Dry run, config not saved')
+    }
+
+    const result = await super.save()
+
+    // Keep the global project registry in sync with the project config
+    if (result.success && this.isProjectInitialized()) {
+      const registryResult = await this.updateProjectRegistry()
+      if (!registryResult.success) {
+        result.addMessage('warning', 'Unable to update the global project registry')
+      }
+    }
+
+    return result
+  }
+
+  /**
+   * Add or update the project in the global project registry
+   *
+   * @param {Object} options - Options object
+   * @param {boolean} options.started - Set the last started time to now
+   * @returns {Promise<TryCatchResult<boolean>>}
+   */
+  public async updateProjectRegistry(
+    { started = false }: { started?: boolean } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    const registry = ProjectRegistry.getInstance()
+    const loadResult = await registry.load()
+    if (!loadResult.success) {
+      return failure<boolean>('Unable to load the global project registry', loadResult, false)
+    }
+    return await registry.register({
+      projectName: this.projectName,
+      path: Deno.cwd(),
+      configFile: this.configFile,
+      basePort: this.basePort,
+      services: this.getEnabledServices().map((service) => service.service),
+      started,
+    })
   }
 
   /**
@@ -408,10 +637,12 @@ export class Config extends ConfigBase {
    * @returns {Promise<TryCatchResult<boolean>>}
    */
   override async prepareEnv(
-    { all = false, silent = false, force = false }: {
+    { all = false, silent = false, force = false, checkPorts = false, remapPorts = false }: {
       all?: boolean
       silent?: boolean
       force?: boolean
+      checkPorts?: boolean // Check the host ports of the services are available
+      remapPorts?: boolean // Remap ports that are in use instead of failing
     } = {},
   ): Promise<TryCatchResult<boolean>> {
     const results = success<boolean>(true)
@@ -424,6 +655,18 @@ export class Config extends ConfigBase {
     // Prepare the env for the project
     results.collect([await super.prepareEnv({ force })])
 
+    // Check ports before the compose overrides are generated so remapped ports are included
+    if (checkPorts) {
+      results.collect([await this.preparePorts({ all, remap: remapPorts })])
+      if (!results.success) {
+        this._envPrepared = false
+        return results
+      }
+    }
+
+    // Load the images lock before the compose overrides pin the images
+    results.collect([await this.loadImagesLock()])
+
     // TODO: log messages in services instead of collecting them
     // Services prep could take awhile, so it's better to log messages as they come in
 
@@ -439,6 +682,187 @@ export class Config extends ConfigBase {
     return results
   }
 
+  /**
+   * Get the images lock, null if the project doesn't have a lockfile
+   */
+  get imagesLock(): IImagesLock | null {
+    return this._imagesLock
+  }
+
+  /**
+   * Get the pinned image references from the images lock
+   *
+   * @returns {Record<string, string>} Map of image reference to `<image>@<digest>`
+   */
+  public getPinnedImages(): Record<string, string> {
+    // Offline projects use the images loaded from the bundle, they don't have the registry
+    // digests needed to match the pinned images
+    return this.offline ? {} : getPinnedImages(this._imagesLock)
+  }
+
+  /**
+   * Load the image digests from the images lockfile
+   *
+   * @returns {Promise<TryCatchResult<boolean>>} True if a lockfile was loaded
+   */
+  public async loadImagesLock(): Promise<TryCatchResult<boolean>> {
+    const result = await readImagesLock(this.imagesLockFile)
+    if (!result.success) {
+      return failure<boolean>('Failed to load images lock', result, false)
+    }
+    this._imagesLock = result.data ?? null
+    return success<boolean>(!!this._imagesLock)
+  }
+
+  /**
+   * Get the published host ports of the services
+   *
+   * Parses the published ports from each service compose file, compose files from repos
+   * are skipped until the repo is cloned.
+   *
+   * @param {ServicesMap} services - The services to get the ports of, defaults to enabled services
+   * @returns {Promise<TryCatchResult<IServiceHostPort[]>>} The ports and project host ports
+   */
+  public async getServicesHostPorts(
+    services: ServicesMap = this.getEnabledServices(),
+  ): Promise<TryCatchResult<IServiceHostPort[]>> {
+    const results = success<IServiceHostPort[]>([])
+    const env = { ...(await dockerEnv(this)), ...this.env }
+    for (const service of services.toArray()) {
+      const portsResult = await tryCatch(
+        getPortsFromComposeYaml(service.composeFile, { env, profiles: service.getProfiles() }),
+      )
+      if (!portsResult.success || !portsResult.data) {
+        results.addMessage('debug', `Unable to read ports from ${service.composeFile}`, {
+          error: portsResult.error,
+        })
+        continue
+      }
+      portsResult.data.forEach((port) => {
+        results.data!.push({
+          service: service.service,
+          name: service.name,
+          composeService: port.service,
+          port: port.published,
+          hostPort: getHostPort(port.published, this.basePort, env, service.service),
+          protocol: port.protocol,
+        })
+      })
+    }
+    return results
+  }
+
+  /**
+   * Get the host ports of the services that are already in use
+   *
+   * Ports published by the project's running containers are not conflicts.
+   *
+   * @param {ServicesMap} services - The services to check, defaults to enabled services
+   * @returns {Promise<TryCatchResult<IServiceHostPort[]>>} The conflicting ports
+   */
+  public async getPortConflicts(
+    services: ServicesMap = this.getEnabledServices(),
+  ): Promise<TryCatchResult<IServiceHostPort[]>> {
+    const portsResult = await this.getServicesHostPorts(services)
+
+    const psResult = await tryCatch(dockerComposePs(this.projectName))
+    const published = new Set(
+      ((psResult.data || []) as DockerComposePsResult).flatMap((container) =>
+        (container.Publishers || []).map((publisher) => publisher.PublishedPort)
+      ),
+    )
+
+    // Services that publish the same port are each reported so they can be remapped separately
+    const available = new Map<string, boolean>()
+    const conflicts = (portsResult.data || []).filter((port) => {
+      if (published.has(port.hostPort)) {
+        return false
+      }
+      const key = `${port.hostPort}/${port.protocol}`
+      if (!available.has(key)) {
+        available.set(key, isHostPortAvailable(port.hostPort, '0.0.0.0', port.protocol))
+      }
+      return !available.get(key)
+    })
+    return success<IServiceHostPort[]>(conflicts).addMessages(portsResult.messages)
+  }
+
+  /**
+   * Remap host ports that are in use to the next available port
+   *
+   * The new ports are saved to .env as LLEMONSTACK_HOST_PORT_<service>_<port> env vars.
+   *
+   * @param {IServiceHostPort[]} conflicts - The conflicts from getPortConflicts
+   * @returns {Promise<TryCatchResult<Record<string, string>>>} The env vars that were set
+   */
+  public async remapPorts(
+    conflicts: IServiceHostPort[],
+  ): Promise<TryCatchResult<Record<string, string>>> {
+    const results = success<Record<string, string>>({})
+    // Don't remap to a port used by another service in the stack
+    const reserved = new Set(
+      (await this.getServicesHostPorts(this.getAllServices())).data?.map((port) => port.hostPort),
+    )
+    for (const conflict of conflicts) {
+      const hostPort = findAvailableHostPort(conflict.hostPort, reserved, conflict.protocol)
+      if (!hostPort) {
+        return failure<Record<string, string>>(
+          `No available port found for ${conflict.name} port ${conflict.hostPort}`,
+          results,
+        )
+      }
+      reserved.add(hostPort)
+      results.data![getHostPortEnvVar(conflict.service, conflict.port)] = String(hostPort)
+      results.addMessage(
+        'warning',
+        `${conflict.name} port ${conflict.hostPort} is in use, remapped to ${hostPort}`,
+      )
+    }
+    if (Object.keys(results.data!).length) {
+      results.collect([await this.setEnvFileVars(results.data!)])
+    }
+    return results
+  }
+
+  /**
+   * Check the host ports of the services are available, optionally remapping ports in use
+   *
+   * @param {boolean} all - Check all services, not just enabled ones
+   * @param {boolean} remap - Remap ports that are in use instead of failing
+   * @returns {Promise<TryCatchResult<boolean>>}
+   */
+  protected async preparePorts(
+    { all = false, remap = false }: { all?: boolean; remap?: boolean } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    const conflictsResult = await this.getPortConflicts(
+      all ? this.getAllServices() : this.getEnabledServices(),
+    )
+    const conflicts = conflictsResult.data || []
+    if (!conflictsResult.success || conflicts.length === 0) {
+      return success<boolean>(true)
+    }
+
+    if (remap) {
+      const remapResult = await this.remapPorts(conflicts)
+      return remapResult.success
+        ? success<boolean>(true).collect([remapResult])
+        : failure<boolean>('Failed to remap ports', remapResult, false)
+    }
+
+    const results = failure<boolean>('Host ports are already in use', success<boolean>(false))
+    conflicts.forEach((conflict) => {
+      results.addMessage(
+        'error',
+        `${conflict.name} (${conflict.composeService}) port ${conflict.hostPort} is already in use`,
+      )
+    })
+    results.addMessage(
+      'info',
+      'Stop the processes using the ports or run `llmn start --remap-ports` to use other ports',
+    )
+    return results
+  }
+
   //
   // Private Methods
   //
@@ -1,10 +1,11 @@
 import { Service } from '@/core/services/service.ts'
-import { fileExists, path, readDir, readYaml } from '@/lib/fs.ts'
+import { fileExists, path, readDir, readTextFile } from '@/lib/fs.ts'
 import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
-import { IServiceOptions, LLemonStackConfig, ServiceYaml } from '@/types'
+import { IServiceOptions, LLemonStackConfig } from '@/types'
 import { Config } from '../config.ts'
+import { validateServiceYaml } from './schema.ts'
 
-const SERVICE_CONFIG_FILE_NAME = 'llemonstack.yaml'
+export const SERVICE_CONFIG_FILE_NAME = 'llemonstack.yaml'
 
 /**
  * Load services from services directory
@@ -53,11 +54,20 @@ export async function loadServices(
         result.addMessage('debug', `Service config file not found: ${serviceDir.name}`)
         continue
       }
-      const yamlResult = await readYaml<ServiceYaml>(
-        yamlFilePath,
-      )
-      if (!yamlResult.success || !yamlResult.data) {
+      const yamlTextResult = await readTextFile(yamlFilePath)
+      if (!yamlTextResult.success || yamlTextResult.data === null) {
         result.addMessage('error', `Error reading service config file: ${serviceDir.name}`, {
+          error: yamlTextResult.error,
+        })
+        continue
+      }
+
+      // Validate the config against the llemonstack.yaml schema
+      // Unknown keys are logged as warnings, invalid configs are skipped
+      const yamlResult = validateServiceYaml(yamlTextResult.data, yamlFilePath)
+      result.addMessages(yamlResult.messages)
+      if (!yamlResult.success || !yamlResult.data) {
+        result.addMessage('error', `Invalid service config file: ${serviceDir.name}`, {
           error: yamlResult.error,
         })
         continue
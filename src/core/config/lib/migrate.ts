@@ -0,0 +1,186 @@
+/**
+ * Config migrations
+ *
+ * Upgrades the config.json and .env of a project created with an older version of LLemonStack.
+ * Each migration upgrades a project to its version. Migrations are run in version order,
+ * the files are backed up to .llemonstack/migrations/ before each migration.
+ *
+ * See ../migrations/mod.ts for the list of migrations.
+ */
+import { EnvDocument, writeEnvDocument } from '@/lib/env.ts'
+import { fileExists, fs, path, readJson, readTextFile, saveJson } from '@/lib/fs.ts'
+import { failure, success, tryCatch, TryCatchResult } from '@/lib/try-catch.ts'
+import { isVersionAtLeast } from '@/lib/utils/compare.ts'
+import { LLemonStackConfig } from '@/types'
+import configTemplate from '@templateConfig' with { type: 'json' }
+import { MIGRATIONS } from '../migrations/mod.ts'
+
+// Config version of the current LLemonStack version
+export const CONFIG_VERSION: string = configTemplate.version
+
+export interface IMigrationFiles {
+  config: LLemonStackConfig // Parsed config.json, updated in place
+  env: string | null // Contents of the .env file, null if the project doesn't have one
+}
+
+export interface IMigration {
+  version: string // Config version after the migration
+  description: string
+  migrate: (files: IMigrationFiles) => string[] // Returns a description of each change
+}
+
+export interface IMigrationStep {
+  version: string
+  description: string
+  changes: string[]
+  backupDir: string | null // Null in check mode
+}
+
+export interface IMigrationResult {
+  from: string
+  to: string
+  steps: IMigrationStep[]
+}
+
+/**
+ * Get the migrations needed to upgrade a config version, in order
+ */
+export function getPendingMigrations(
+  version: string,
+  migrations: IMigration[] = MIGRATIONS,
+): IMigration[] {
+  return migrations.filter((migration) => !isVersionAtLeast(version, migration.version))
+}
+
+/**
+ * Check if a config version can be used by the current LLemonStack version
+ *
+ * @returns Failure if the config needs to be migrated or is from a newer LLemonStack version
+ */
+export function checkConfigVersion(
+  version: string,
+  target: string = CONFIG_VERSION,
+): TryCatchResult<boolean> {
+  if (version === target) {
+    return success<boolean>(true)
+  }
+  if (version && isVersionAtLeast(version, target)) {
+    return failure<boolean>(
+      `Config version ${version} is newer than this version of LLemonStack supports (${target}), ` +
+        'please upgrade LLemonStack',
+      success(null),
+      false,
+    )
+  }
+  return failure<boolean>(
+    `Config version ${version || 'unknown'} is outdated, ` +
+      `run \`llmn migrate\` to upgrade it to ${target}`,
+    success(null),
+    false,
+  )
+}
+
+/**
+ * Run a migration on the files in memory and update the config version
+ *
+ * @returns The changes made by the migration
+ */
+export function applyMigration(files: IMigrationFiles, migration: IMigration): string[] {
+  const changes = migration.migrate(files)
+  files.config.version = migration.version
+  return changes
+}
+
+/**
+ * Copy the files to a backup dir, files that don't exist are skipped
+ */
+async function backupFiles(dir: string, files: string[]): Promise<TryCatchResult<boolean>> {
+  for (const file of files) {
+    if (!(await fileExists(file)).data) continue
+    const result = await tryCatch(
+      fs.copy(file, path.join(dir, path.basename(file)), { overwrite: true }),
+    )
+    if (!result.success) {
+      return failure<boolean>(`Unable to back up ${file}`, result, false)
+    }
+  }
+  return success<boolean>(true)
+}
+
+/**
+ * Migrate a project config.json and .env to the current config version
+ *
+ * Reads the files directly, the config can't be initialized until it has been migrated.
+ *
+ * @param configFile - The project config file
+ * @param check - Only report the pending migrations and their changes, nothing is saved
+ */
+export async function migrateProject(
+  configFile: string,
+  { check = false, migrations = MIGRATIONS }: {
+    check?: boolean
+    migrations?: IMigration[]
+  } = {},
+): Promise<TryCatchResult<IMigrationResult | null>> {
+  const configResult = await readJson<LLemonStackConfig>(configFile)
+  if (!configResult.success || !configResult.data) {
+    return failure<IMigrationResult | null>(
+      `Unable to read config file: ${configFile}`,
+      configResult,
+      null,
+    )
+  }
+
+  const config = configResult.data
+  const data: IMigrationResult = { from: config.version, to: CONFIG_VERSION, steps: [] }
+
+  const versionResult = checkConfigVersion(config.version)
+  if (!versionResult.success && isVersionAtLeast(config.version || '0', CONFIG_VERSION)) {
+    return failure<IMigrationResult | null>('Unable to migrate config', versionResult, data)
+  }
+
+  const envFile = path.resolve(Deno.cwd(), config.envFile || '.env')
+  const envResult = await readTextFile(envFile)
+  const files: IMigrationFiles = { config, env: envResult.success ? envResult.data : null }
+
+  const timestamp = new Date().toISOString().replace(/[:.]/g, '-')
+  for (const migration of getPendingMigrations(config.version || '0', migrations)) {
+    const backupDir = check ? null : path.join(
+      path.dirname(configFile),
+      'migrations',
+      `${timestamp}-${migration.version}`,
+    )
+    if (backupDir) {
+      const backupResult = await backupFiles(backupDir, [configFile, envFile])
+      if (!backupResult.success) {
+        return failure<IMigrationResult | null>(
+          `Unable to back up files before migrating to ${migration.version}`,
+          backupResult,
+          data,
+        )
+      }
+    }
+
+    const changes = applyMigration(files, migration)
+    data.steps.push({
+      version: migration.version,
+      description: migration.description,
+      changes,
+      backupDir,
+    })
+    if (check) continue
+
+    const saveResult = await saveJson(configFile, files.config)
+    if (!saveResult.success) {
+      return failure<IMigrationResult | null>(`Unable to save ${configFile}`, saveResult, data)
+    }
+    if (files.env !== null) {
+      const envSaveResult = await writeEnvDocument(envFile, EnvDocument.parse(files.env))
+      if (!envSaveResult.success) {
+        return failure<IMigrationResult | null>(`Unable to save ${envFile}`, envSaveResult, data)
+      }
+    }
+  }
+
+  return success<IMigrationResult | null>(data)
+}
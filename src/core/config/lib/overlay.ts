@@ -0,0 +1,107 @@
+/**
+ * Environment overlays
+ *
+ * A project can run in several environments, e.g. dev, ci and demo. Each environment can have
+ * an overlay config, e.g. .llemonstack/config.ci.json, and an overlay env file, e.g. .env.ci.
+ * Overlays only contain the values to override, they're layered on top of config.json and .env
+ * when the config is initialized.
+ *
+ * The environment is selected with the --env option or the LLEMONSTACK_ENV env var.
+ */
+import { path } from '@/lib/fs.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import { LLemonStackConfig } from '@/types'
+
+export const ENVIRONMENT_ENV = 'LLEMONSTACK_ENV'
+
+// Overlay of config.json, only the keys to override
+export type ConfigOverlay = Record<string, unknown>
+
+const RE_ENVIRONMENT_NAME = /^[a-zA-Z0-9_-]+$/
+
+function isPlainObject(value: unknown): value is Record<string, unknown> {
+  return !!value && typeof value === 'object' && !Array.isArray(value)
+}
+
+/**
+ * Check the environment name can be used in a file name
+ */
+export function checkEnvironmentName(name: string): TryCatchResult<boolean> {
+  if (!RE_ENVIRONMENT_NAME.test(name)) {
+    return failure<boolean>(
+      `Invalid environment name: "${name}", use letters, numbers, - and _`,
+      success(null),
+      false,
+    )
+  }
+  return success<boolean>(true)
+}
+
+/**
+ * Get the overlay config file for an environment, e.g. config.json -> config.ci.json
+ */
+export function getConfigOverlayFile(configFile: string, name: string): string {
+  const ext = path.extname(configFile)
+  return `${configFile.slice(0, configFile.length - ext.length)}.${name}${ext}`
+}
+
+/**
+ * Get the overlay env file for an environment, e.g. .env -> .env.ci
+ */
+export function getEnvOverlayFile(envFile: string, name: string): string {
+  return `${envFile}.${name}`
+}
+
+/**
+ * Layer an overlay on top of a config
+ *
+ * Objects are merged recursively, all other values including arrays replace the config value.
+ * e.g. { services: { n8n: { enabled: false } } } only disables n8n and keeps its profiles.
+ *
+ * @returns A new config, the config and overlay are not changed
+ */
+export function mergeConfigOverlay<T extends Record<string, unknown> | LLemonStackConfig>(
+  config: T,
+  overlay: ConfigOverlay,
+): T {
+  const merged = structuredClone(config) as Record<string, unknown>
+  for (const [key, value] of Object.entries(overlay)) {
+    merged[key] = isPlainObject(value) && isPlainObject(merged[key])
+      ? mergeConfigOverlay(merged[key] as Record<string, unknown>, value)
+      : structuredClone(value)
+  }
+  return merged as T
+}
+
+/**
+ * Remove the overlay values from a merged config before it's saved
+ *
+ * Values set by the overlay are restored to the base config values, other changes are kept.
+ *
+ * @param config - The merged config, including any changes made after merging
+ * @param overlay - The overlay that was merged
+ * @param base - The config before the overlay was merged
+ * @returns A new config without the overlay values
+ */
+export function stripConfigOverlay<T extends Record<string, unknown> | LLemonStackConfig>(
+  config: T,
+  overlay: ConfigOverlay,
+  base: T,
+): T {
+  const stripped = structuredClone(config) as Record<string, unknown>
+  const baseValues = base as Record<string, unknown>
+  for (const [key, value] of Object.entries(overlay)) {
+    if (isPlainObject(value) && isPlainObject(stripped[key]) && isPlainObject(baseValues[key])) {
+      stripped[key] = stripConfigOverlay(
+        stripped[key] as Record<string, unknown>,
+        value,
+        baseValues[key] as Record<string, unknown>,
+      )
+    } else if (key in baseValues) {
+      stripped[key] = structuredClone(baseValues[key])
+    } else {
+      delete stripped[key]
+    }
+  }
+  return stripped as T
+}
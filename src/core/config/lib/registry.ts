@@ -0,0 +1,193 @@
+import { fileExists, path, readJson, saveJson } from '@/lib/fs.ts'
+import { BASE_PORT_STEP } from '@/lib/ports.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+
+// Name of the global dir in the user's home dir, can be changed with LLEMONSTACK_HOME env var
+const GLOBAL_DIR_NAME = '.llemonstack'
+const REGISTRY_FILE_NAME = 'projects.json'
+const REGISTRY_VERSION = 1
+
+export interface IRegistryProject {
+  projectName: string
+  path: string // Absolute path to the project dir
+  configFile: string // Absolute path to the project config.json
+  ports: {
+    base: number
+    range: [number, number] // Host ports reserved for the project
+  }
+  services: string[] // Enabled services
+  lastStarted: string | null // ISO 8601 timestamp
+  updated: string // ISO 8601 timestamp
+}
+
+interface IRegistryFile {
+  version: number
+  current: string | null // Path of the project used when llmn is run outside of a project dir
+  projects: IRegistryProject[]
+}
+
+/**
+ * Global registry of all LLemonStack projects on the host
+ *
+ * Stored in ~/.llemonstack/projects.json. Used to detect project name and port collisions
+ * between projects, and to run llmn commands for a project from any dir.
+ */
+export class ProjectRegistry {
+  private static instance: ProjectRegistry
+
+  protected _file: string
+  protected _data: IRegistryFile = { version: REGISTRY_VERSION, current: null, projects: [] }
+  protected _loaded = false
+
+  constructor(file: string = ProjectRegistry.defaultFile) {
+    this._file = file
+  }
+
+  public static getInstance(): ProjectRegistry {
+    if (!ProjectRegistry.instance) {
+      ProjectRegistry.instance = new ProjectRegistry()
+    }
+    return ProjectRegistry.instance
+  }
+
+  /**
+   * Get the global LLemonStack dir, ~/.llemonstack by default
+   */
+  static get globalDir(): string {
+    const home = Deno.env.get('HOME') || Deno.env.get('USERPROFILE') || ''
+    return Deno.env.get('LLEMONSTACK_HOME') || path.join(home, GLOBAL_DIR_NAME)
+  }
+
+  static get defaultFile(): string {
+    return path.join(ProjectRegistry.globalDir, REGISTRY_FILE_NAME)
+  }
+
+  get file(): string {
+    return this._file
+  }
+
+  get projects(): IRegistryProject[] {
+    return this._data.projects
+  }
+
+  /**
+   * Get the current project set with `llmn projects switch`
+   */
+  get current(): IRegistryProject | null {
+    return this.projects.find((project) => project.path === this._data.current) || null
+  }
+
+  /**
+   * Load the registry file
+   *
+   * A missing registry file is not an error, the registry starts empty.
+   */
+  public async load({ reload = false }: { reload?: boolean } = {}): Promise<
+    TryCatchResult<IRegistryProject[]>
+  > {
+    if (this._loaded && !reload) {
+      return success<IRegistryProject[]>(this.projects)
+    }
+    if (!(await fileExists(this._file)).data) {
+      this._loaded = true
+      return success<IRegistryProject[]>(this.projects)
+    }
+    const result = await readJson<IRegistryFile>(this._file)
+    if (!result.success || !result.data) {
+      return failure<IRegistryProject[]>(`Unable to read project registry: ${this._file}`, result)
+    }
+    this._data = {
+      version: result.data.version ?? REGISTRY_VERSION,
+      current: result.data.current ?? null,
+      projects: result.data.projects ?? [],
+    }
+    this._loaded = true
+    return success<IRegistryProject[]>(this.projects)
+  }
+
+  public async save(): Promise<TryCatchResult<boolean>> {
+    return await saveJson(this._file, this._data)
+  }
+
+  /**
+   * Get a project by dir path or project name
+   */
+  public get(pathOrName: string): IRegistryProject | null {
+    return this.projects.find((project) =>
+      project.path === pathOrName || project.projectName === pathOrName
+    ) || null
+  }
+
+  /**
+   * Get projects using the project name, excluding the project in the given dir
+   */
+  public getByProjectName(projectName: string, { excludePath }: { excludePath?: string } = {}) {
+    return this.projects.filter((project) =>
+      project.projectName.toLowerCase() === projectName.toLowerCase() &&
+      project.path !== excludePath
+    )
+  }
+
+  /**
+   * Add or update a project in the registry
+   */
+  public async register(
+    project: Omit<IRegistryProject, 'ports' | 'lastStarted' | 'updated'> & {
+      basePort: number
+      started?: boolean
+    },
+  ): Promise<TryCatchResult<boolean>> {
+    const existing = this.get(project.path)
+    const entry: IRegistryProject = {
+      projectName: project.projectName,
+      path: project.path,
+      configFile: project.configFile,
+      ports: {
+        base: project.basePort,
+        range: [project.basePort, project.basePort + BASE_PORT_STEP - 1],
+      },
+      services: project.services,
+      lastStarted: project.started ? new Date().toISOString() : existing?.lastStarted ?? null,
+      updated: new Date().toISOString(),
+    }
+    this._data.projects = [...this.projects.filter((p) => p.path !== project.path), entry]
+      .sort((a, b) => a.projectName.localeCompare(b.projectName))
+    return await this.save()
+  }
+
+  /**
+   * Set the project used when llmn is run outside of a project dir
+   */
+  public async setCurrent(projectPath: string | null): Promise<TryCatchResult<boolean>> {
+    this._data.current = projectPath
+    return await this.save()
+  }
+
+  /**
+   * Remove projects whose config file no longer exists
+   *
+   * @returns The removed projects
+   */
+  public async prune(
+    { dryRun = false }: { dryRun?: boolean } = {},
+  ): Promise<TryCatchResult<IRegistryProject[]>> {
+    const missing: IRegistryProject[] = []
+    for (const project of this.projects) {
+      if (!(await fileExists(project.configFile)).data) {
+        missing.push(project)
+      }
+    }
+    if (dryRun || missing.length === 0) {
+      return success<IRegistryProject[]>(missing)
+    }
+    this._data.projects = this.projects.filter((project) => !missing.includes(project))
+    if (this._data.current && !this.current) {
+      this._data.current = null
+    }
+    const saveResult = await this.save()
+    if (!saveResult.success) {
+      return failure<IRegistryProject[]>('Unable to save project registry', saveResult)
+    }
+    return success<IRegistryProject[]>(missing)
+  }
+}
@@ -0,0 +1,278 @@
+import { failure, TryCatchResult } from '@/lib/try-catch.ts'
+import { ServiceYaml } from '@/types'
+import * as yaml from 'jsr:@std/yaml'
+import serviceSchema from '../schemas/llemonstack.schema.json' with { type: 'json' }
+
+export type SchemaPath = (string | number)[]
+
+/**
+ * Subset of JSON Schema draft-07 supported by validateSchema
+ */
+export interface JsonSchema {
+  $ref?: string
+  type?: string | string[]
+  enum?: unknown[]
+  pattern?: string
+  minimum?: number
+  required?: string[]
+  properties?: Record<string, JsonSchema>
+  additionalProperties?: boolean | JsonSchema
+  items?: JsonSchema
+  anyOf?: JsonSchema[]
+  definitions?: Record<string, JsonSchema>
+  [key: string]: unknown
+}
+
+export interface SchemaIssue {
+  level: 'error' | 'warning'
+  path: SchemaPath
+  message: string
+}
+
+export const SERVICE_YAML_SCHEMA = serviceSchema as JsonSchema
+
+function getType(value: unknown): string {
+  if (value === null) return 'null'
+  if (Array.isArray(value)) return 'array'
+  if (typeof value === 'number' && Number.isInteger(value)) return 'integer'
+  return typeof value
+}
+
+function isType(value: unknown, type: string): boolean {
+  const valueType = getType(value)
+  return valueType === type || (type === 'number' && valueType === 'integer')
+}
+
+function resolveRef(ref: string, root: JsonSchema): JsonSchema {
+  // Only local refs are supported, e.g. #/definitions/repo
+  const schema = ref.replace(/^#\//, '').split('/').reduce<unknown>(
+    (node, key) => (node as Record<string, unknown>)?.[key],
+    root,
+  )
+  if (!schema) {
+    throw new Error(`Unable to resolve schema ref: ${ref}`)
+  }
+  return schema as JsonSchema
+}
+
+/**
+ * Validate a value against a JSON Schema
+ *
+ * Only supports the keywords used by the LLemonStack schemas.
+ * Keys not allowed by `additionalProperties: false` are returned as warnings instead of errors
+ * so new or misspelled keys don't prevent a service from loading.
+ *
+ * @param value - The value to validate
+ * @param schema - The schema to validate against
+ * @param root - The root schema used to resolve refs
+ * @param path - The path to the value, used in the returned issues
+ * @returns List of errors and warnings
+ */
+export function validateSchema(
+  value: unknown,
+  schema: JsonSchema,
+  root: JsonSchema = schema,
+  path: SchemaPath = [],
+): SchemaIssue[] {
+  if (schema.$ref) {
+    return validateSchema(value, resolveRef(schema.$ref, root), root, path)
+  }
+
+  if (schema.anyOf) {
+    const results = schema.anyOf.map((option) => validateSchema(value, option, root, path))
+    const match = results.find((issues) => !issues.some((issue) => issue.level === 'error'))
+    if (match) {
+      return match
+    }
+    // Return the issues of the closest match
+    return results.sort((a, b) => a.length - b.length)[0]
+  }
+
+  const issues: SchemaIssue[] = []
+  const error = (message: string, errorPath = path) =>
+    issues.push({ level: 'error', path: errorPath, message })
+
+  if (schema.type) {
+    const types = Array.isArray(schema.type) ? schema.type : [schema.type]
+    if (!types.some((type) => isType(value, type))) {
+      error(`Expected ${types.join(' or ')}, got ${getType(value)}`)
+      return issues
+    }
+  }
+
+  if (schema.enum && !schema.enum.includes(value)) {
+    error(`Invalid value "${value}", expected one of: ${schema.enum.join(', ')}`)
+  }
+
+  if (schema.pattern && typeof value === 'string' && !new RegExp(schema.pattern).test(value)) {
+    error(`Invalid value "${value}", must match ${schema.pattern}`)
+  }
+
+  if (schema.minimum !== undefined && typeof value === 'number' && value < schema.minimum) {
+    error(`Invalid value ${value}, must be at least ${schema.minimum}`)
+  }
+
+  if (Array.isArray(value) && schema.items) {
+    value.forEach((item, index) => {
+      issues.push(...validateSchema(item, schema.items!, root, [...path, index]))
+    })
+  }
+
+  if (isType(value, 'object')) {
+    const obj = value as Record<string, unknown>
+    schema.required?.forEach((key) => {
+      if (obj[key] === undefined) {
+        error(`Missing required key: ${key}`)
+      }
+    })
+    for (const [key, keyValue] of Object.entries(obj)) {
+      const keySchema = schema.properties?.[key]
+      if (keySchema) {
+        issues.push(...validateSchema(keyValue, keySchema, root, [...path, key]))
+      } else if (schema.additionalProperties === false) {
+        issues.push({ level: 'warning', path: [...path, key], message: `Unknown key: ${key}` })
+      } else if (typeof schema.additionalProperties === 'object') {
+        issues.push(
+          ...validateSchema(keyValue, schema.additionalProperties, root, [...path, key]),
+        )
+      }
+    }
+  }
+
+  return issues
+}
+
+/**
+ * Map each key and list item in a YAML document to its line number
+ *
+ * Works on the block style used in llemonstack.yaml files. Flow style collections and
+ * block scalars are mapped to the line of their parent key.
+ *
+ * @param yamlText - The YAML document
+ * @returns Map of path joined with '.' to the 1-based line number
+ */
+export function getYamlLines(yamlText: string): Map<string, number> {
+  const lines = new Map<string, number>()
+  const stack: { indent: number; path: SchemaPath }[] = [{ indent: -1, path: [] }]
+  const listCounts = new Map<string, number>()
+  let blockScalarIndent: number | null = null
+
+  yamlText.split('\n').forEach((text, index) => {
+    const trimmed = text.trim()
+    if (!trimmed || trimmed.startsWith('#')) {
+      return
+    }
+    let indent = text.length - text.trimStart().length
+    if (blockScalarIndent !== null) {
+      if (indent > blockScalarIndent) {
+        return // Inside a block scalar, e.g. `info: |`
+      }
+      blockScalarIndent = null
+    }
+
+    let content = trimmed
+    const isListItem = content === '-' || content.startsWith('- ')
+    while (stack.length > 1) {
+      const top = stack[stack.length - 1]
+      // List items can have the same indent as their parent key
+      const isParent = isListItem && top.indent === indent &&
+        typeof top.path[top.path.length - 1] === 'string'
+      if (top.indent < indent || isParent) {
+        break
+      }
+      stack.pop()
+    }
+
+    // List item, the item content can start on the same line, e.g. `- source: dir`
+    if (isListItem) {
+      const parentPath = stack[stack.length - 1].path
+      const listIndex = (listCounts.get(parentPath.join('.')) ?? -1) + 1
+      listCounts.set(parentPath.join('.'), listIndex)
+      const itemPath = [...parentPath, listIndex]
+      lines.set(itemPath.join('.'), index + 1)
+      stack.push({ indent, path: itemPath })
+      const rest = content.slice(1).trimStart()
+      indent += content.length - rest.length
+      content = rest
+    }
+
+    const match = content.match(/^(["']?)([^"'#]+?)\1\s*:(?:\s+(.*))?$/)
+    if (!match) {
+      return
+    }
+    const keyPath = [...stack[stack.length - 1].path, match[2]]
+    lines.set(keyPath.join('.'), index + 1)
+    stack.push({ indent, path: keyPath })
+    if (/^[|>][-+0-9]*\s*(#.*)?$/.test(match[3] || '')) {
+      blockScalarIndent = indent
+    }
+  })
+
+  return lines
+}
+
+/**
+ * Get the line number of a path in a YAML document
+ *
+ * Falls back to the closest parent path that has a line number.
+ */
+export function getYamlLine(lines: Map<string, number>, path: SchemaPath): number | null {
+  for (let i = path.length; i > 0; i--) {
+    const line = lines.get(path.slice(0, i).join('.'))
+    if (line) {
+      return line
+    }
+  }
+  return null
+}
+
+/**
+ * Parse and validate a service llemonstack.yaml file against the service schema
+ *
+ * Errors and warnings are added to the result messages with the file and line number,
+ * e.g. `services/n8n/llemonstack.yaml:12 init.generate.N8N_KEY.method: Invalid value...`
+ *
+ * @param yamlText - The contents of the llemonstack.yaml file
+ * @param file - The path to the file, used in messages
+ * @returns The parsed service config, fails if the YAML is invalid or has schema errors
+ */
+export function validateServiceYaml(
+  yamlText: string,
+  file: string,
+): TryCatchResult<ServiceYaml> {
+  const result = new TryCatchResult<ServiceYaml>({ data: null, error: null, success: true })
+
+  let serviceYaml: unknown
+  try {
+    serviceYaml = yaml.parse(yamlText)
+  } catch (error) {
+    return failure<ServiceYaml>(`${file}: Invalid YAML: ${(error as Error).message}`, {
+      data: null,
+      error: error as Error,
+      success: false,
+    })
+  }
+
+  const lines = getYamlLines(yamlText)
+  const issues = validateSchema(serviceYaml, SERVICE_YAML_SCHEMA)
+  issues.forEach((issue) => {
+    const line = getYamlLine(lines, issue.path)
+    const location = line ? `${file}:${line}` : file
+    const key = issue.path.length ? ` ${issue.path.join('.')}:` : ''
+    const message = `${location}${key} ${issue.message}`
+    if (issue.level === 'error') {
+      result.addMessage('error', message, { error: new Error(message) })
+    } else {
+      result.addMessage('warning', message)
+    }
+  })
+
+  const errors = issues.filter((issue) => issue.level === 'error')
+  if (errors.length) {
+    result.error = new Error(`${file} has ${errors.length} schema error(s)`)
+    return result
+  }
+
+  result.data = serviceYaml as ServiceYaml
+  return result
+}
@@ -63,3 +63,42 @@ export function isValidConfig(
   }
   return result
 }
+
+/**
+ * Check the services in the project config have a valid state
+ *
+ * isValidConfig only checks the required keys exist. Used to check values that aren't
+ * patched from the template, e.g. the services of an environment overlay.
+ * @returns {TryCatchResult<boolean>}
+ */
+export function isValidServicesConfig(
+  services: LLemonStackConfig['services'],
+): TryCatchResult<boolean> {
+  const result = success<boolean>(true)
+  if (typeof services !== 'object' || services === null || Array.isArray(services)) {
+    return failure<boolean>('Invalid services, expected an object', result, false)
+  }
+  for (const [service, state] of Object.entries(services)) {
+    if (typeof state !== 'object' || state === null || Array.isArray(state)) {
+      return failure<boolean>(`Invalid config for service: ${service}`, result, false)
+    }
+    if (typeof state.enabled !== 'boolean' && state.enabled !== 'auto') {
+      return failure<boolean>(
+        `Invalid services.${service}.enabled, expected true, false or "auto"`,
+        result,
+        false,
+      )
+    }
+    if (
+      state.profiles !== undefined &&
+      (!Array.isArray(state.profiles) || state.profiles.some((p) => typeof p !== 'string'))
+    ) {
+      return failure<boolean>(
+        `Invalid services.${service}.profiles, expected an array of strings`,
+        result,
+        false,
+      )
+    }
+  }
+  return result
+}
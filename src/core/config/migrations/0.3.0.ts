@@ -0,0 +1,85 @@
+/**
+ * 0.3.0: services are enabled in config.json instead of ENABLE_* vars in .env
+ *
+ * Upgrades 0.1.0 and 0.2.0 projects.
+ */
+import { EnvDocument } from '@/lib/env.ts'
+import { isTruthy } from '@/lib/utils/compare.ts'
+import { IServiceConfigState, LLemonStackConfig } from '@/types'
+import template from '../templates/config.0.3.0.json' with { type: 'json' }
+import type { IMigration } from '../lib/migrate.ts'
+
+const ENABLE_VAR_REGEX = /^ENABLE_([A-Z0-9_]+)$/
+
+// ENABLE_OLLAMA was set to the ollama profile to use
+const OLLAMA_PROFILES = ['cpu', 'gpu-nvidia', 'gpu-amd', 'host']
+
+/**
+ * Get the service state from an ENABLE_* value
+ */
+function getServiceState(service: string, value: string): IServiceConfigState {
+  const enabled = value.trim().toLowerCase()
+  if (service === 'ollama' && OLLAMA_PROFILES.includes(enabled)) {
+    return { enabled: true, profiles: [`ollama-${enabled}`] }
+  }
+  return { enabled: isTruthy(enabled) }
+}
+
+export const migration: IMigration = {
+  version: '0.3.0',
+  description: 'Move ENABLE_* vars from .env to services in config.json',
+  migrate(files) {
+    const changes: string[] = []
+    const { config } = files
+    const templateConfig = template as LLemonStackConfig
+
+    // 0.1.0 configs saved the init date in timestamp
+    if (config.timestamp !== undefined) {
+      if (!config.initialized) {
+        config.initialized = config.timestamp
+        changes.push('Moved timestamp to initialized in config.json')
+      } else {
+        changes.push('Removed timestamp from config.json')
+      }
+      delete config.timestamp
+    }
+
+    if (config.dirs && !('services' in config.dirs)) {
+      config.dirs.services = []
+      changes.push('Added dirs.services to config.json')
+    }
+    if (!config.ports) {
+      config.ports = { ...templateConfig.ports! }
+      changes.push('Added ports to config.json')
+    }
+
+    const services: Record<string, IServiceConfigState> = { ...config.services }
+    const added = Object.keys(templateConfig.services).filter((key) => !(key in services))
+    for (const key of added) {
+      services[key] = structuredClone(templateConfig.services[key])
+    }
+    if (added.length) {
+      changes.push(`Added services to config.json: ${added.join(', ')}`)
+    }
+
+    // Replace ENABLE_* vars with the services enabled state
+    if (files.env !== null) {
+      const keys = new Map(
+        Object.keys(services).map((key) => [key.toUpperCase().replace(/-/g, '_'), key]),
+      )
+      const doc = EnvDocument.parse(files.env)
+      for (const key of doc.keys) {
+        const match = key.match(ENABLE_VAR_REGEX)
+        const service = match ? keys.get(match[1]) : undefined
+        if (!match || !service) continue
+        services[service] = { ...services[service], ...getServiceState(service, doc.get(key)!) }
+        doc.unset(key)
+        changes.push(`Moved ${key} from .env to services.${service} in config.json`)
+      }
+      files.env = doc.toString()
+    }
+
+    config.services = services
+    return changes
+  },
+}
@@ -0,0 +1,12 @@
+import type { IMigration } from '../lib/migrate.ts'
+import { migration as migration_0_3_0 } from './0.3.0.ts'
+
+/**
+ * Config migrations in version order
+ *
+ * Add a migration when a release changes the format of config.json or .env.
+ * The last migration version must match the config template version.
+ */
+export const MIGRATIONS: IMigration[] = [
+  migration_0_3_0,
+]
@@ -1,5 +1,6 @@
 import { Service } from './service.ts'
 import { ServicesMap } from './services-map.ts'
+import { ServiceStatusMonitor } from './status-monitor.ts'
 
-export { Service, ServicesMap }
+export { Service, ServicesMap, ServiceStatusMonitor }
 export type { Service as ServiceType, ServicesMap as ServicesMapType }
@@ -1,21 +1,46 @@
-import { tryDockerCompose, tryDockerComposePs } from '@/lib/docker.ts'
-import { path } from '@/lib/fs.ts'
+import {
+  type DockerComposePsResult,
+  getRuntime,
+  tryDockerCompose,
+  tryDockerComposePs,
+} from '@/lib/docker.ts'
+import { dirExists, path } from '@/lib/fs.ts'
 import { generateRandomBase64, generateSecretKey, generateUUID } from '@/lib/jwt.ts'
-import { createServiceSchema, isPostgresConnectionValid } from '@/lib/postgres.ts'
+import { getHostPort } from '@/lib/ports.ts'
+import {
+  type ConnectionConfig,
+  createServiceSchema,
+  isPostgresConnectionValid,
+  setServiceSchemaPassword,
+} from '@/lib/postgres.ts'
 import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
-import { ObservableStruct } from '@/lib/utils/observable.ts'
+import { Callback, ObservableMapValue, ObservableStruct } from '@/lib/utils/observable.ts'
 import {
   ExposeHost,
   IRepoConfig,
   IServiceActionOptions,
   IServiceOptions,
+  IServicePlan,
+  IServiceSecret,
   IServiceStartOptions,
   IServiceState,
+  ServiceDependsOnCondition,
+  ServicePlanAction,
   ServiceStatusType,
   ServiceYaml,
 } from '@/types'
 import { Config } from '../config/config.ts'
-import { getEndpoints, prepareServiceVolumes, setupServiceRepo } from './utils/mod.ts'
+import {
+  getEndpoints,
+  prepareComposeOverride,
+  prepareServiceVolumes,
+  setupServiceRepo,
+} from './utils/mod.ts'
+
+// Max time to wait for a service to meet a depends_on condition
+const SERVICE_READY_TIMEOUT_MS = 180_000
+// Time between state checks while waiting for a service
+const SERVICE_READY_INTERVAL_MS = 2_000
 
 /**
  * Service
@@ -37,6 +62,9 @@ export class Service {
     ready: false,
     last_checked: null,
     state: null,
+    exit_code: null,
+    status: null,
+    ports: [],
   })
 
   // Reference back to the active config object
@@ -108,6 +136,25 @@ export class Service {
     return this._composeFile
   }
 
+  // Directory with the service llemonstack.yaml
+  public get dir(): string {
+    return this._dir
+  }
+
+  // Default env vars for the service, merged into the project .env when the service is enabled
+  public get envExampleFile(): string {
+    return path.join(this._dir, '.env.example')
+  }
+
+  /**
+   * Get the path to the generated compose override file for the project
+   *
+   * @returns {string} The path, the file only exists after prepareEnv
+   */
+  public get composeOverrideFile(): string {
+    return path.join(this._configInstance.configDir, 'compose', `${this._service}.override.yaml`)
+  }
+
   public get config(): ServiceYaml {
     return this._config
   }
@@ -146,6 +193,22 @@ export class Service {
     return Object.keys(this._config.depends_on || {}) ?? []
   }
 
+  /**
+   * Get the depends_on conditions for the service
+   *
+   * Returns [['postgres', 'service_healthy']] where postgres is a provided service
+   * that must be healthy before this service is started.
+   * Defaults to service_started if no condition is set in llemonstack.yaml.
+   *
+   * @returns {[string, ServiceDependsOnCondition][]} The dependency and condition
+   */
+  public get dependsOnConditions(): [string, ServiceDependsOnCondition][] {
+    return Object.entries(this._config.depends_on || {}).map(([dependency, settings]) => [
+      dependency,
+      settings?.condition || 'service_started',
+    ])
+  }
+
   /**
    * Get the services that this service provides
    *
@@ -194,8 +257,40 @@ export class Service {
     return true
   }
 
-  public async getStatus(): Promise<ServiceStatusType> {
-    await this.checkState()
+  /**
+   * Listen for changes to the service state
+   *
+   * @param callback - Called with the key, old value, and new value of each change
+   */
+  public listenState(
+    callback: Callback<ObservableMapValue<keyof IServiceState, unknown>>,
+  ): void {
+    this._state.listen(callback)
+  }
+
+  /**
+   * Remove a listener added with listenState
+   *
+   * @param callback - The callback to remove
+   */
+  public unlistenState(
+    callback: Callback<ObservableMapValue<keyof IServiceState, unknown>>,
+  ): void {
+    this._state.unlisten(callback as Callback<ObservableMapValue<string, unknown>>)
+  }
+
+  /**
+   * Get the status of the service
+   *
+   * @param {boolean} refresh - Check the state with Docker Compose first, defaults to true
+   * @returns {ServiceStatusType} The status of the service
+   */
+  public async getStatus(
+    { refresh = true }: { refresh?: boolean } = {},
+  ): Promise<ServiceStatusType> {
+    if (refresh) {
+      await this.checkState()
+    }
     if (!this.isEnabled()) {
       return 'disabled'
     }
@@ -209,6 +304,13 @@ export class Service {
         return 'started'
       }
     }
+    const state = this._state.get('state')
+    if (state === 'created' || state === 'restarting') {
+      return 'starting'
+    }
+    if (state === 'exited' || state === 'dead') {
+      return 'stopped'
+    }
     if (this._state.get('ready')) {
       return 'ready'
     }
@@ -253,19 +355,123 @@ export class Service {
       )
     }
 
+    this.updateState(psResults.data)
+
+    return results
+  }
+
+  /**
+   * Update the state object from Docker Compose ps results
+   *
+   * The ps results can include containers from other services, e.g. when the status
+   * monitor runs a single ps for the whole project.
+   *
+   * @param {DockerComposePsResult} psResults - The results from dockerComposePs
+   */
+  public updateState(psResults: DockerComposePsResult): void {
+    const serviceNames = this.containerNames
+
     // Use the ps results for the first service listed in provides key in llemonstack.yaml.
     // This first container is considered primary. e.g. supabase will check the db container.
-    const data = psResults.data.find((c) => c.Service === serviceNames[0])
+    const data = psResults.find((c) => c.Service === serviceNames[0])
     // TODO: combine the status of all the matching services in the ps results?
 
     const state = data?.State ?? null
     this.setState('state', state)
     this.setState('started', state === 'running')
+    // Health is empty when the container doesn't have a healthcheck,
+    // treat running containers without a healthcheck as healthy
+    const health = data?.Health || (state === 'running' ? 'healthy' : '')
+    this.setState(
+      'healthy',
+      health === 'healthy' ? true : health === 'unhealthy' ? false : null,
+    )
+    this.setState('exit_code', data?.ExitCode ?? null)
+    this.setState('status', data?.Status ?? null)
+
+    // Collect the published host ports for all of the service's containers
+    const ports = psResults
+      .filter((c) => c.Service && serviceNames.includes(c.Service))
+      .flatMap((c) => c.Publishers || [])
+      .filter((p) => p.PublishedPort)
+      .map((p) => `${p.PublishedPort}->${p.TargetPort}/${p.Protocol || 'tcp'}`)
+    this.setState('ports', [...new Set(ports)])
+
     this.setState('last_checked', new Date())
     this.setState('enabled', this.isEnabled())
     // TODO add more states checks here
+  }
 
-    return results
+  /**
+   * Check if the service meets a depends_on condition based on the last checked state
+   *
+   * @param {ServiceDependsOnCondition} condition - The condition to check
+   * @returns {boolean} True if the condition is met
+   */
+  public isConditionMet(condition: ServiceDependsOnCondition): boolean {
+    switch (condition) {
+      case 'service_completed_successfully':
+        return this._state.get('state') === 'exited' && this._state.get('exit_code') === 0
+      case 'service_healthy':
+        return this.isStarted() && this._state.get('healthy') === true
+      case 'service_started':
+      default:
+        return this.isStarted()
+    }
+  }
+
+  /**
+   * Wait for the service to meet a depends_on condition
+   *
+   * Polls Docker Compose until the condition is met, the container exits,
+   * or the timeout is reached.
+   *
+   * @param {ServiceDependsOnCondition} condition - The condition to wait for
+   * @param {number} [timeout] - Max time to wait in milliseconds
+   * @param {number} [interval] - Time between checks in milliseconds
+   * @returns {TryCatchResult<boolean>} - True if the condition was met
+   */
+  public async waitForCondition(
+    condition: ServiceDependsOnCondition = 'service_healthy',
+    { timeout = SERVICE_READY_TIMEOUT_MS, interval = SERVICE_READY_INTERVAL_MS }: {
+      timeout?: number
+      interval?: number
+    } = {},
+  ): Promise<TryCatchResult<boolean>> {
+    // Services that don't run any containers are always ready, e.g. Ollama on the host
+    if (this.containerNames.length === 0) {
+      return success<boolean>(true)
+    }
+
+    const deadline = Date.now() + timeout
+    while (true) {
+      const stateResult = await this.checkState()
+      if (stateResult.success && this.isConditionMet(condition)) {
+        return success<boolean>(true, `✔️ ${this.name} is ${getConditionLabel(condition)}`)
+      }
+
+      // Return early if the container stopped and will never meet the condition
+      if (
+        condition !== 'service_completed_successfully' &&
+        ['exited', 'dead'].includes(String(this._state.get('state')))
+      ) {
+        return failure<boolean>(
+          `${this.name} exited before it was ${getConditionLabel(condition)}`,
+          stateResult,
+          false,
+        )
+      }
+
+      if (Date.now() >= deadline) {
+        return failure<boolean>(
+          `Timed out waiting for ${this.name} to be ${getConditionLabel(condition)}`,
+          stateResult,
+          false,
+        )
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, interval))
+    }
   }
 
   /**
@@ -302,7 +508,9 @@ export class Service {
    * @returns The container DNS host name and port, e.g. 'ollama:11434'
    */
   public getEndpoints(context: string = '*.*'): ExposeHost[] {
-    return getEndpoints(this, context, this._configInstance.env)
+    return getEndpoints(this, context, this._configInstance.env, {
+      basePort: this._configInstance.basePort,
+    })
   }
 
   /**
@@ -350,6 +558,11 @@ export class Service {
       await this.prepareVolumes({ silent }),
     ])
 
+    // Compose files from repos are only available after the repo is prepared
+    if (results.success) {
+      results.collect([await this.prepareComposeOverride()])
+    }
+
     if (!results.success) {
       return failure<boolean>(`Failed to prepare service environment: ${this.name}`, results, false)
     }
@@ -377,6 +590,7 @@ export class Service {
       pull,
       silent,
       createBaseDir: false, // Base dir is ensured in config
+      offline: this._configInstance.offline,
     })
   }
 
@@ -391,6 +605,23 @@ export class Service {
     return await prepareServiceVolumes(this, this._configInstance.volumesDir)
   }
 
+  /**
+   * Generate the compose override to namespace container names, offset host ports
+   * and pin images to the digests in the images lock
+   *
+   * @returns {TryCatchResult<string>} - The path to the override file
+   */
+  protected async prepareComposeOverride(): Promise<TryCatchResult<string>> {
+    const config = this._configInstance
+    return await prepareComposeOverride(this, {
+      projectName: config.projectName,
+      basePort: config.basePort,
+      overrideFile: this.composeOverrideFile,
+      env: config.env,
+      images: config.getPinnedImages(),
+    })
+  }
+
   //
   // Service Actions
   //
@@ -405,7 +636,7 @@ export class Service {
   public async init(envVars: Record<string, string> = {}): Promise<TryCatchResult<boolean>> {
     const results = success<boolean>(true)
 
-    const env = await this._configInstance.env
+    const env = this._configInstance.env
 
     // Create postgres schema if needed
     if (this.config.init?.postgres_schema) {
@@ -415,49 +646,17 @@ export class Service {
       if (env[dbEnvKeys.user] && env[dbEnvKeys.pass]) {
         results.addMessage('debug', 'Postgres schema already exists, skipping')
       } else {
-        // Get postgres service
-        const postgresService = this._configInstance.getServiceByProvides('postgres')
-        if (!postgresService) {
-          results.addMessage('error', `Postgres service not found, required by ${this.name}`)
-          return failure<boolean>(`Unable to initialize ${this.name}`, results, false)
-        }
-
-        // Start postgres service if not running
-        if (!await postgresService!.isRunning()) {
-          const postgresResults = await postgresService.start()
-          if (!postgresResults.success) {
-            results.collect([postgresResults])
-            return failure<boolean>(`Unable to initialize ${this.name}`, results, false)
-          }
-          // Wait 3 seconds for postgres to start
-          await new Promise((resolve) => setTimeout(resolve, 3000))
-        }
-
-        if (!env.POSTGRES_PASSWORD) {
-          results.addMessage('error', 'Postgres password not set, required by service')
+        const postgresResults = await this.preparePostgres(env)
+        if (!postgresResults.success) {
+          results.collect([postgresResults])
           return failure<boolean>(`Unable to initialize ${this.name}`, results, false)
         }
 
-        // Try to connect up to 3 times to postgres
-        for (let attempt = 1; attempt <= 3; attempt++) {
-          if (await isPostgresConnectionValid({ password: env.POSTGRES_PASSWORD })) {
-            results.addMessage('debug', `Successfully connected to Postgres`)
-            break
-          }
-
-          if (attempt === 3) {
-            results.addMessage('error', 'Failed to connect to Postgres after 3 attempts')
-            return failure<boolean>(`Unable to initialize ${this.name}`, results, false)
-          }
-
-          // Wait longer on each attempt
-          await new Promise((resolve) => setTimeout(resolve, 2000 * attempt))
-        }
-
         // Postgres is connected, create the schema
-        const credentials = await createServiceSchema(this.service, {
-          password: env.POSTGRES_PASSWORD,
-        })
+        const credentials = await createServiceSchema(
+          this.service,
+          this.getPostgresConnection(env),
+        )
 
         // Update env vars with new schema credentials
         envVars[dbEnvKeys.user] = credentials.username
@@ -478,16 +677,7 @@ export class Service {
           results.addMessage('debug', `Env var ${key} already set, skipping`)
           return
         }
-        if (settings.method === 'generateSecretKey') {
-          envVars[key] = generateSecretKey(settings.length || 32)
-        } else if (settings.method === 'generateRandomBase64') {
-          envVars[key] = generateRandomBase64(settings.length || 32)
-        } else if (settings.method === 'generateUUID') {
-          envVars[key] = generateUUID()
-        }
-        if (settings.prefix) {
-          envVars[key] = `${settings.prefix}${envVars[key]}`
-        }
+        envVars[key] = generateSecret(settings)
         results.addMessage('info', `Set env var ${key} to ${envVars[key]}`)
       })
     }
@@ -501,6 +691,215 @@ export class Service {
     return results
   }
 
+  /**
+   * Get the secrets generated for the service during init
+   *
+   * Includes the init.generate env vars and the postgres_schema password.
+   * @returns {Record<string, IServiceSecret>} Map of env var name to the secret settings
+   */
+  public getGeneratedSecrets(): Record<string, IServiceSecret> {
+    const secrets: Record<string, IServiceSecret> = {}
+    Object.entries(this.config.init?.generate || {}).forEach(([key, settings]) => {
+      secrets[key] = { ...settings }
+    })
+    const postgresSchema = this.config.init?.postgres_schema
+    if (postgresSchema?.pass) {
+      // Same length as the passwords generated by createServiceSchema
+      secrets[postgresSchema.pass] = {
+        method: 'generateSecretKey',
+        length: 22,
+        postgresUserKey: postgresSchema.user,
+      }
+    }
+    return secrets
+  }
+
+  /**
+   * Get the generated secrets that are rotated by default
+   *
+   * Secrets with rotatable: false in init.generate are stored in the service's data,
+   * e.g. a database password or encryption key, and are only rotated when named explicitly.
+   * @returns {string[]} The env var names
+   */
+  public getRotatableSecrets(): string[] {
+    const secrets = this.getGeneratedSecrets()
+    return Object.keys(secrets).filter((key) => secrets[key].rotatable !== false)
+  }
+
+  /**
+   * Generate new values for the service's generated secrets
+   *
+   * Postgres schema passwords are updated in the database with ALTER ROLE.
+   * The .env file is not updated, call config.setEnvFileVars with the returned values.
+   *
+   * @param {string[]} keys - The env vars to rotate, defaults to the rotatable generated secrets
+   * @returns {TryCatchResult<Record<string, string>>} Map of env var name to the new value
+   */
+  public async rotateSecrets(keys?: string[]): Promise<TryCatchResult<Record<string, string>>> {
+    const secrets = this.getGeneratedSecrets()
+    const results = success<Record<string, string>>({})
+    const envVars: Record<string, string> = {}
+
+    for (const key of keys || this.getRotatableSecrets()) {
+      const settings = secrets[key]
+      if (!settings) {
+        return failure<Record<string, string>>(
+          `${key} is not a generated secret of ${this.name}`,
+          results,
+        )
+      }
+      envVars[key] = generateSecret(settings)
+    }
+
+    // Update the postgres schema user passwords
+    const postgresKeys = Object.keys(envVars).filter((key) => secrets[key].postgresUserKey)
+    if (postgresKeys.length > 0) {
+      const env = this._configInstance.env
+      const postgresResults = await this.preparePostgres(env)
+      if (!postgresResults.success) {
+        return failure<Record<string, string>>(
+          `Unable to rotate postgres password for ${this.name}`,
+          results.collect([postgresResults]),
+        )
+      }
+      for (const key of postgresKeys) {
+        const username = env[secrets[key].postgresUserKey!]
+        if (!username) {
+          return failure<Record<string, string>>(
+            `Postgres user ${secrets[key].postgresUserKey} is not set, run llmn init ${this.service}`,
+            results,
+          )
+        }
+        try {
+          await setServiceSchemaPassword(username, envVars[key], this.getPostgresConnection(env))
+          results.addMessage('info', `Updated postgres password for ${username}`)
+        } catch (error) {
+          return failure<Record<string, string>>(
+            `Unable to update postgres password for ${username}`,
+            { data: null, error: error as Error, success: false },
+          )
+        }
+      }
+    }
+
+    results.data = envVars
+    return results
+  }
+
+  /**
+   * Get what a command would do to the service without running it
+   *
+   * Used by the --dry-run option of start, stop, update and config.
+   *
+   * @param {ServicePlanAction} action - The action the command would take on the service
+   * @returns {Promise<IServicePlan>} The compose file, profiles, env vars, repo and volumes
+   */
+  public async getPlan(action: ServicePlanAction = 'none'): Promise<IServicePlan> {
+    const config = this._configInstance
+    const env = config.env
+    const generated = Object.keys(this.getGeneratedSecrets())
+    const postgresSchema = this.config.init?.postgres_schema
+    const keys = [
+      ...generated,
+      ...(postgresSchema ? [postgresSchema.user, postgresSchema.schema] : []),
+    ].filter((key, index, all): key is string => !!key && all.indexOf(key) === index)
+    const missing = keys.filter((key) => !env[key])
+
+    const volumes: string[] = []
+    for (const volume of this.volumes) {
+      if (!(await dirExists(path.join(config.volumesDir, volume))).data) {
+        volumes.push(volume)
+      }
+    }
+
+    const repoDir = this.repoDir
+    return {
+      service: this.service,
+      name: this.name,
+      action,
+      enabled: this.isEnabled(),
+      auto: config.isServiceAutoEnabled(this),
+      composeFile: this.composeFile,
+      overrideFile: this.composeOverrideFile,
+      profiles: this.getProfiles(),
+      env: missing,
+      secrets: config.secretsEnabled ? missing.filter((key) => config.isSecretKey(key)) : [],
+      repo: this.repoConfig?.url && repoDir
+        ? {
+          url: this.repoConfig.url,
+          dir: repoDir,
+          action: (await dirExists(repoDir)).data ? 'none' : 'clone',
+        }
+        : null,
+      volumes,
+    }
+  }
+
+  /**
+   * Get the connection config for the project postgres database on the host
+   */
+  protected getPostgresConnection(env: Record<string, string>): ConnectionConfig {
+    return {
+      password: env.POSTGRES_PASSWORD,
+      port: getHostPort(
+        Number(env.POSTGRES_PORT) || 5432,
+        this._configInstance.basePort,
+        env,
+        // Service that publishes the postgres port, e.g. supabase
+        this._configInstance.getServiceContainers('postgres')?.[0].service,
+      ),
+      ...(env.POSTGRES_TENANT ? { tenant: env.POSTGRES_TENANT } : {}),
+    }
+  }
+
+  /**
+   * Start the postgres service if needed and wait for it to accept connections
+   */
+  protected async preparePostgres(env: Record<string, string>): Promise<TryCatchResult<boolean>> {
+    const results = success<boolean>(true)
+
+    // Get postgres service
+    const postgresService = this._configInstance.getServiceByProvides('postgres')
+    if (!postgresService) {
+      return failure<boolean>(`Postgres service not found, required by ${this.name}`, results, false)
+    }
+
+    // Start postgres service if not running
+    if (!await postgresService.isRunning()) {
+      const postgresResults = await postgresService.start()
+      if (!postgresResults.success) {
+        return failure<boolean>('Unable to start postgres', results.collect([postgresResults]))
+      }
+    }
+
+    // Wait for postgres to pass its healthcheck
+    const readyResults = await postgresService.waitForCondition('service_healthy')
+    if (!readyResults.success) {
+      return failure<boolean>('Postgres is not healthy', results.collect([readyResults]), false)
+    }
+
+    if (!env.POSTGRES_PASSWORD) {
+      return failure<boolean>('Postgres password not set, required by service', results, false)
+    }
+
+    // Try to connect up to 3 times to postgres
+    for (let attempt = 1; attempt <= 3; attempt++) {
+      if (await isPostgresConnectionValid(this.getPostgresConnection(env))) {
+        results.addMessage('debug', `Successfully connected to Postgres`)
+        break
+      }
+
+      if (attempt === 3) {
+        return failure<boolean>('Failed to connect to Postgres after 3 attempts', results, false)
+      }
+
+      // Wait longer on each attempt
+      await new Promise((resolve) => setTimeout(resolve, 2000 * attempt))
+    }
+
+    return results
+  }
+
   /**
    * Configure the service
    * @param {boolean} [silent] - Whether to run the configuration in silent or interactive mode
@@ -534,6 +933,9 @@ export class Service {
       )
     }
 
+    // Offline projects only use the images loaded from the bundle
+    const pullNever = config.offline && (await getRuntime()).features.pullPolicy
+
     const results = await tryDockerCompose('up', {
       projectName: this._configInstance.projectName,
       composeFile: this.composeFile,
@@ -542,6 +944,7 @@ export class Service {
       args: [
         '-d',
         options.build ? '--build' : false,
+        ...(pullNever ? ['--pull', 'never'] : []),
       ],
       env: options.envVars,
       silent: options.silent,
@@ -595,6 +998,13 @@ export class Service {
   public async update(
     options: IServiceActionOptions = {},
   ): Promise<TryCatchResult<boolean>> {
+    if (this._configInstance.offline) {
+      return failure<boolean>(
+        `Unable to update ${this.name} while offline, load a new bundle instead`,
+        success<boolean>(false),
+        false,
+      )
+    }
     const result = success<boolean>(true)
     result.collect([
       await tryDockerCompose('pull', {
@@ -624,4 +1034,51 @@ export class Service {
   public async showStartInfo(_options: IServiceActionOptions = {}): Promise<void> {
     // Override in subclasses to show additional info
   }
+
+  /**
+   * Get additional status info for the service, shown by the info command
+   *
+   * @returns {Promise<Record<string, unknown> | null>} - The info or null if there is none
+   */
+  // deno-lint-ignore require-await
+  public async getInfo(): Promise<Record<string, unknown> | null> {
+    // Override in subclasses to add info, see PgBackupService for an example
+    return null
+  }
+}
+
+/**
+ * Get a human readable label for a depends_on condition
+ *
+ * @param {ServiceDependsOnCondition} condition - The condition
+ * @returns {string} The label, e.g. 'healthy'
+ */
+export function getConditionLabel(condition: ServiceDependsOnCondition): string {
+  switch (condition) {
+    case 'service_healthy':
+      return 'healthy'
+    case 'service_completed_successfully':
+      return 'completed'
+    case 'service_started':
+    default:
+      return 'started'
+  }
+}
+
+/**
+ * Generate a secret value from the init.generate settings in llemonstack.yaml
+ *
+ * @param {IServiceSecret} settings - The method, length and prefix of the secret
+ * @returns {string} The new secret value
+ */
+export function generateSecret(settings: IServiceSecret): string {
+  let value = ''
+  if (settings.method === 'generateSecretKey') {
+    value = generateSecretKey(settings.length || 32)
+  } else if (settings.method === 'generateRandomBase64') {
+    value = generateRandomBase64(settings.length || 32)
+  } else if (settings.method === 'generateUUID') {
+    value = generateUUID()
+  }
+  return settings.prefix ? `${settings.prefix}${value}` : value
 }
@@ -0,0 +1,131 @@
+import { tryDockerComposePs } from '@/lib/docker.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import type { Callback, ObservableMapValue } from '@/lib/utils/observable.ts'
+import type { IServiceState } from '@/types'
+import type { Config } from '../config/config.ts'
+import type { ServicesMap } from './services-map.ts'
+
+// Default time between Docker Compose ps checks
+const STATUS_POLL_INTERVAL_MS = 2_000
+
+type StateChangeCallback = Callback<ObservableMapValue<keyof IServiceState, unknown>>
+
+/**
+ * Keeps the state of services up to date with Docker Compose
+ *
+ * Runs a single `docker compose ps` for the whole project on each refresh and updates
+ * the state of each service. Listeners are called once per refresh when any service
+ * state changed, excluding last_checked.
+ *
+ * @example
+ * ```ts
+ * const monitor = new ServiceStatusMonitor(config)
+ * monitor.onChange((services) => render(services))
+ * monitor.start()
+ * ```
+ */
+export class ServiceStatusMonitor {
+  protected _config: Config
+  protected _services: ServicesMap
+  protected _interval: number
+  protected _timer: number | undefined
+  protected _changed = false
+  protected _listeners: Set<(services: ServicesMap) => void> = new Set()
+  protected _stateListener: StateChangeCallback
+
+  constructor(
+    config: Config,
+    { services = config.getAllServices(), interval = STATUS_POLL_INTERVAL_MS }: {
+      services?: ServicesMap
+      interval?: number
+    } = {},
+  ) {
+    this._config = config
+    this._services = services
+    this._interval = interval
+
+    this._stateListener = (update) => {
+      if (!update || update.key === 'last_checked') {
+        return
+      }
+      // Compare serialized values so new arrays with the same ports are not a change
+      if (JSON.stringify(update.oldValue) !== JSON.stringify(update.newValue)) {
+        this._changed = true
+      }
+    }
+    this._services.forEach((service) => service.listenState(this._stateListener))
+  }
+
+  public get services(): ServicesMap {
+    return this._services
+  }
+
+  public get isRunning(): boolean {
+    return this._timer !== undefined
+  }
+
+  /**
+   * Add a listener that is called after a refresh when any service state changed
+   *
+   * @param callback - Called with the monitored services
+   */
+  public onChange(callback: (services: ServicesMap) => void): void {
+    this._listeners.add(callback)
+  }
+
+  /**
+   * Update the state of all monitored services
+   *
+   * @returns {TryCatchResult<boolean>} True if any service state changed
+   */
+  public async refresh(): Promise<TryCatchResult<boolean>> {
+    const psResults = await tryDockerComposePs(this._config.projectName)
+    if (!psResults.success || !psResults.data) {
+      return failure<boolean>('Failed to get service status from Docker Compose', psResults)
+    }
+
+    this._changed = false
+    const data = psResults.data
+    this._services.forEach((service) => service.updateState(data))
+
+    const changed = this._changed
+    if (changed) {
+      this._listeners.forEach((callback) => callback(this._services))
+    }
+    return success<boolean>(changed)
+  }
+
+  /**
+   * Start polling Docker Compose for changes
+   *
+   * @param onError - Called when a refresh fails, polling continues
+   */
+  public start(onError?: (result: TryCatchResult<boolean>) => void): void {
+    if (this.isRunning) {
+      return
+    }
+
+    const poll = async () => {
+      const result = await this.refresh()
+      if (!result.success) {
+        onError?.(result)
+      }
+      // Schedule the next poll after the refresh completes to avoid overlapping checks
+      if (this.isRunning) {
+        this._timer = setTimeout(poll, this._interval)
+      }
+    }
+
+    this._timer = setTimeout(poll, 0)
+  }
+
+  /**
+   * Stop polling and remove all listeners
+   */
+  public stop(): void {
+    clearTimeout(this._timer)
+    this._timer = undefined
+    this._listeners.clear()
+    this._services.forEach((service) => service.unlistenState(this._stateListener))
+  }
+}
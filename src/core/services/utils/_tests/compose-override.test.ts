@@ -0,0 +1,41 @@
+import { assertEquals, assertStringIncludes } from 'jsr:@std/assert'
+import * as yaml from 'jsr:@std/yaml'
+import { ComposeConfigJson, getComposeOverrideYaml } from '../compose-override.ts'
+
+const COMPOSE_CONFIG: ComposeConfigJson = {
+  services: {
+    n8n: {
+      image: 'n8nio/n8n:latest',
+      container_name: 'n8n',
+      networks: { default: null },
+      ports: [
+        { target: 5678, published: '5678', protocol: 'tcp' },
+        '127.0.0.1:5679:5679',
+        { target: 6000 },
+      ],
+    },
+  },
+}
+
+Deno.test('getComposeOverrideYaml offsets and remaps the published ports', () => {
+  const overrideYaml = getComposeOverrideYaml(COMPOSE_CONFIG, {
+    projectName: 'project',
+    basePort: 10000,
+    source: 'services/n8n/docker-compose.yaml',
+    service: 'n8n',
+    env: { LLEMONSTACK_HOST_PORT_N8N_5678: '5700' },
+    images: { 'n8nio/n8n:latest': 'n8nio/n8n@sha256:abc' },
+  })
+  assertStringIncludes(overrideYaml, 'ports: !override')
+
+  const override = yaml.parse(overrideYaml.replace(' !override', '')) as {
+    services: Record<string, Record<string, unknown>>
+  }
+  assertEquals(override.services.n8n, {
+    labels: { 'dev.llemonstack.base_port': '10000' },
+    image: 'n8nio/n8n@sha256:abc',
+    container_name: 'project-n8n',
+    networks: { default: { aliases: ['n8n'] } },
+    ports: ['5700:5678/tcp', '127.0.0.1:15679:5679', '6000'],
+  })
+})
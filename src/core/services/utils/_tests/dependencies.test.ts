@@ -0,0 +1,109 @@
+import { Config } from '@/core/config/config.ts'
+import { Service } from '@/core/services/service.ts'
+import { ServicesMap } from '@/core/services/services-map.ts'
+import { ServiceYaml } from '@/types'
+import { assertEquals } from 'jsr:@std/assert'
+import { getDependencyWaves } from '../dependencies.ts'
+
+function createService(
+  service: string,
+  { provides = {}, depends_on = {} }: {
+    provides?: Record<string, string>
+    depends_on?: ServiceYaml['depends_on']
+  } = {},
+): Service {
+  return new Service({
+    serviceYaml: {
+      service,
+      name: service,
+      description: '',
+      disabled: false,
+      compose_file: 'docker-compose.yaml',
+      service_group: 'apps',
+      provides,
+      depends_on,
+    },
+    serviceDir: `/tmp/${service}`,
+    config: Config.getInstance(),
+    configSettings: { enabled: true },
+    enabled: true,
+  })
+}
+
+/**
+ * Resolve dependencies by provides key, same as Config.getServiceDependencies
+ */
+function resolver(services: Service[]) {
+  return (service: Service) =>
+    new ServicesMap(
+      services.filter((s) =>
+        s.id !== service.id && s.provides.some(([key]) => service.depends_on.includes(key))
+      ),
+    )
+}
+
+function waveNames(waves: ServicesMap[] | null): string[][] {
+  return (waves || []).map((wave) => wave.map((s) => s.service).sort())
+}
+
+Deno.test('getDependencyWaves', async (t) => {
+  const supabase = createService('supabase', { provides: { postgres: 'db' } })
+  const redis = createService('redis', { provides: { redis: 'redis' } })
+  const clickhouse = createService('clickhouse', { provides: { clickhouse: 'clickhouse' } })
+  const langfuse = createService('langfuse', {
+    provides: { langfuse: 'langfuse' },
+    depends_on: {
+      postgres: { condition: 'service_healthy' },
+      redis: { condition: 'service_healthy' },
+      clickhouse: { condition: 'service_healthy' },
+    },
+  })
+  const litellm = createService('litellm', {
+    provides: { litellm: 'litellm' },
+    depends_on: {
+      postgres: { condition: 'service_healthy' },
+      langfuse: { condition: 'service_started' },
+    },
+  })
+  const dozzle = createService('dozzle', { provides: { dozzle: 'dozzle' } })
+
+  await t.step('sorts services into dependency waves', () => {
+    const all = [litellm, langfuse, dozzle, clickhouse, redis, supabase]
+    const result = getDependencyWaves(new ServicesMap(all), resolver(all))
+
+    assertEquals(result.success, true)
+    assertEquals(waveNames(result.data), [
+      ['clickhouse', 'dozzle', 'redis', 'supabase'],
+      ['langfuse'],
+      ['litellm'],
+    ])
+  })
+
+  await t.step('ignores dependencies that are not being started', () => {
+    const all = [litellm, langfuse, dozzle, clickhouse, redis, supabase]
+    const result = getDependencyWaves(new ServicesMap([litellm, dozzle]), resolver(all))
+
+    assertEquals(result.success, true)
+    assertEquals(waveNames(result.data), [['dozzle', 'litellm']])
+  })
+
+  await t.step('returns a failure for circular dependencies', () => {
+    const a = createService('a', {
+      provides: { a: 'a' },
+      depends_on: { b: { condition: 'service_started' } },
+    })
+    const b = createService('b', {
+      provides: { b: 'b' },
+      depends_on: { a: { condition: 'service_started' } },
+    })
+    const all = [a, b, dozzle]
+    const result = getDependencyWaves(new ServicesMap(all), resolver(all))
+
+    assertEquals(result.success, false)
+  })
+
+  await t.step('defaults missing conditions to service_started', () => {
+    const a = createService('a', { depends_on: { b: {} as { condition: 'service_started' } } })
+    assertEquals(a.dependsOnConditions, [['b', 'service_started']])
+  })
+})
@@ -0,0 +1,77 @@
+import { validateServiceYaml } from '@/core/config/lib/schema.ts'
+import { assert, assertEquals, assertStringIncludes, assertThrows } from 'jsr:@std/assert'
+import * as yaml from 'jsr:@std/yaml'
+import {
+  getComposeScaffoldYaml,
+  getDefaultHealthcheck,
+  getServiceClassName,
+  getServiceScaffoldTs,
+  getServiceScaffoldYaml,
+  IServiceScaffold,
+  parseDependsOn,
+  parsePort,
+  parseSecret,
+} from '../scaffold.ts'
+
+const SPEC: IServiceScaffold = {
+  service: 'my-app',
+  name: 'My App',
+  description: 'Example app: with a colon',
+  serviceGroup: 'apps',
+  image: 'nginx:latest',
+  ports: [{ host: 8080, container: 80 }],
+  provides: ['my-app'],
+  dependsOn: { postgres: 'service_healthy' },
+  exposes: { host: 'http://localhost:8080', internal: 'http://my-app:80' },
+  secrets: { MY_APP_KEY: { method: 'generateSecretKey', length: 32 } },
+  healthcheck: getDefaultHealthcheck([{ host: 8080, container: 80 }]),
+}
+
+Deno.test('getServiceScaffoldYaml generates a valid llemonstack.yaml', () => {
+  const result = validateServiceYaml(getServiceScaffoldYaml(SPEC), 'my-app/llemonstack.yaml')
+  assert(result.success)
+  assertEquals(result.messages.length, 0)
+  assertEquals(result.data?.description, SPEC.description)
+  assertEquals(result.data?.provides, { 'my-app': 'my-app' })
+  assertEquals(result.data?.init?.generate?.MY_APP_KEY, { method: 'generateSecretKey', length: 32 })
+})
+
+Deno.test('getComposeScaffoldYaml uses the project network, dozzle labels and healthcheck', () => {
+  const compose = yaml.parse(getComposeScaffoldYaml(SPEC)) as {
+    networks: Record<string, { name: string; external: boolean }>
+    services: Record<string, Record<string, unknown>>
+  }
+  assertEquals(compose.networks.default, { name: '${LLEMONSTACK_NETWORK_NAME}', external: true })
+  const service = compose.services['my-app']
+  assertEquals(service.image, 'nginx:latest')
+  assertEquals(service.labels, { 'dev.dozzle.group': 'Apps' })
+  assertEquals(service.ports, ['8080:80'])
+  assertEquals(service.environment, ['MY_APP_KEY=${MY_APP_KEY}'])
+  assertStringIncludes(JSON.stringify(service.healthcheck), 'http://localhost:80/')
+})
+
+Deno.test('getServiceScaffoldTs uses a valid class name', () => {
+  assertEquals(getServiceClassName('my-app'), 'MyAppService')
+  assertEquals(getServiceClassName('2fa-proxy'), 'Custom2faProxyService')
+  assertStringIncludes(
+    getServiceScaffoldTs({ ...SPEC, service: '2fa-proxy' }),
+    'export class Custom2faProxyService extends Service {',
+  )
+})
+
+Deno.test('scaffold option parsers', () => {
+  assertEquals(parsePort('8080'), { host: 8080, container: 8080 })
+  assertEquals(parsePort('8080:80'), { host: 8080, container: 80 })
+  assertThrows(() => parsePort('http'))
+  assertEquals(parseDependsOn('postgres'), ['postgres', 'service_healthy'])
+  assertEquals(parseDependsOn('redis:service_started'), ['redis', 'service_started'])
+  assertThrows(() => parseDependsOn('redis:healthy'))
+  assertEquals(parseSecret('MY_KEY'), ['MY_KEY', { method: 'generateSecretKey' }])
+  assertEquals(parseSecret('MY_ID:generateUUID'), ['MY_ID', { method: 'generateUUID' }])
+  assertEquals(parseSecret('MY_KEY:generateRandomBase64:16'), [
+    'MY_KEY',
+    { method: 'generateRandomBase64', length: 16 },
+  ])
+  assertThrows(() => parseSecret('my_key'))
+  assertThrows(() => parseSecret('MY_KEY:random'))
+})
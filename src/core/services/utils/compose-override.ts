@@ -0,0 +1,260 @@
+import { toLongPort } from '@/lib/compose.ts'
+import { getRuntime, tryDockerCompose } from '@/lib/docker.ts'
+import { ensureDir, path } from '@/lib/fs.ts'
+import { BASE_PORT_LABEL, getHostPort, HOST_PORT_ENV_PREFIX } from '@/lib/ports.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import type { ComposePort, ServiceType } from '@/types'
+import * as yaml from 'jsr:@std/yaml'
+
+// Subset of the `docker compose config --format json` output used to generate overrides
+export interface ComposeConfigJson {
+  services?: Record<string, {
+    image?: string
+    build?: unknown
+    container_name?: string
+    ports?: Array<ComposePort | string | number>
+    networks?: Record<string, unknown> | string[]
+  }>
+}
+
+// Image used by a compose service, see getComposeImages
+export interface IComposeImage {
+  composeService: string
+  image: string
+  build: boolean // Built from a Dockerfile
+}
+
+/**
+ * Convert a compose port to the short syntax with the published port offset
+ *
+ * @example
+ * toShortPort({ target: 5678, published: '5678', protocol: 'tcp' }, 10000) // '15678:5678/tcp'
+ */
+function toShortPort(
+  port: ComposePort,
+  basePort: number,
+  env: Record<string, string> = {},
+  service?: string,
+): string {
+  let published = String(port.published ?? '')
+  // Only offset single ports, ranges and random ports are left as is
+  if (/^\d+$/.test(published)) {
+    published = getHostPort(Number(published), basePort, env, service).toString()
+  }
+  return [
+    port.host_ip ? `${port.host_ip}:` : '',
+    published ? `${published}:` : '',
+    port.target,
+    port.protocol ? `/${port.protocol}` : '',
+  ].join('')
+}
+
+/**
+ * Generate the compose override yaml for a resolved compose config
+ *
+ * - container_name is prefixed with the project name so multiple projects can run at once
+ * - The original container name is added as a network alias to keep internal hostnames working
+ * - Published host ports are offset by the project base port or remapped by env var
+ * - Images are pinned to the digests in the images lock, built images are left as is
+ *
+ * Written by hand instead of with yaml.stringify, the !override tag is required to replace
+ * the ports instead of merging them with the ports in the original compose file.
+ */
+export function getComposeOverrideYaml(
+  composeConfig: ComposeConfigJson,
+  { projectName, basePort, source, service, overrideTags = true, env = {}, images = {} }: {
+    projectName: string
+    basePort: number
+    source: string
+    service?: string // LLemonStack service the compose file belongs to, used for remapped ports
+    overrideTags?: boolean // Set to false for compose commands that don't support !override
+    env?: Record<string, string> // Env vars with remapped host ports, see getHostPort
+    images?: Record<string, string> // Pinned images by image reference, see getPinnedImages
+  },
+): string {
+  const q = (value: string | number) => JSON.stringify(String(value))
+  const lines = [
+    `# Generated by LLemonStack from ${source}`,
+    '# Do not edit, this file is regenerated each time the stack starts',
+    'services:',
+  ]
+
+  for (const [composeName, composeService] of Object.entries(composeConfig.services || {})) {
+    lines.push(`  ${q(composeName)}:`)
+    lines.push('    labels:')
+    lines.push(`      ${BASE_PORT_LABEL}: ${q(basePort)}`)
+
+    if (composeService.image && !composeService.build && images[composeService.image]) {
+      lines.push(`    image: ${q(images[composeService.image])}`)
+    }
+
+    const containerName = composeService.container_name
+    if (containerName && !containerName.startsWith(`${projectName}-`)) {
+      lines.push(`    container_name: ${q(`${projectName}-${containerName}`)}`)
+      // Services with network_mode set don't have networks
+      const networks = Array.isArray(composeService.networks)
+        ? composeService.networks
+        : Object.keys(composeService.networks || {})
+      if (networks.length) {
+        lines.push('    networks:')
+      }
+      networks.forEach((network) => {
+        lines.push(`      ${q(network)}:`)
+        lines.push('        aliases:')
+        lines.push(`          - ${q(containerName)}`)
+      })
+    }
+
+    // Without !override the ports would be added to the original ports instead of replacing them
+    if (composeService.ports?.length && overrideTags) {
+      lines.push('    ports: !override')
+      composeService.ports.forEach((port) =>
+        lines.push(`      - ${q(toShortPort(toLongPort(port), basePort, env, service))}`)
+      )
+    }
+  }
+
+  return lines.join('\n') + '\n'
+}
+
+/**
+ * Resolve a service compose file with `docker compose config`
+ *
+ * Env vars, profiles and services from extended or included files are resolved.
+ * The generated override is not included.
+ *
+ * @param service - The service to resolve the compose file for
+ * @returns The resolved compose config
+ */
+export async function getComposeConfig(
+  service: ServiceType,
+  { projectName }: { projectName: string },
+): Promise<TryCatchResult<ComposeConfigJson>> {
+  const runtime = await getRuntime()
+  const configResult = await tryDockerCompose('config', {
+    projectName,
+    composeFile: service.composeFile,
+    profiles: service.getProfiles(),
+    args: runtime.features.configJson ? ['--format', 'json'] : [],
+    captureOutput: true,
+    silent: true,
+    composeOverrides: false, // Resolve the original compose file without the previous override
+  })
+  if (!configResult.success || !configResult.data) {
+    return failure<ComposeConfigJson>(
+      `Failed to resolve compose file for ${service.name}`,
+      configResult,
+    )
+  }
+
+  try {
+    // JSON is valid yaml, other runtimes only output yaml
+    return success<ComposeConfigJson>(
+      yaml.parse(configResult.data.toString()) as ComposeConfigJson,
+    )
+  } catch (error) {
+    return failure<ComposeConfigJson>(`Failed to parse compose config for ${service.name}`, {
+      data: null,
+      error: error as Error,
+      success: false,
+    })
+  }
+}
+
+/**
+ * Get the images used by a service
+ *
+ * Built images without an image name use the compose default name, `<project>-<service>`.
+ *
+ * @param service - The service to get the images for
+ * @returns The images with the compose service that uses each image
+ */
+export async function getComposeImages(
+  service: ServiceType,
+  { projectName }: { projectName: string },
+): Promise<TryCatchResult<IComposeImage[]>> {
+  const configResult = await getComposeConfig(service, { projectName })
+  if (!configResult.success || !configResult.data) {
+    return failure<IComposeImage[]>(`Unable to get the images for ${service.name}`, configResult)
+  }
+  const images = Object.entries(configResult.data.services || {}).map((
+    [composeService, { image, build }],
+  ): IComposeImage => ({
+    composeService,
+    image: image || (build ? `${projectName}-${composeService}` : ''),
+    build: !!build,
+  })).filter(({ image }) => image)
+  return success<IComposeImage[]>(images)
+}
+
+/**
+ * Write the compose override file for a service
+ *
+ * Resolves the service compose file with `docker compose config` so ports set with env vars
+ * and services from extended files are included in the override.
+ *
+ * @param service - The service to generate the override for
+ * @returns The path to the override file
+ */
+export async function prepareComposeOverride(
+  service: ServiceType,
+  { projectName, basePort, overrideFile, env = {}, images = {} }: {
+    projectName: string
+    basePort: number
+    overrideFile: string
+    env?: Record<string, string>
+    images?: Record<string, string>
+  },
+): Promise<TryCatchResult<string>> {
+  const runtime = await getRuntime()
+  const remapped = Object.keys(env).some((key) => key.startsWith(HOST_PORT_ENV_PREFIX))
+  if (!runtime.features.overrideTags && (basePort || remapped)) {
+    return failure<string>(
+      `${runtime.compose.join(' ')} doesn't support !override tags required for base ports ` +
+        'and remapped ports, upgrade it or set ports.base to 0 in config.json',
+      { data: null, error: new Error('Compose override tags not supported'), success: false },
+    )
+  }
+
+  const configResult = await getComposeConfig(service, { projectName })
+  if (!configResult.success || !configResult.data) {
+    return failure<string>(`Failed to generate compose override for ${service.name}`, configResult)
+  }
+
+  try {
+    const overrideYaml = getComposeOverrideYaml(configResult.data, {
+      projectName,
+      basePort,
+      source: service.composeFile,
+      service: service.service,
+      overrideTags: runtime.features.overrideTags,
+      env,
+      images,
+    })
+    await ensureDir(path.dirname(overrideFile))
+    await Deno.writeTextFile(overrideFile, overrideYaml)
+  } catch (error) {
+    return failure<string>(`Failed to write compose override for ${service.name}`, {
+      data: null,
+      error: error as Error,
+      success: false,
+    })
+  }
+
+  const result = success<string>(overrideFile)
+  // Warn about images missing from the lock, e.g. a service enabled after the lock was created
+  if (Object.keys(images).length) {
+    const unlocked = Object.values(configResult.data.services || {})
+      .filter((composeService) => composeService.image && !composeService.build)
+      .map((composeService) => composeService.image!)
+      .filter((image) => !images[image] && !image.includes('@'))
+    if (unlocked.length) {
+      result.addMessage(
+        'warning',
+        `${service.name} images are not in the images lock: ${unlocked.join(', ')}\n` +
+          'Run `llmn lock` to pin them',
+      )
+    }
+  }
+  return result
+}
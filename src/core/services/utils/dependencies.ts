@@ -0,0 +1,58 @@
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import type { ServicesMapType, ServiceType } from '@/types'
+import { ServicesMap } from '../services-map.ts'
+
+/**
+ * Sort services into waves based on their dependencies
+ *
+ * Services in a wave only depend on services in earlier waves, so each wave can be
+ * started in parallel once the previous waves are ready. Dependencies that are not in
+ * the services map are ignored, e.g. disabled services.
+ *
+ * @example
+ * ```ts
+ * // [[supabase, redis], [litellm, n8n]]
+ * const waves = getDependencyWaves(services, (service) => config.getServiceDependencies(service))
+ * ```
+ *
+ * @param services - The services to sort
+ * @param getDependencies - Returns the services a service depends on
+ * @returns The services grouped into waves in start order
+ */
+export function getDependencyWaves(
+  services: ServicesMapType,
+  getDependencies: (service: ServiceType) => ServicesMapType,
+): TryCatchResult<ServicesMapType[]> {
+  const results = success<ServicesMapType[]>([])
+  const waves: ServicesMapType[] = []
+
+  // Cache each service's dependencies that are in the services map
+  const dependencies = new Map<string, string[]>()
+  services.forEach((service) => {
+    const deps = getDependencies(service).filterMap((dependency) =>
+      dependency.id !== service.id && services.has(dependency.id) ? dependency.id : false
+    )
+    dependencies.set(service.id, deps)
+  })
+
+  let remaining: ServicesMapType = new ServicesMap(services.toArray())
+  while (remaining.size > 0) {
+    // Services with no remaining dependencies can start in this wave
+    const wave = remaining.filter((service) =>
+      !dependencies.get(service.id)?.some((id) => remaining.has(id))
+    )
+
+    if (wave.size === 0) {
+      return failure<ServicesMapType[]>(
+        `Circular dependency detected between services: ${remaining.map((s) => s.name).join(', ')}`,
+        results,
+      )
+    }
+
+    waves.push(wave)
+    remaining = remaining.filter((service) => !wave.has(service.id))
+  }
+
+  results.data = waves
+  return results
+}
@@ -1,3 +1,4 @@
+import { offsetHostUrl } from '@/lib/ports.ts'
 import { expandEnvVars } from '@/lib/utils/envvars.ts'
 import { searchObjectPaths } from '@/lib/utils/search-object.ts'
 import type { ExposeHost, ServiceType } from '@/types'
@@ -8,12 +9,14 @@ import type { ExposeHost, ServiceType } from '@/types'
  * @param service - The service to search for
  * @param context - The context to search for
  * @param env - The environment variables to expand
+ * @param basePort - The project base port to offset host urls by
  * @returns The endpoints for the service
  */
 export function getEndpoints(
   service: ServiceType,
   context: string = 'host.*',
   env: Record<string, string> = {},
+  { basePort = 0 }: { basePort?: number } = {},
 ): ExposeHost[] {
   // Search the service config exposes sections
   const data = searchObjectPaths<ExposeHost>(service._config.exposes, context)
@@ -35,6 +38,11 @@ export function getEndpoints(
       host.info = expandEnvVars(host.info, env)
     }
 
+    // Host ports are offset by the project base port or remapped, see prepareComposeOverride
+    if (item.key.startsWith('host.')) {
+      host.url = offsetHostUrl(host.url, basePort, env, service.service)
+    }
+
     // Expand credentials from env vars
     if (item.data?.credentials) {
       host.credentials = {}
@@ -1,3 +1,9 @@
+export {
+  getComposeConfig,
+  getComposeImages,
+  prepareComposeOverride,
+} from './compose-override.ts'
+export { getDependencyWaves } from './dependencies.ts'
 export { getEndpoints } from './endpoints.ts'
 export { setupServiceRepo } from './repo.ts'
 export { prepareServiceVolumes } from './volumes.ts'
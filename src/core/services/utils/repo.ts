@@ -51,11 +51,13 @@ export async function setupServiceRepo(
     silent = false,
     captureOutput = false,
     createBaseDir = false,
+    offline = false, // Fail instead of cloning missing repos, skip pulls
   }: {
     pull?: boolean
     silent?: boolean
     captureOutput?: boolean
     createBaseDir?: boolean
+    offline?: boolean
   },
 ): Promise<TryCatchResult<boolean>> {
   const results = success<boolean>(true)
@@ -80,6 +82,16 @@ export async function setupServiceRepo(
     return failure<boolean>(`Error checking repo dir: ${repoDir}`, results, false)
   }
 
+  // Offline projects only have the repos from the loaded bundle
+  if (!repoDirResults.data && offline) {
+    return failure<boolean>(
+      `${service.name} repo not found and the project is offline: ${repoDir}\n` +
+        'Load a bundle that includes the repo with `llmn bundle load`',
+      results,
+      false,
+    )
+  }
+
   // If repo does not exist, clone it
   if (!repoDirResults.data) {
     const repoBaseDir = path.dirname(repoDir)
@@ -165,7 +177,9 @@ export async function setupServiceRepo(
     // Repo directory exists
 
     // Pull latest changes from remote if pull is true
-    if (pull) {
+    if (pull && offline) {
+      results.addMessage('debug', `${service.name} repo exists, skipping pull while offline`)
+    } else if (pull) {
       results.addMessage('debug', `${service.name} repo exists, pulling latest code...`)
       await runGit(results, {
         args: [
@@ -0,0 +1,278 @@
+import type { ServiceDependsOnCondition } from '@/types'
+
+export const SERVICE_GROUPS = ['apps', 'middleware', 'databases'] as const
+export const GENERATE_METHODS = ['generateSecretKey', 'generateRandomBase64', 'generateUUID'] as const
+export const DEPENDS_ON_CONDITIONS: ServiceDependsOnCondition[] = [
+  'service_started',
+  'service_healthy',
+  'service_completed_successfully',
+]
+
+// Default Dozzle log group for each service group
+const DOZZLE_GROUPS: Record<string, string> = {
+  apps: 'Apps',
+  middleware: 'Middleware',
+  databases: 'Data Stores',
+}
+
+export interface IScaffoldPort {
+  host: number
+  container: number
+}
+
+export interface IScaffoldSecret {
+  method: typeof GENERATE_METHODS[number]
+  length?: number
+  prefix?: string
+}
+
+/**
+ * Settings for a new service created with `llmn service new`
+ */
+export interface IServiceScaffold {
+  service: string // Service name and service dir name, e.g. my-app
+  name: string // Friendly name, e.g. My App
+  description: string
+  serviceGroup: string
+  image: string
+  ports: IScaffoldPort[]
+  provides: string[]
+  dependsOn: Record<string, ServiceDependsOnCondition>
+  exposes: {
+    host?: string // Dashboard url on the host, e.g. http://localhost:8080
+    internal?: string // API url inside the stack network, e.g. http://my-app:8080
+  }
+  secrets: Record<string, IScaffoldSecret>
+  healthcheck: string // Shell command run in the container
+  dozzleGroup?: string
+}
+
+/**
+ * Parse a port mapping: `8080` or `8080:80`
+ */
+export function parsePort(port: string): IScaffoldPort {
+  const match = port.trim().match(/^(\d+)(?::(\d+))?$/)
+  if (!match) {
+    throw new Error(`Invalid port: ${port}, expected host:container, e.g. 8080:80`)
+  }
+  const host = Number(match[1])
+  return { host, container: match[2] ? Number(match[2]) : host }
+}
+
+/**
+ * Parse a depends_on option: `postgres` or `postgres:service_healthy`
+ */
+export function parseDependsOn(dependsOn: string): [string, ServiceDependsOnCondition] {
+  const [name, condition = 'service_healthy'] = dependsOn.trim().split(':')
+  if (!name || !DEPENDS_ON_CONDITIONS.includes(condition as ServiceDependsOnCondition)) {
+    throw new Error(
+      `Invalid depends_on: ${dependsOn}, expected service[:${DEPENDS_ON_CONDITIONS.join('|')}]`,
+    )
+  }
+  return [name, condition as ServiceDependsOnCondition]
+}
+
+/**
+ * Parse a generated secret option: `MY_KEY`, `MY_KEY:generateUUID` or `MY_KEY:generateSecretKey:32`
+ */
+export function parseSecret(secret: string): [string, IScaffoldSecret] {
+  const [key, method = 'generateSecretKey', length] = secret.trim().split(':')
+  if (!/^[A-Z_][A-Z0-9_]*$/.test(key || '')) {
+    throw new Error(`Invalid secret env var name: ${key}, expected uppercase, e.g. MY_APP_KEY`)
+  }
+  if (!GENERATE_METHODS.includes(method as IScaffoldSecret['method'])) {
+    throw new Error(`Invalid secret method: ${method}, expected ${GENERATE_METHODS.join('|')}`)
+  }
+  if (length !== undefined && !/^\d+$/.test(length)) {
+    throw new Error(`Invalid secret length: ${length}`)
+  }
+  return [key, {
+    method: method as IScaffoldSecret['method'],
+    ...(length ? { length: Number(length) } : {}),
+  }]
+}
+
+/**
+ * Get the default healthcheck command for a service
+ *
+ * Checks the first container port over http with wget or curl, whichever is in the image.
+ */
+export function getDefaultHealthcheck(ports: IScaffoldPort[]): string {
+  const port = ports[0]?.container
+  if (!port) {
+    return 'exit 0' // No ports to check, replace with a command that checks the service
+  }
+  return `wget -q --spider http://localhost:${port}/ || curl -fsS -o /dev/null http://localhost:${port}/ || exit 1`
+}
+
+// Quote yaml values that would otherwise be parsed as another type or break the yaml syntax
+function yamlValue(value: string | number): string {
+  const str = String(value)
+  if (
+    typeof value === 'number' ||
+    (/^[\w${}./@-][\w${}./@:-]*$/.test(str) &&
+      !/^(true|false|null|yes|no|on|off|~|[\d.]+)$/i.test(str))
+  ) {
+    return str
+  }
+  return JSON.stringify(str)
+}
+
+/**
+ * Generate the llemonstack.yaml file for a new service
+ */
+export function getServiceScaffoldYaml(spec: IServiceScaffold): string {
+  const lines = [
+    'version: 0.2.0 # Config file version',
+    '',
+    `service: ${spec.service}`,
+    `name: ${yamlValue(spec.name)}`,
+    `description: ${yamlValue(spec.description)}`,
+    '',
+    'compose_file: docker-compose.yaml',
+    `service_group: ${spec.serviceGroup}`,
+    '',
+    'provides:',
+    ...spec.provides.map((provides) => `  ${provides}: ${spec.service} # Name of the container`),
+  ]
+
+  const dependsOn = Object.entries(spec.dependsOn)
+  if (dependsOn.length) {
+    lines.push('', 'depends_on:')
+    dependsOn.forEach(([dependency, condition]) => {
+      lines.push(`  ${dependency}:`, `    condition: ${condition}`)
+    })
+  }
+
+  if (spec.exposes.host || spec.exposes.internal) {
+    lines.push('', 'exposes:')
+    if (spec.exposes.host) {
+      lines.push(
+        '  host:',
+        '    dashboard:',
+        `      name: ${yamlValue(spec.name)}`,
+        `      url: ${yamlValue(spec.exposes.host)}`,
+      )
+    }
+    if (spec.exposes.internal) {
+      lines.push(
+        '  internal: # Internal to the stack, not exposed to the host',
+        '    api:',
+        `      name: ${yamlValue(`${spec.name} API`)}`,
+        `      url: ${yamlValue(spec.exposes.internal)}`,
+      )
+    }
+  }
+
+  const secrets = Object.entries(spec.secrets)
+  if (secrets.length) {
+    lines.push('', 'init:', '  generate:')
+    secrets.forEach(([key, secret]) => {
+      lines.push(`    ${key}:`, `      method: ${secret.method}`)
+      secret.length && lines.push(`      length: ${secret.length}`)
+      secret.prefix && lines.push(`      prefix: ${yamlValue(secret.prefix)}`)
+    })
+  }
+
+  return lines.join('\n') + '\n'
+}
+
+/**
+ * Generate the docker-compose.yaml file for a new service
+ *
+ * The service joins the external project network and is grouped in Dozzle.
+ */
+export function getComposeScaffoldYaml(spec: IServiceScaffold): string {
+  const dozzleGroup = spec.dozzleGroup || DOZZLE_GROUPS[spec.serviceGroup] || spec.name
+  const lines = [
+    `# ${spec.name}`,
+    '#',
+    `# ${spec.description}`,
+    '',
+    'networks:',
+    '  default:',
+    '    name: ${LLEMONSTACK_NETWORK_NAME}',
+    '    external: true',
+    '',
+    'services:',
+    `  ${spec.service}:`,
+    `    container_name: ${spec.service}`,
+    '    labels:',
+    `      dev.dozzle.group: ${yamlValue(dozzleGroup)}`,
+    `    image: ${yamlValue(spec.image)}`,
+    '    restart: unless-stopped',
+  ]
+
+  if (spec.ports.length) {
+    lines.push('    ports:')
+    spec.ports.forEach((port) => lines.push(`      - ${port.host}:${port.container}`))
+  }
+
+  const secrets = Object.keys(spec.secrets)
+  if (secrets.length) {
+    lines.push('    environment:')
+    secrets.forEach((key) => lines.push(`      - ${key}=\${${key}}`))
+  } else {
+    lines.push('    # environment:', '    #   - MY_ENV_VAR=${MY_ENV_VAR:-default}')
+  }
+
+  lines.push(
+    '',
+    '    extra_hosts:',
+    '      - "host.docker.internal:${LLEMONSTACK_HOST_GATEWAY:-host-gateway}"',
+    '',
+    '    # volumes:',
+    `    #   - \${LLEMONSTACK_VOLUMES_PATH:-./volumes}/${spec.service}:/data`,
+    '',
+    '    healthcheck:',
+    `      test: ${JSON.stringify(['CMD-SHELL', spec.healthcheck])}`,
+    '      interval: 30s',
+    '      timeout: 5s',
+    '      retries: 3',
+    '      start_period: 30s',
+  )
+
+  return lines.join('\n') + '\n'
+}
+
+/**
+ * Get the class name for a service, e.g. my-app -> MyAppService
+ *
+ * Service names can start with a number, the class name is prefixed with Custom so it's a valid
+ * identifier, e.g. 2fa-proxy -> Custom2faProxyService
+ */
+export function getServiceClassName(service: string): string {
+  const name = service
+    .split(/[-_]/)
+    .map((part) => part.charAt(0).toUpperCase() + part.slice(1))
+    .join('')
+  return `${/^\d/.test(name) ? 'Custom' : ''}${name}Service`
+}
+
+/**
+ * Generate the service.ts file for a new service
+ */
+export function getServiceScaffoldTs(spec: IServiceScaffold): string {
+  const className = getServiceClassName(spec.service)
+  return `import { Config } from '@/core/config/config.ts'
+import { Service } from '@/core/services/service.ts'
+
+/**
+ * ${spec.name} service
+ *
+ * Override Service methods to customize how the service is configured and started.
+ */
+export class ${className} extends Service {
+  // Add env vars for the service before it starts, e.g. values from a config file
+  // deno-lint-ignore require-await
+  override async loadEnv(
+    envVars: Record<string, string>,
+    { config: _config }: { config: Config },
+  ) {
+    return envVars
+  }
+}
+
+export default ${className}
+`
+}
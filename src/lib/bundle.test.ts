@@ -0,0 +1,67 @@
+import { assert, assertEquals } from 'jsr:@std/assert'
+import {
+  BUNDLE_MANIFEST_FILE,
+  createArchive,
+  extractArchive,
+  getBundleFileName,
+  readBundleManifest,
+  writeBundleManifest,
+} from './bundle.ts'
+
+const MANIFEST = {
+  llemonstack: '0.4.0',
+  project: 'stack',
+  images: ['n8nio/n8n:latest', 'stack-browser-use'],
+  repos: ['supabase'],
+  services: ['n8n', 'supabase'],
+}
+
+Deno.test('getBundleFileName uses the project and date', () => {
+  assertEquals(
+    getBundleFileName('stack', new Date('2025-04-01T12:00:00Z')),
+    'stack-bundle-20250401.tar',
+  )
+})
+
+Deno.test('readBundleManifest round trips a written manifest', async () => {
+  const dir = await Deno.makeTempDir()
+  try {
+    assert(!(await readBundleManifest(dir)).success)
+
+    assert((await writeBundleManifest(dir, MANIFEST)).success)
+    const result = await readBundleManifest(dir)
+    assert(result.success)
+    assertEquals(result.data?.images, MANIFEST.images)
+    assertEquals(result.data?.version, 1)
+
+    await Deno.writeTextFile(`${dir}/${BUNDLE_MANIFEST_FILE}`, JSON.stringify({ version: 99 }))
+    assert(!(await readBundleManifest(dir)).success)
+
+    for (const repos of [['../../..'], ['supabase/../..'], ['..'], ['']]) {
+      assert((await writeBundleManifest(dir, { ...MANIFEST, repos })).success)
+      assert(!(await readBundleManifest(dir)).success, JSON.stringify(repos))
+    }
+    assert((await writeBundleManifest(dir, { ...MANIFEST, services: ['/tmp'] })).success)
+    assert(!(await readBundleManifest(dir)).success)
+  } finally {
+    await Deno.remove(dir, { recursive: true })
+  }
+})
+
+Deno.test('createArchive and extractArchive round trip a dir', async () => {
+  const dir = await Deno.makeTempDir()
+  try {
+    await Deno.mkdir(`${dir}/src/repo`, { recursive: true })
+    await Deno.writeTextFile(`${dir}/src/repo/README.md`, 'repo')
+    await Deno.writeTextFile(`${dir}/src/skipped.txt`, 'skipped')
+
+    assert((await createArchive(`${dir}/repos.tar.gz`, `${dir}/src`, ['repo'])).success)
+    await Deno.mkdir(`${dir}/out`)
+    assert((await extractArchive(`${dir}/repos.tar.gz`, `${dir}/out`)).success)
+
+    assertEquals(await Deno.readTextFile(`${dir}/out/repo/README.md`), 'repo')
+    assert(!(await Deno.stat(`${dir}/out/skipped.txt`).catch(() => null)))
+  } finally {
+    await Deno.remove(dir, { recursive: true })
+  }
+})
@@ -0,0 +1,143 @@
+/**
+ * Offline bundles
+ *
+ * A bundle is a tar archive with everything needed to run a project without internet access:
+ *
+ * - manifest.json: the bundled images, repos and services
+ * - images.tar: `docker save` output for the images of the enabled services
+ * - repos.tar.gz: the service repos from the project repos dir
+ * - services/: the service definitions, loaded instead of the installed services
+ *
+ * Projects are marked offline after a bundle is loaded, see ConfigBase.offline.
+ */
+
+import { tryRunCommand } from '@/lib/command.ts'
+import { fileExists, path, readJson, saveJson } from '@/lib/fs.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+
+// Set to true to run any project offline, repos are not cloned and images are not pulled
+export const OFFLINE_ENV = 'LLEMONSTACK_OFFLINE'
+
+export const BUNDLE_MANIFEST_FILE = 'manifest.json'
+export const BUNDLE_IMAGES_FILE = 'images.tar'
+export const BUNDLE_REPOS_FILE = 'repos.tar.gz'
+export const BUNDLE_SERVICES_DIR = 'services'
+
+const BUNDLE_VERSION = 1
+
+export interface IBundleManifest {
+  version: number
+  created: string // ISO 8601 timestamp
+  llemonstack: string // Version of LLemonStack used to create the bundle
+  project: string
+  images: string[]
+  repos: string[] // Repo dirs in the repos archive
+  services: string[] // Service dirs in the services dir
+}
+
+/**
+ * Get the default bundle file name for a project
+ *
+ * @example
+ * getBundleFileName('llemonstack', new Date('2025-04-01')) // 'llemonstack-bundle-20250401.tar'
+ */
+export function getBundleFileName(project: string, date: Date = new Date()): string {
+  return `${project}-bundle-${date.toISOString().slice(0, 10).replaceAll('-', '')}.tar`
+}
+
+/**
+ * Create a tar archive
+ *
+ * @param file - The archive to create, gzipped if it ends with .gz
+ * @param dir - The dir the entries are relative to
+ * @param entries - Files or dirs in dir to archive, defaults to the whole dir
+ */
+export async function createArchive(
+  file: string,
+  dir: string,
+  entries: string[] = ['.'],
+): Promise<TryCatchResult<boolean>> {
+  const result = await tryRunCommand('tar', {
+    args: [file.endsWith('.gz') ? '-czf' : '-cf', file, '-C', dir, ...entries],
+    silent: true,
+    captureOutput: true,
+    autoLoadEnv: false,
+  })
+  return result.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to create archive: ${file}`, result, false)
+}
+
+/**
+ * Extract a tar archive into a dir
+ */
+export async function extractArchive(file: string, dir: string): Promise<TryCatchResult<boolean>> {
+  const result = await tryRunCommand('tar', {
+    args: [file.endsWith('.gz') ? '-xzf' : '-xf', file, '-C', dir],
+    silent: true,
+    captureOutput: true,
+    autoLoadEnv: false,
+  })
+  return result.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to extract archive: ${file}`, result, false)
+}
+
+/**
+ * Write the bundle manifest to an unpacked bundle dir
+ */
+export async function writeBundleManifest(
+  dir: string,
+  manifest: Omit<IBundleManifest, 'version' | 'created'>,
+): Promise<TryCatchResult<boolean>> {
+  return await saveJson(path.join(dir, BUNDLE_MANIFEST_FILE), {
+    version: BUNDLE_VERSION,
+    created: new Date().toISOString(),
+    ...manifest,
+  })
+}
+
+/**
+ * Check a repo or service entry of a bundle manifest is a single dir name
+ *
+ * Entries are joined to the project dirs when a bundle is loaded, so paths like `../..` would
+ * copy the bundle contents outside of the project.
+ */
+function isBundleDirName(entry: unknown): boolean {
+  return typeof entry === 'string' && entry !== '' && entry !== '.' && entry !== '..' &&
+    path.basename(entry) === entry
+}
+
+/**
+ * Read the bundle manifest from an unpacked bundle dir
+ */
+export async function readBundleManifest(dir: string): Promise<TryCatchResult<IBundleManifest>> {
+  const file = path.join(dir, BUNDLE_MANIFEST_FILE)
+  if (!(await fileExists(file)).data) {
+    return failure<IBundleManifest>(
+      'Bundle manifest not found, is this a LLemonStack bundle?',
+      success(null),
+    )
+  }
+  const result = await readJson<IBundleManifest>(file)
+  if (!result.success || !result.data) {
+    return failure<IBundleManifest>('Unable to read bundle manifest', result)
+  }
+  if (result.data.version !== BUNDLE_VERSION) {
+    return failure<IBundleManifest>(
+      `Unsupported bundle version: ${result.data.version}`,
+      success(null),
+    )
+  }
+  const manifest = { images: [], repos: [], services: [], ...result.data }
+  const invalid = [manifest.repos, manifest.services].flatMap((entries) =>
+    Array.isArray(entries) ? entries.filter((entry) => !isBundleDirName(entry)) : [entries]
+  )
+  if (invalid.length) {
+    return failure<IBundleManifest>(
+      `Invalid repo or service in bundle manifest: ${invalid.join(', ')}`,
+      success(null),
+    )
+  }
+  return success<IBundleManifest>(manifest)
+}
@@ -1,6 +1,7 @@
 import { Config } from '@/core/config/config.ts'
 import { tryCatch, type TryCatchResult } from '@/lib/try-catch.ts'
 import { Relayer } from '@/relayer/relayer.ts'
+import { InterfaceRelayer } from '@/relayer/ui/interface.ts'
 import { showDebug, showError, showInfo } from '@/relayer/ui/show.ts'
 import type { AppLogRecord, CommandOutput, RunCommandOptions } from '@/types'
 
@@ -102,6 +103,7 @@ export function tryRunCommand(
  * @param env - The environment variables to set
  * @param autoLoadEnv - If true, load env from .env file
  * @param debug - If true, show debug output, defaults to global debug setting
+ * @param interactive - If true, pass stdin and the terminal through, output is not captured
  * @returns {RunCommandOutput} The output of the command
  */
 export async function runCommand(
@@ -115,6 +117,7 @@ export async function runCommand(
     debug = Config.getInstance().DEBUG ?? false,
     relayer = Relayer.getInstance('runCommand'),
     cwd = Deno.cwd(),
+    interactive = false,
   }: RunCommandOptions = {},
 ): Promise<RunCommandOutput> {
   // If verbose debug is enabled, show output even if silent is true
@@ -123,7 +126,18 @@ export async function runCommand(
   }
 
   // If silent is true, pipe output so streamStdout receives output below
-  const stdout = captureOutput ? 'piped' : (silent || debug) ? 'piped' : 'inherit'
+  // Interactive commands always inherit so the command gets the TTY
+  // Forward command output to stderr when the output format is json or yaml
+  // to keep stdout clean for the result document
+  const forwardToStderr = !interactive && !captureOutput && !silent &&
+    InterfaceRelayer.isStructuredOutput()
+  const stdout = interactive
+    ? 'inherit'
+    : captureOutput
+    ? 'piped'
+    : (silent || debug || forwardToStderr)
+    ? 'piped'
+    : 'inherit'
   const stderr = stdout
 
   // Auto load env from .env file
@@ -191,6 +205,7 @@ export async function runCommand(
 
   const command = new Deno.Command(cmdCmd, {
     args: (cmdArgs.length && cmdArgs?.map((arg) => arg.toString())) || undefined,
+    stdin: interactive ? 'inherit' : undefined,
     stdout,
     stderr,
     env: cmdEnv,
@@ -226,13 +241,14 @@ export async function runCommand(
   const decoder = new TextDecoder()
 
   // Set up streaming for stdout
+  const stdoutTarget = forwardToStderr ? Deno.stderr : Deno.stdout
   const streamStdout = async () => {
     for await (const chunk of process.stdout) {
       const text = decoder.decode(chunk)
       stdoutCollector += text
       if (!silent) {
         // Stream to console in real-time
-        Deno.stdout.writeSync(chunk)
+        stdoutTarget.writeSync(chunk)
       }
     }
   }
@@ -262,7 +278,7 @@ export async function runCommand(
       stdout === 'piped' && showDebug(`STDOUT: ${stdoutCollector}`)
       stderr === 'piped' && showDebug(`STDERR: ${stderrCollector}`)
     }
-  } else if (!silent) {
+  } else if (!silent && !forwardToStderr) {
     // TODO: replace with Relayer.show.console() or similar
     stdoutCollector && console.log(stdoutCollector)
     stderrCollector && console.error(stderrCollector)
@@ -280,7 +296,8 @@ export async function runCommand(
       _meta: { error },
     }
 
-    if (!silent || debug) {
+    // Interactive commands already showed their output, the caller handles the exit code
+    if (!interactive && (!silent || debug)) {
       relayer.error(`[${cmdCmd}] Command failed: ${error.stderr?.replace('\n', '')}`, context)
     }
 
@@ -297,3 +314,102 @@ export async function runCommand(
     cmd: fullCmd,
   })
 }
+
+/**
+ * Run a long running command and stream the output line by line
+ *
+ * Used for commands that follow output until stopped, e.g. `docker compose logs --follow`.
+ * Lines from stdout and stderr are passed to onLine as they arrive and are not captured.
+ *
+ * @param cmd - The command to run
+ * @param args - The arguments to pass to the command
+ * @param env - The environment variables to set
+ * @param autoLoadEnv - If true, load env from .env file
+ * @param onLine - Called for each line of output
+ * @param signal - Abort signal to kill the command
+ * @returns {RunCommandOutput} The output of the command, stdout and stderr are empty
+ */
+export async function streamCommand(
+  cmd: string,
+  {
+    args,
+    env = {},
+    autoLoadEnv = true,
+    cwd = Deno.cwd(),
+    onLine,
+    signal,
+  }: Omit<RunCommandOptions, 'silent' | 'captureOutput'> & {
+    onLine: (line: string, stream: 'stdout' | 'stderr') => void
+    signal?: AbortSignal
+  },
+): Promise<RunCommandOutput> {
+  const cmdArgs = (args?.filter(Boolean) || []) as string[]
+  const fullCmd = [cmd, cmdArgs.join(' ')].filter(Boolean).join(' ')
+
+  const envVars = !autoLoadEnv ? {} : Config.getInstance().env
+  const cmdEnv: Record<string, string> = {
+    ...envVars,
+    ...Object.fromEntries(Object.entries(env).map(([k, v]) => [k, String(v)])),
+  }
+
+  let process: Deno.ChildProcess
+  try {
+    process = new Deno.Command(cmd, {
+      args: cmdArgs,
+      stdout: 'piped',
+      stderr: 'piped',
+      env: cmdEnv,
+      cwd,
+      signal,
+    }).spawn()
+  } catch (error) {
+    throw new CommandError(`Unable to run '${cmd}'`, {
+      code: 1,
+      cmd: fullCmd,
+      stdout: '',
+      stderr: String(error),
+    })
+  }
+
+  // Split the stream into lines, holding any partial line until the next chunk
+  const streamLines = async (
+    stream: ReadableStream<Uint8Array>,
+    name: 'stdout' | 'stderr',
+  ) => {
+    let buffer = ''
+    for await (const text of stream.pipeThrough(new TextDecoderStream())) {
+      buffer += text
+      const lines = buffer.split(/\r?\n/)
+      buffer = lines.pop() ?? ''
+      lines.forEach((line) => onLine(line, name))
+    }
+    if (buffer) {
+      onLine(buffer, name)
+    }
+  }
+
+  const [status] = await Promise.all([
+    process.status,
+    streamLines(process.stdout, 'stdout'),
+    streamLines(process.stderr, 'stderr'),
+  ])
+
+  // Aborted commands are expected to be killed and are not an error
+  if (!status.success && !signal?.aborted) {
+    throw new CommandError('Command failed', {
+      code: status.code,
+      cmd: fullCmd,
+      stdout: '',
+      stderr: '',
+    })
+  }
+
+  return new RunCommandOutput({
+    stdout: '',
+    stderr: '',
+    code: status.code,
+    success: status.success,
+    signal: status.signal,
+    cmd: fullCmd,
+  })
+}
@@ -2,7 +2,7 @@ import { dockerEnv } from '@/lib/docker.ts'
 import { path } from '@/lib/fs.ts'
 import { expandEnvVars } from '@/lib/utils/envvars.ts'
 import { showDebug, showWarning } from '@/relayer/ui/show.ts'
-import { ComposeYaml, IServiceImage } from '@/types'
+import { ComposePort, ComposeYaml, IComposeHostPort, IServiceImage } from '@/types'
 import * as yaml from 'jsr:@std/yaml'
 
 export const COMPOSE_IMAGES_CACHE = {} as Record<string, IServiceImage[]>
@@ -168,3 +168,99 @@ export async function getImageFromCompose(
   const serviceImages = await getImagesFromComposeYaml(composeFile)
   return serviceImages.find((img) => img.service === serviceName) || null
 }
+
+/**
+ * Convert a short syntax port to the long syntax
+ *
+ * `podman-compose config` and `nerdctl compose config` output the ports as written in the
+ * compose file instead of the long syntax.
+ *
+ * @example
+ * toLongPort('127.0.0.1:5678:5678/tcp') // { host_ip: '127.0.0.1', published: '5678', target: 5678, protocol: 'tcp' }
+ */
+export function toLongPort(port: ComposePort | string | number): ComposePort {
+  if (typeof port === 'object') {
+    return port
+  }
+  const [mapping, protocol] = String(port).split('/')
+  const parts = mapping.split(':')
+  const target = Number(parts.pop())
+  const published = parts.pop()
+  const host_ip = parts.join(':') || undefined
+  return {
+    target,
+    ...(published ? { published } : {}),
+    ...(host_ip ? { host_ip } : {}),
+    ...(protocol ? { protocol } : {}),
+  }
+}
+
+/**
+ * Get the published host ports from the compose file
+ *
+ * Only includes services without profiles or with one of the active profiles.
+ * Port ranges and random host ports are skipped.
+ *
+ * @param {string} composeFile - The path to the compose file
+ * @param {Record<string, string>} env - Env vars to expand in the ports
+ * @param {string[]} profiles - The active compose profiles
+ * @returns {Array<IComposeHostPort>} The published ports
+ */
+export async function getPortsFromComposeYaml(
+  composeFile: string,
+  { env = {}, profiles = [], processedFiles = new Set() }: {
+    env?: Record<string, string>
+    profiles?: string[]
+    processedFiles?: Set<string>
+  } = {},
+): Promise<IComposeHostPort[]> {
+  if (composeFile.includes('${')) {
+    composeFile = expandEnvVars(composeFile, env)
+  }
+  // Prevent circular references
+  if (processedFiles.has(composeFile)) {
+    return []
+  }
+  processedFiles.add(composeFile)
+
+  const composeConfig = yaml.parse(await Deno.readTextFile(composeFile)) as ComposeYaml
+  const ports: IComposeHostPort[] = []
+
+  const includes = !composeConfig.include
+    ? []
+    : Array.isArray(composeConfig.include)
+    ? composeConfig.include
+    : [composeConfig.include]
+  for (const include of includes) {
+    const includePath = typeof include === 'string' ? include : include?.path
+    if (includePath) {
+      ports.push(
+        ...await getPortsFromComposeYaml(
+          path.resolve(path.dirname(composeFile), expandEnvVars(includePath, env)),
+          { env, profiles, processedFiles },
+        ),
+      )
+    }
+  }
+
+  for (const [serviceName, service] of Object.entries(composeConfig.services || {})) {
+    const serviceProfiles = service?.profiles || []
+    if (serviceProfiles.length && !serviceProfiles.some((p) => profiles.includes(p))) {
+      continue
+    }
+    for (const port of service?.ports || []) {
+      const longPort = toLongPort(typeof port === 'string' ? expandEnvVars(port, env) : port)
+      const published = expandEnvVars(String(longPort.published ?? ''), env)
+      if (!/^\d+$/.test(published)) {
+        continue
+      }
+      ports.push({
+        service: serviceName,
+        published: Number(published),
+        target: Number(longPort.target),
+        protocol: longPort.protocol || 'tcp',
+      })
+    }
+  }
+  return ports
+}
@@ -0,0 +1,120 @@
+import { assertEquals, assertRejects } from 'jsr:@std/assert'
+import {
+  calculateDockerStats,
+  demuxDockerStream,
+  DockerApiClient,
+  DockerConflictError,
+  type DockerContainerSummary,
+  DockerSocketError,
+} from './docker-api.ts'
+import { toComposePsContainer } from './docker.ts'
+
+// Serve a single raw HTTP response on a unix socket
+async function withSocketServer(
+  response: string,
+  fn: (client: DockerApiClient) => Promise<void>,
+): Promise<void> {
+  const dir = await Deno.makeTempDir()
+  const socketPath = `${dir}/docker.sock`
+  const listener = Deno.listen({ transport: 'unix', path: socketPath })
+  const served = (async () => {
+    const conn = await listener.accept()
+    await conn.read(new Uint8Array(4096))
+    await conn.write(new TextEncoder().encode(response))
+    conn.close()
+  })()
+  try {
+    await fn(new DockerApiClient(socketPath))
+  } finally {
+    await served
+    listener.close()
+    await Deno.remove(dir, { recursive: true })
+  }
+}
+
+Deno.test('DockerApiClient parses chunked JSON responses', async () => {
+  const body = JSON.stringify([{ Id: 'abc', Name: 'llemonstack_network' }])
+  const response = 'HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n' +
+    'Transfer-Encoding: chunked\r\n\r\n' +
+    `${(10).toString(16)}\r\n${body.slice(0, 10)}\r\n` +
+    `${(body.length - 10).toString(16)}\r\n${body.slice(10)}\r\n0\r\n\r\n`
+  await withSocketServer(response, async (client) => {
+    assertEquals(await client.listNetworks(), [{ Id: 'abc', Name: 'llemonstack_network' }])
+  })
+})
+
+Deno.test('DockerApiClient throws typed errors for API errors', async () => {
+  const body = JSON.stringify({ message: 'network with name llemonstack_network already exists' })
+  const response = 'HTTP/1.1 409 Conflict\r\nContent-Type: application/json\r\n' +
+    `Content-Length: ${body.length}\r\n\r\n${body}`
+  await withSocketServer(response, async (client) => {
+    const error = await assertRejects(
+      () => client.createNetwork('llemonstack_network'),
+      DockerConflictError,
+    )
+    assertEquals(error.status, 409)
+    assertEquals(error.message, 'network with name llemonstack_network already exists')
+  })
+})
+
+Deno.test('DockerApiClient throws DockerSocketError when the socket is missing', async () => {
+  await assertRejects(() => new DockerApiClient(null).version(), DockerSocketError)
+  await assertRejects(
+    () => new DockerApiClient('/tmp/llemonstack-missing.sock').version(),
+    DockerSocketError,
+  )
+})
+
+Deno.test('demuxDockerStream splits stdout and stderr frames', () => {
+  const frame = (type: number, text: string) => {
+    const data = new TextEncoder().encode(text)
+    const header = new Uint8Array(8)
+    header[0] = type
+    new DataView(header.buffer).setUint32(4, data.length)
+    return [...header, ...data]
+  }
+  const stream = new Uint8Array([...frame(1, 'out\n'), ...frame(2, 'err\n'), ...frame(1, 'more')])
+  assertEquals(demuxDockerStream(stream), { stdout: 'out\nmore', stderr: 'err\n' })
+})
+
+Deno.test('calculateDockerStats matches docker stats', () => {
+  const stats = calculateDockerStats({
+    cpu_stats: { cpu_usage: { total_usage: 400 }, system_cpu_usage: 2000, online_cpus: 4 },
+    precpu_stats: { cpu_usage: { total_usage: 200 }, system_cpu_usage: 1000 },
+    memory_stats: { usage: 300, limit: 1000, stats: { inactive_file: 100 } },
+  })
+  assertEquals(stats, { cpuPercent: 80, memoryUsage: 200, memoryLimit: 1000, memoryPercent: 20 })
+})
+
+const CONTAINER: DockerContainerSummary = {
+  Id: '0123456789abcdef',
+  Names: ['/llemonstack-n8n'],
+  Image: 'n8nio/n8n:latest',
+  ImageID: 'sha256:abc',
+  Command: '',
+  Created: 0,
+  State: 'running',
+  Status: 'Up 2 hours (health: starting)',
+  Ports: [{ IP: '0.0.0.0', PrivatePort: 5678, PublicPort: 15678, Type: 'tcp' }],
+  Labels: { 'com.docker.compose.service': 'n8n', 'com.docker.compose.project': 'llemonstack' },
+}
+
+Deno.test('toComposePsContainer converts API containers to docker compose ps format', () => {
+  const container = toComposePsContainer(CONTAINER)
+  assertEquals(container.ID, '0123456789ab')
+  assertEquals(container.Name, 'llemonstack-n8n')
+  assertEquals(container.Service, 'n8n')
+  assertEquals(container.Health, 'starting')
+  assertEquals(container.ExitCode, 0)
+  assertEquals(container.Publishers, [
+    { URL: '0.0.0.0', TargetPort: 5678, PublishedPort: 15678, Protocol: 'tcp' },
+  ])
+
+  const exited = toComposePsContainer({
+    ...CONTAINER,
+    State: 'exited',
+    Status: 'Exited (137) 5 minutes ago',
+  })
+  assertEquals(exited.ExitCode, 137)
+  assertEquals(exited.Health, '')
+})
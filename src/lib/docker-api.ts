@@ -0,0 +1,638 @@
+/**
+ * Docker Engine API client
+ *
+ * Talks to the Docker daemon, or Podman's Docker compatible API, over a unix socket instead of
+ * running the CLI.
+ * Used for fast status, health, network and version queries. Compose operations still use
+ * the docker compose CLI, see docker.ts.
+ *
+ * See https://docs.docker.com/reference/api/engine/
+ */
+
+const CRLF = '\r\n'
+
+//
+// Errors
+//
+
+/**
+ * Error returned by the Docker Engine API
+ */
+export class DockerApiError extends Error {
+  status: number
+  method: string
+  path: string
+
+  constructor(
+    message: string,
+    { status, method, path }: { status: number; method: string; path: string },
+  ) {
+    super(message)
+    this.name = 'DockerApiError'
+    this.status = status
+    this.method = method
+    this.path = path
+  }
+
+  override toString(): string {
+    return `${this.message} (${this.status} ${this.method} ${this.path})`
+  }
+}
+
+// 404: the container, image, network or exec instance does not exist
+export class DockerNotFoundError extends DockerApiError {
+  override name = 'DockerNotFoundError'
+}
+
+// 409: name conflicts, e.g. a network with the same name already exists
+export class DockerConflictError extends DockerApiError {
+  override name = 'DockerConflictError'
+}
+
+/**
+ * Unable to connect to the Docker socket, Docker is not running or the socket is not available
+ */
+export class DockerSocketError extends Error {
+  socketPath: string | null
+
+  constructor(message: string, socketPath: string | null, options?: ErrorOptions) {
+    super(message, options)
+    this.name = 'DockerSocketError'
+    this.socketPath = socketPath
+  }
+}
+
+//
+// API types, only the fields used by LLemonStack are typed
+//
+
+export type DockerFilters = Record<string, string[]>
+
+export interface DockerContainerSummary {
+  Id: string
+  Names: string[]
+  Image: string
+  ImageID: string
+  Command: string
+  Created: number
+  State: string // created, running, paused, restarting, removing, exited, dead
+  Status: string // e.g. Up 2 hours (healthy), Exited (0) 5 minutes ago
+  Ports: Array<{ IP?: string; PrivatePort: number; PublicPort?: number; Type: string }>
+  Labels: Record<string, string>
+}
+
+export interface DockerContainerInspect {
+  Id: string
+  Name: string
+  Created: string
+  Image: string
+  RestartCount: number
+  State: {
+    Status: string
+    Running: boolean
+    ExitCode: number
+    StartedAt: string
+    FinishedAt: string
+    Health?: { Status: string; FailingStreak: number }
+  }
+  Config: {
+    Image: string
+    Env: string[]
+    Labels: Record<string, string>
+  }
+  NetworkSettings: {
+    Networks: Record<string, { IPAddress: string; NetworkID: string }>
+  }
+}
+
+export interface DockerImageInspect {
+  Id: string
+  RepoTags: string[]
+  RepoDigests: string[]
+  Created: string
+  Config: { Labels: Record<string, string> | null }
+}
+
+export interface DockerNetwork {
+  Id: string
+  Name: string
+  Driver: string
+  Labels: Record<string, string>
+  Containers?: Record<string, { Name: string }>
+}
+
+export interface DockerSystemInfo {
+  MemTotal: number // Bytes available to containers, the Docker Desktop VM memory on macOS
+  NCPU: number
+  ServerVersion: string
+  OperatingSystem: string
+}
+
+export interface DockerEvent {
+  Type: string // container, network, image, volume, etc.
+  Action: string // start, die, health_status: healthy, etc.
+  Actor: { ID: string; Attributes: Record<string, string> }
+  time: number
+  timeNano: number
+}
+
+export interface DockerContainerStats {
+  cpuPercent: number
+  memoryUsage: number // Bytes, excluding the page cache
+  memoryLimit: number
+  memoryPercent: number
+}
+
+export interface DockerExecResult {
+  stdout: string
+  stderr: string
+  exitCode: number
+}
+
+interface DockerResponse {
+  status: number
+  headers: Headers
+  body: AsyncGenerator<Uint8Array>
+}
+
+/**
+ * Buffered reader for a raw HTTP response
+ */
+class ResponseReader {
+  private _reader: ReadableStreamDefaultReader<Uint8Array>
+  private _buffer = new Uint8Array(0)
+  private _done = false
+
+  constructor(readable: ReadableStream<Uint8Array>) {
+    this._reader = readable.getReader()
+  }
+
+  private async fill(): Promise<boolean> {
+    if (this._done) return false
+    const { value, done } = await this._reader.read()
+    if (done || !value) {
+      this._done = true
+      return false
+    }
+    const buffer = new Uint8Array(this._buffer.length + value.length)
+    buffer.set(this._buffer)
+    buffer.set(value, this._buffer.length)
+    this._buffer = buffer
+    return true
+  }
+
+  private take(length: number): Uint8Array {
+    const data = this._buffer.slice(0, length)
+    this._buffer = this._buffer.slice(length)
+    return data
+  }
+
+  async readLine(): Promise<string | null> {
+    while (true) {
+      const index = this._buffer.findIndex((byte, i) => byte === 10 && this._buffer[i - 1] === 13)
+      if (index >= 0) {
+        return new TextDecoder().decode(this.take(index + 1)).slice(0, -CRLF.length)
+      }
+      if (!(await this.fill())) {
+        return null
+      }
+    }
+  }
+
+  async readExact(length: number): Promise<Uint8Array> {
+    while (this._buffer.length < length) {
+      if (!(await this.fill())) {
+        throw new Error('Docker socket closed before the response was complete')
+      }
+    }
+    return this.take(length)
+  }
+
+  async readAvailable(): Promise<Uint8Array | null> {
+    if (this._buffer.length === 0 && !(await this.fill())) {
+      return null
+    }
+    return this.take(this._buffer.length)
+  }
+
+  async cancel(): Promise<void> {
+    await this._reader.cancel().catch(() => {})
+  }
+}
+
+/**
+ * Split a multiplexed exec or logs stream into stdout and stderr
+ *
+ * Each frame has an 8 byte header: stream type, 3 empty bytes, and the frame size.
+ */
+export function demuxDockerStream(data: Uint8Array): { stdout: string; stderr: string } {
+  const decoder = new TextDecoder()
+  const output = { stdout: '', stderr: '' }
+  let offset = 0
+  while (offset + 8 <= data.length) {
+    const type = data[offset]
+    const size = new DataView(data.buffer, data.byteOffset + offset + 4, 4).getUint32(0)
+    const frame = decoder.decode(data.slice(offset + 8, offset + 8 + size))
+    if (type === 2) {
+      output.stderr += frame
+    } else {
+      output.stdout += frame
+    }
+    offset += 8 + size
+  }
+  return output
+}
+
+/**
+ * Calculate the CPU and memory usage from a Docker stats response, same as `docker stats`
+ */
+// deno-lint-ignore no-explicit-any
+export function calculateDockerStats(stats: any): DockerContainerStats {
+  const cpuDelta = (stats.cpu_stats?.cpu_usage?.total_usage ?? 0) -
+    (stats.precpu_stats?.cpu_usage?.total_usage ?? 0)
+  const systemDelta = (stats.cpu_stats?.system_cpu_usage ?? 0) -
+    (stats.precpu_stats?.system_cpu_usage ?? 0)
+  const cpus = stats.cpu_stats?.online_cpus || stats.cpu_stats?.cpu_usage?.percpu_usage?.length || 1
+  const cpuPercent = systemDelta > 0 && cpuDelta > 0 ? (cpuDelta / systemDelta) * cpus * 100 : 0
+
+  // Page cache is not included in the memory usage, cgroup v2 uses inactive_file
+  const cache = stats.memory_stats?.stats?.inactive_file ??
+    stats.memory_stats?.stats?.total_inactive_file ?? 0
+  const memoryUsage = Math.max((stats.memory_stats?.usage ?? 0) - cache, 0)
+  const memoryLimit = stats.memory_stats?.limit ?? 0
+  return {
+    cpuPercent,
+    memoryUsage,
+    memoryLimit,
+    memoryPercent: memoryLimit ? (memoryUsage / memoryLimit) * 100 : 0,
+  }
+}
+
+/**
+ * Docker Engine API client
+ *
+ * Methods throw DockerSocketError if the socket is not available and DockerApiError for
+ * API errors. Use the try* helpers in docker.ts to get a TryCatchResult with a CLI fallback.
+ */
+export class DockerApiClient {
+  private static instance: DockerApiClient
+
+  readonly socketPath: string | null
+
+  /**
+   * @param socketPath - Docker compatible API socket, see getRuntimeSocketPath in runtime.ts
+   */
+  constructor(socketPath: string | null) {
+    this.socketPath = socketPath
+  }
+
+  static getInstance(socketPath: string | null): DockerApiClient {
+    if (!DockerApiClient.instance || DockerApiClient.instance.socketPath !== socketPath) {
+      DockerApiClient.instance = new DockerApiClient(socketPath)
+    }
+    return DockerApiClient.instance
+  }
+
+  get isAvailable(): boolean {
+    return !!this.socketPath
+  }
+
+  //
+  // System
+  //
+
+  async ping(): Promise<boolean> {
+    try {
+      await this.requestText('GET', '/_ping')
+      return true
+    } catch (_error) {
+      return false
+    }
+  }
+
+  async version(): Promise<{ Version: string; ApiVersion: string; Os: string; Arch: string }> {
+    return await this.requestJson('GET', '/version')
+  }
+
+  async info(): Promise<DockerSystemInfo> {
+    return await this.requestJson('GET', '/info')
+  }
+
+  //
+  // Containers
+  //
+
+  async listContainers(
+    { all = false, filters }: { all?: boolean; filters?: DockerFilters } = {},
+  ): Promise<DockerContainerSummary[]> {
+    return await this.requestJson('GET', '/containers/json', {
+      query: { all: all ? '1' : '0', filters: filters && JSON.stringify(filters) },
+    })
+  }
+
+  async inspectContainer(id: string): Promise<DockerContainerInspect> {
+    return await this.requestJson('GET', `/containers/${encodeURIComponent(id)}/json`)
+  }
+
+  /**
+   * Get a single CPU and memory usage sample for a running container
+   */
+  async stats(id: string): Promise<DockerContainerStats> {
+    const stats = await this.requestJson('GET', `/containers/${encodeURIComponent(id)}/stats`, {
+      query: { stream: 'false' },
+    })
+    return calculateDockerStats(stats)
+  }
+
+  /**
+   * Run a command in a running container and wait for it to exit
+   *
+   * Output is captured, use the docker CLI for interactive commands.
+   */
+  async exec(
+    id: string,
+    cmd: string[],
+    { user, env, workingDir }: { user?: string; env?: Record<string, string>; workingDir?: string } =
+      {},
+  ): Promise<DockerExecResult> {
+    const { Id: execId } = await this.requestJson<{ Id: string }>(
+      'POST',
+      `/containers/${encodeURIComponent(id)}/exec`,
+      {
+        body: {
+          AttachStdout: true,
+          AttachStderr: true,
+          Cmd: cmd,
+          ...(user ? { User: user } : {}),
+          ...(workingDir ? { WorkingDir: workingDir } : {}),
+          ...(env ? { Env: Object.entries(env).map(([key, value]) => `${key}=${value}`) } : {}),
+        },
+      },
+    )
+    const response = await this.request('POST', `/exec/${execId}/start`, {
+      body: { Detach: false, Tty: false },
+    })
+    const output = demuxDockerStream(await this.readBody(response))
+    const { ExitCode } = await this.requestJson<{ ExitCode: number | null }>(
+      'GET',
+      `/exec/${execId}/json`,
+    )
+    return { ...output, exitCode: ExitCode ?? 0 }
+  }
+
+  /**
+   * Stream Docker events until the signal is aborted or the until time is reached
+   *
+   * @example
+   * ```ts
+   * for await (const event of client.events({ filters: { label: ['com.docker.compose.project=llemonstack'] } })) {
+   *   console.log(event.Action, event.Actor.Attributes.name)
+   * }
+   * ```
+   */
+  async *events(
+    { filters, since, until, signal }: {
+      filters?: DockerFilters
+      since?: number // Unix timestamp
+      until?: number
+      signal?: AbortSignal
+    } = {},
+  ): AsyncGenerator<DockerEvent> {
+    const response = await this.request('GET', '/events', {
+      query: {
+        filters: filters && JSON.stringify(filters),
+        since: since?.toString(),
+        until: until?.toString(),
+      },
+      signal,
+    })
+    const decoder = new TextDecoder()
+    let buffer = ''
+    for await (const chunk of response.body) {
+      buffer += decoder.decode(chunk, { stream: true })
+      const lines = buffer.split('\n')
+      buffer = lines.pop() || ''
+      for (const line of lines) {
+        if (line.trim()) {
+          yield JSON.parse(line) as DockerEvent
+        }
+      }
+    }
+  }
+
+  //
+  // Images
+  //
+
+  async inspectImage(name: string): Promise<DockerImageInspect> {
+    return await this.requestJson('GET', `/images/${encodeURIComponent(name)}/json`)
+  }
+
+  //
+  // Networks
+  //
+
+  async listNetworks({ filters }: { filters?: DockerFilters } = {}): Promise<DockerNetwork[]> {
+    return await this.requestJson('GET', '/networks', {
+      query: { filters: filters && JSON.stringify(filters) },
+    })
+  }
+
+  async inspectNetwork(id: string): Promise<DockerNetwork> {
+    return await this.requestJson('GET', `/networks/${encodeURIComponent(id)}`)
+  }
+
+  /**
+   * Create a network
+   *
+   * @throws {DockerConflictError} If a network with the same name already exists
+   */
+  async createNetwork(
+    name: string,
+    { driver = 'bridge', labels = {} }: { driver?: string; labels?: Record<string, string> } = {},
+  ): Promise<{ Id: string }> {
+    return await this.requestJson('POST', '/networks/create', {
+      body: { Name: name, Driver: driver, Labels: labels, CheckDuplicate: true },
+    })
+  }
+
+  async removeNetwork(id: string): Promise<void> {
+    await this.requestText('DELETE', `/networks/${encodeURIComponent(id)}`)
+  }
+
+  //
+  // Requests
+  //
+
+  async requestJson<T = unknown>(
+    method: string,
+    path: string,
+    options: { query?: Record<string, string | undefined>; body?: unknown } = {},
+  ): Promise<T> {
+    const text = await this.requestText(method, path, options)
+    return (text ? JSON.parse(text) : null) as T
+  }
+
+  async requestText(
+    method: string,
+    path: string,
+    options: { query?: Record<string, string | undefined>; body?: unknown } = {},
+  ): Promise<string> {
+    const response = await this.request(method, path, options)
+    return new TextDecoder().decode(await this.readBody(response))
+  }
+
+  /**
+   * Send a request to the Docker socket
+   *
+   * Each request uses a new connection, the Docker socket is local so connections are cheap.
+   *
+   * @throws {DockerSocketError} If the socket is not available
+   * @throws {DockerApiError} If the API returns an error status
+   */
+  async request(
+    method: string,
+    path: string,
+    { query, body, signal }: {
+      query?: Record<string, string | undefined>
+      body?: unknown
+      signal?: AbortSignal
+    } = {},
+  ): Promise<DockerResponse> {
+    if (!this.socketPath) {
+      throw new DockerSocketError('Docker socket not found', null)
+    }
+
+    const params = new URLSearchParams(
+      Object.entries(query || {}).filter(([, value]) => value !== undefined) as [string, string][],
+    ).toString()
+    const target = `${path}${params ? `?${params}` : ''}`
+
+    let conn: Deno.UnixConn
+    try {
+      conn = await Deno.connect({ transport: 'unix', path: this.socketPath })
+    } catch (error) {
+      throw new DockerSocketError(
+        `Unable to connect to the Docker socket: ${this.socketPath}`,
+        this.socketPath,
+        { cause: error },
+      )
+    }
+    signal?.addEventListener('abort', () => {
+      try {
+        conn.close()
+      } catch (_error) {
+        // Already closed
+      }
+    })
+
+    const payload = body === undefined ? null : new TextEncoder().encode(JSON.stringify(body))
+    const head = [
+      `${method} ${target} HTTP/1.1`,
+      'Host: docker',
+      'User-Agent: llemonstack',
+      'Connection: close',
+      ...(payload ? ['Content-Type: application/json', `Content-Length: ${payload.length}`] : []),
+      '',
+      '',
+    ].join(CRLF)
+
+    const reader = new ResponseReader(conn.readable)
+    let status = 0
+    const headers = new Headers()
+    try {
+      const headBytes = new TextEncoder().encode(head)
+      const requestBytes = new Uint8Array(headBytes.length + (payload?.length || 0))
+      requestBytes.set(headBytes)
+      payload && requestBytes.set(payload, headBytes.length)
+      const writer = conn.writable.getWriter()
+      await writer.write(requestBytes)
+      writer.releaseLock()
+
+      const statusLine = await reader.readLine()
+      status = Number(statusLine?.split(' ')[1])
+      if (!status) {
+        throw new Error(`Invalid response from the Docker socket: ${statusLine}`)
+      }
+      let line: string | null
+      while ((line = await reader.readLine())) {
+        const index = line.indexOf(':')
+        headers.append(line.slice(0, index).trim(), line.slice(index + 1).trim())
+      }
+    } catch (error) {
+      await reader.cancel()
+      throw new DockerSocketError(
+        `Docker socket request failed: ${method} ${path}`,
+        this.socketPath,
+        { cause: error },
+      )
+    }
+
+    const response = { status, headers, body: this.readBodyChunks(reader, headers) }
+    if (status >= 400) {
+      const text = new TextDecoder().decode(await this.readBody(response))
+      let message = text.trim()
+      try {
+        message = JSON.parse(text).message || message
+      } catch (_error) {
+        // Plain text error
+      }
+      const ErrorClass = status === 404
+        ? DockerNotFoundError
+        : status === 409
+        ? DockerConflictError
+        : DockerApiError
+      throw new ErrorClass(message || `Docker API error: ${status}`, { status, method, path })
+    }
+    return response
+  }
+
+  private async readBody(response: DockerResponse): Promise<Uint8Array> {
+    const chunks: Uint8Array[] = []
+    for await (const chunk of response.body) {
+      chunks.push(chunk)
+    }
+    const data = new Uint8Array(chunks.reduce((length, chunk) => length + chunk.length, 0))
+    let offset = 0
+    for (const chunk of chunks) {
+      data.set(chunk, offset)
+      offset += chunk.length
+    }
+    return data
+  }
+
+  private async *readBodyChunks(
+    reader: ResponseReader,
+    headers: Headers,
+  ): AsyncGenerator<Uint8Array> {
+    try {
+      if (headers.get('transfer-encoding')?.includes('chunked')) {
+        while (true) {
+          const sizeLine = await reader.readLine()
+          const size = parseInt(sizeLine || '0', 16)
+          if (!size) break
+          yield await reader.readExact(size)
+          await reader.readLine() // CRLF after each chunk
+        }
+      } else if (headers.has('content-length')) {
+        const length = Number(headers.get('content-length'))
+        if (length) {
+          yield await reader.readExact(length)
+        }
+      } else {
+        // Raw streams, e.g. exec output, end when the connection is closed
+        let chunk: Uint8Array | null
+        while ((chunk = await reader.readAvailable())) {
+          yield chunk
+        }
+      }
+    } catch (error) {
+      // The connection is closed when an events stream is aborted
+      if (!(error instanceof Deno.errors.BadResource || error instanceof Deno.errors.Interrupted)) {
+        throw error
+      }
+    } finally {
+      await reader.cancel()
+    }
+  }
+}
@@ -1,6 +1,9 @@
 /**
  * Docker management library
  *
+ * Status, network and inspect queries use the Docker Engine API when the Docker socket is
+ * available and fall back to the docker CLI. Compose commands always use the CLI.
+ *
  * TODO: move all docker related functions here
  * TODO: convert into a class
  * TODO: add a catchall method that auto detects 'try' prefix and wraps the result in a TryCatchResult?
@@ -8,10 +11,21 @@
 
 import { Config } from '@/core/config/config.ts'
 import Host from '@/core/config/lib/host.ts'
-import { success, tryCatch, TryCatchResult } from '@/lib/try-catch.ts'
+import { path } from '@/lib/fs.ts'
+import { failure, success, tryCatch, TryCatchResult } from '@/lib/try-catch.ts'
 import { Relayer } from '@/relayer/relayer.ts'
 import type { EnvVars, RunCommandOutput } from '@/types'
-import { CommandError, runCommand, tryRunCommand } from './command.ts'
+import { CommandError, runCommand, streamCommand, tryRunCommand } from './command.ts'
+import { getContainerRuntime, type IContainerRuntime } from './runtime.ts'
+import {
+  DockerApiClient,
+  DockerConflictError,
+  type DockerContainerSummary,
+  type DockerNetwork,
+  DockerNotFoundError,
+  DockerSocketError,
+  type DockerSystemInfo,
+} from './docker-api.ts'
 
 export type DockerCommandOptions = {
   args?: Array<string | false>
@@ -31,6 +45,7 @@ export type DockerComposeOptions = {
   captureOutput?: boolean
   env?: EnvVars
   autoLoadEnv?: boolean
+  composeOverrides?: boolean // Include the generated compose override files, default true
 }
 
 export type DockerComposePsResult = Array<{
@@ -44,6 +59,13 @@ export type DockerComposePsResult = Array<{
   RunningFor?: string
   Size?: string
   State?: string
+  ExitCode?: number
+  Publishers?: Array<{
+    URL?: string
+    TargetPort?: number
+    PublishedPort?: number
+    Protocol?: string
+  }>
 }>
 
 /**
@@ -51,20 +73,66 @@ export type DockerComposePsResult = Array<{
  *
  * @returns Record<string, string>
  */
-// deno-lint-ignore require-await
 export async function dockerEnv(config?: Config): Promise<Record<string, string>> {
   if (!config) {
     config = Config.getInstance()
   }
+  const runtime = await getRuntime()
   return {
     LLEMONSTACK_VOLUMES_PATH: config.volumesDir,
     LLEMONSTACK_SHARED_VOLUME_PATH: config.sharedDir,
     LLEMONSTACK_IMPORT_VOLUME_PATH: config.importDir,
     LLEMONSTACK_REPOS_PATH: config.reposDir,
     LLEMONSTACK_NETWORK_NAME: config.dockerNetworkName,
+    // Host user for containers that write to bind mounted host dirs, null on Windows
+    LLEMONSTACK_UID: String(Deno.uid() ?? 1000),
+    LLEMONSTACK_GID: String(Deno.gid() ?? 1000),
     TARGETPLATFORM: getDockerTargetPlatform(), // Docker platform for building images
     DOCKERFILE_ARCH: getDockerfileArch(), // Dockerfile.arm64 if on Mac Silicon or aarch64 platform
     COMPOSE_IGNORE_ORPHANS: 'true', // Always ignore orphan container warnings
+    // extra_hosts value for host.docker.internal, host-gateway is not supported by all runtimes
+    LLEMONSTACK_HOST_GATEWAY: runtime.hostGateway,
+  }
+}
+
+/**
+ * Get the container runtime for the project: docker, podman or nerdctl
+ *
+ * @returns {Promise<IContainerRuntime>} The detected runtime, docker if none is found
+ */
+export async function getRuntime(): Promise<IContainerRuntime> {
+  return await getContainerRuntime(Config.getInstance().containerRuntime)
+}
+
+/**
+ * Get the compose command and global args for the container runtime
+ *
+ * Runtimes that don't support --profile get the profiles in the COMPOSE_PROFILES env var.
+ *
+ * @returns The command, args before the compose subcommand, and env vars to add
+ */
+export function getComposeCommand(
+  runtime: IContainerRuntime,
+  { projectName, composeFiles = [], profiles = [], ansi }: {
+    projectName: string
+    composeFiles?: Array<string | false | null | undefined>
+    profiles?: string[]
+    ansi?: DockerComposeOptions['ansi']
+  },
+): { cmd: string; args: string[]; env: Record<string, string> } {
+  const [cmd, ...composeArgs] = runtime.compose
+  const useProfileArgs = runtime.features.profiles
+  return {
+    cmd,
+    args: [
+      ...composeArgs,
+      ...(ansi && runtime.features.ansi ? ['--ansi', ansi] : []),
+      '-p',
+      projectName,
+      ...composeFiles.flatMap((file) => file ? ['-f', file] : []),
+      ...(useProfileArgs ? profiles.flatMap((profile) => ['--profile', profile]) : []),
+    ],
+    env: !useProfileArgs && profiles.length ? { COMPOSE_PROFILES: profiles.join(',') } : {},
   }
 }
 
@@ -78,6 +146,18 @@ export async function getDockerNetworks(
   { name, silent = true }: { name?: string; silent?: boolean } = {},
 ): Promise<TryCatchResult<string[]>> {
   const results = success<string[]>([])
+
+  const apiResult = await tryDockerApi((api) =>
+    api.listNetworks({ filters: name ? { name: [name] } : undefined })
+  )
+  if (apiResult) {
+    if (!apiResult.success) {
+      return failure<string[]>('Failed to get docker networks', apiResult, [])
+    }
+    results.data = apiResult.data?.map((network) => network.Id) || []
+    return results
+  }
+
   const networks = await tryDocker('network', {
     args: [
       'ls',
@@ -96,21 +176,91 @@ export async function getDockerNetworks(
   return results
 }
 
+/**
+ * Get docker volumes created by a Docker Compose project
+ * @param projectName - The compose project name
+ * @param silent - If true, don't show any output
+ * @returns {Promise<TryCatchResult<string[]>>} The volume names
+ */
+export async function getDockerVolumes(
+  projectName: string,
+  { silent = true }: { silent?: boolean } = {},
+): Promise<TryCatchResult<string[]>> {
+  const results = success<string[]>([])
+  const volumes = await tryDocker('volume', {
+    args: [
+      'ls',
+      '--filter',
+      `label=com.docker.compose.project=${projectName}`,
+      '--format',
+      '{{.Name}}',
+    ],
+    captureOutput: true,
+    silent,
+  })
+  if (volumes.success) {
+    results.data = volumes.data?.toList() || []
+  } else {
+    return failure<string[]>('Failed to get docker volumes', volumes, [])
+  }
+  return results
+}
+
 /**
  * Remove a docker network
  * @param networks - The network(s) to remove
  * @param silent - If true, don't show any output
- * @returns {Promise<TryCatchResult<RunCommandOutput>>} The output of the command
+ * @returns {Promise<TryCatchResult<boolean>>} True if the networks were removed
  */
 export async function removeDockerNetwork(
   networks: string | string[],
   { silent = true }: { silent?: boolean } = {},
-): Promise<TryCatchResult<RunCommandOutput>> {
-  return await tryDocker('network', {
-    args: ['rm', '-f', ...(Array.isArray(networks) ? networks : [networks])],
+): Promise<TryCatchResult<boolean>> {
+  const networkIds = Array.isArray(networks) ? networks : [networks]
+  // Networks that don't exist are ignored, same as `docker network rm -f`
+  const apiResult = await tryDockerApi(async (api) => {
+    await Promise.all(networkIds.map((id) =>
+      api.removeNetwork(id).catch((error) => {
+        if (!(error instanceof DockerNotFoundError)) throw error
+      })
+    ))
+    return true
+  })
+  if (apiResult) {
+    return apiResult
+  }
+  // nerdctl network rm doesn't support --force
+  const force = (await getRuntime()).name !== 'nerdctl'
+  const results = await tryDocker('network', {
+    args: ['rm', ...(force ? ['-f'] : []), ...networkIds],
     silent,
     captureOutput: true,
   })
+  return results.success
+    ? success<boolean>(true)
+    : failure<boolean>('Failed to remove docker network', results, false)
+}
+
+/**
+ * Run a Docker Engine API request, returns null if the Docker socket is not available
+ *
+ * Callers should fall back to the docker CLI when null is returned.
+ * @param fn - The API request to run
+ * @returns {Promise<TryCatchResult<T> | null>} The result or null if the socket is not available
+ */
+export async function tryDockerApi<T>(
+  fn: (api: DockerApiClient) => Promise<T>,
+): Promise<TryCatchResult<T> | null> {
+  const api = DockerApiClient.getInstance((await getRuntime()).socketPath)
+  if (!api.isAvailable) {
+    return null
+  }
+  const result = await tryCatch<T>(fn(api))
+  if (!result.success && result.error instanceof DockerSocketError) {
+    Relayer.getInstance().debug('Docker socket not available, using the docker CLI', result.error)
+    return null
+  }
+  return result
 }
 
 /**
@@ -170,17 +320,26 @@ export async function runDockerComposeCommand(
     captureOutput = false,
     env = {},
     autoLoadEnv = true, // If true, load env from .env file
+    composeOverrides = true,
   }: DockerComposeOptions = {},
 ): Promise<RunCommandOutput> {
-  const composeFiles = Array.isArray(composeFile) ? composeFile : [composeFile]
-  return await runCommand('docker', {
+  const composeFiles = (Array.isArray(composeFile) ? composeFile : [composeFile]).flatMap(
+    (file) => {
+      // Add the project override after each compose file, see Config.getComposeOverrideFile
+      const override = composeOverrides && file &&
+        Config.getInstance().getComposeOverrideFile(file)
+      return override ? [file, override] : [file]
+    },
+  )
+  const compose = getComposeCommand(await getRuntime(), {
+    projectName,
+    composeFiles,
+    profiles,
+    ansi,
+  })
+  return await runCommand(compose.cmd, {
     args: [
-      'compose',
-      ...(ansi ? ['--ansi', ansi] : []),
-      '-p',
-      projectName,
-      ...(composeFiles.map((file) => file ? ['-f', file] : []).flat()),
-      ...(profiles ? profiles.map((profile) => ['--profile', profile]) : []).flat(),
+      ...compose.args,
       cmd,
       ...(args || []),
     ].filter(Boolean),
@@ -188,6 +347,7 @@ export async function runDockerComposeCommand(
     captureOutput,
     env: {
       ...(await dockerEnv()),
+      ...compose.env,
       ...env,
     },
     autoLoadEnv,
@@ -217,7 +377,7 @@ export async function runDockerCommand(
     autoLoadEnv = true, // If true, load env from .env file
   }: DockerCommandOptions = {},
 ): Promise<RunCommandOutput> {
-  return await runCommand('docker', {
+  return await runCommand((await getRuntime()).command, {
     args: [
       cmd,
       ...(args || []),
@@ -267,13 +427,22 @@ export async function dockerComposePs<T extends PsFormatType>(
   projectName: string,
   { format = 'json' as T, services }: { format?: T; services?: string | string[] } = {},
 ): Promise<T extends 'json' ? DockerComposePsResult : string[]> {
+  if (format === 'json') {
+    const apiResult = await tryDockerApi((api) => dockerApiComposePs(api, projectName, services))
+    if (apiResult?.success) {
+      // deno-lint-ignore no-explicit-any
+      return apiResult.data as any
+    } else if (apiResult) {
+      throw apiResult.error
+    }
+  }
+
+  const compose = getComposeCommand(await getRuntime(), { projectName })
   const results = await runCommand(
-    'docker',
+    compose.cmd,
     {
       args: [
-        'compose',
-        '-p',
-        projectName,
+        ...compose.args,
         'ps',
         '-a',
         '--format',
@@ -292,15 +461,88 @@ export async function dockerComposePs<T extends PsFormatType>(
     : results.toJsonList() as any
 }
 
+/**
+ * Get the containers of a compose project from the Docker Engine API in `docker compose ps` format
+ *
+ * One-off containers created by `docker compose run` are excluded, same as `docker compose ps`.
+ */
+async function dockerApiComposePs(
+  api: DockerApiClient,
+  projectName: string,
+  services?: string | string[],
+): Promise<DockerComposePsResult> {
+  const serviceNames = (Array.isArray(services) ? services : [services]).filter(Boolean)
+  const containers = await api.listContainers({
+    all: true,
+    filters: { label: [`com.docker.compose.project=${projectName}`] },
+  })
+  return containers
+    .filter((c) => c.Labels['com.docker.compose.oneoff'] !== 'True')
+    .filter((c) =>
+      !serviceNames.length || serviceNames.includes(c.Labels['com.docker.compose.service'])
+    )
+    .map((c) => toComposePsContainer(c))
+}
+
+/**
+ * Convert a Docker Engine API container summary to the `docker compose ps` json format
+ */
+export function toComposePsContainer(container: DockerContainerSummary): DockerComposePsResult[0] {
+  const health = container.Status.match(/\((healthy|unhealthy|health: starting)\)/)?.[1]
+  const exitCode = container.Status.match(/^Exited \((-?\d+)\)/)?.[1]
+  return {
+    ID: container.Id.slice(0, 12),
+    Name: container.Names[0]?.replace(/^\//, ''),
+    Image: container.Image,
+    Service: container.Labels['com.docker.compose.service'],
+    Project: container.Labels['com.docker.compose.project'],
+    State: container.State,
+    Status: container.Status,
+    Health: health === 'health: starting' ? 'starting' : health || '',
+    ExitCode: exitCode ? Number(exitCode) : 0,
+    Publishers: container.Ports.map((port) => ({
+      URL: port.IP || '',
+      TargetPort: port.PrivatePort,
+      PublishedPort: port.PublicPort || 0,
+      Protocol: port.Type,
+    })),
+  }
+}
+
+/**
+ * Create the docker network if it doesn't exist
+ * @param network - The network name, defaults to the project network
+ * @returns {Promise<TryCatchResult<boolean>>} True if the network was created or already exists
+ */
 export async function prepareDockerNetwork(
   network?: string,
-): Promise<TryCatchResult<RunCommandOutput>> {
+): Promise<TryCatchResult<boolean>> {
   const relayer = Relayer.getInstance()
   if (!network) {
     network = Config.getInstance().dockerNetworkName
   }
-  const results = await tryRunCommand('docker', {
-    args: ['network', 'ls'],
+
+  const apiResult = await tryDockerApi(async (api) => {
+    const networks = await api.listNetworks({ filters: { name: [network!] } })
+    // The name filter matches partial names
+    if (!networks.some((n) => n.Name === network)) {
+      await api.createNetwork(network!)
+    }
+    return true
+  })
+  if (apiResult?.success || apiResult?.error instanceof DockerConflictError) {
+    return success<boolean>(true)
+  } else if (apiResult) {
+    relayer.error('Unable to create docker network: {network}: {error}', {
+      network,
+      error: apiResult.error?.message,
+    })
+    return failure<boolean>(`Unable to create docker network: ${network}`, apiResult, false)
+  }
+
+  const runtime = await getRuntime()
+  const results = await tryRunCommand(runtime.command, {
+    args: ['network', 'ls', '--format', '{{.Name}}'],
     captureOutput: true,
     silent: true,
   })
@@ -309,21 +551,26 @@ export async function prepareDockerNetwork(
       error: results.error?.stderr,
     })
   }
-  if (results.success && !results.toString().includes(network)) {
+  if (results.success && !results.data?.toList().includes(network)) {
     results.collect([
-      await tryRunCommand('docker', {
+      await tryRunCommand(runtime.command, {
         args: ['network', 'create', network],
         silent: true,
       }),
     ])
-    if (!results.success) {
+    // Podman and nerdctl use different error messages for existing networks
+    if (!results.success && /already (exists|used)/.test(results.error?.stderr || '')) {
+      return success<boolean>(true)
+    } else if (!results.success) {
       relayer.error('Unable to create docker network: {network}: {error}', {
         network,
         error: results.error?.stderr,
       })
     }
   }
-  return results
+  return results.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to create docker network: ${network}`, results, false)
 }
 
 /**
@@ -337,9 +584,20 @@ export async function dockerExec(
   projectName: string,
   service: string,
   cmd: string,
-  { composeFile, args, silent = false, captureOutput = false }: {
+  {
+    composeFile,
+    profiles,
+    args,
+    user,
+    interactive = false,
+    silent = false,
+    captureOutput = false,
+  }: {
     composeFile?: string
+    profiles?: string[]
     args?: Array<string | false>
+    user?: string
+    interactive?: boolean
     silent?: boolean
     captureOutput?: boolean
   } = {},
@@ -353,20 +611,270 @@ export async function dockerExec(
     throw new Error(`Compose file not found for ${service}`)
   }
 
-  return await runCommand('docker', {
+  const compose = getComposeCommand(await getRuntime(), {
+    projectName,
+    composeFiles: [composeFile],
+    profiles,
+  })
+  return await runCommand(compose.cmd, {
     args: [
-      'compose',
-      '-p',
-      projectName,
-      '-f',
-      composeFile,
+      ...compose.args,
       'exec',
+      ...(user ? ['--user', user] : []),
+      // Disable pseudo-TTY allocation when input is piped in
+      interactive && !Deno.stdin.isTerminal() && '-T',
       service,
       cmd,
       ...(args || []),
     ],
     captureOutput,
     silent,
+    interactive,
+    env: compose.env,
+  })
+}
+
+/**
+ * Get the version of a local image from the org.opencontainers.image.version label
+ *
+ * @param {string} image - The image name, e.g. n8nio/n8n:latest
+ * @returns {Promise<TryCatchResult<string>>} The version or an empty string if not labeled
+ */
+export async function getDockerImageVersion(image: string): Promise<TryCatchResult<string>> {
+  const apiResult = await tryDockerApi((api) => api.inspectImage(image))
+  if (apiResult) {
+    return apiResult.success
+      ? success<string>(apiResult.data?.Config.Labels?.['org.opencontainers.image.version'] || '')
+      : failure<string>(`Unable to inspect image: ${image}`, apiResult)
+  }
+  const results = await tryDocker('inspect', {
+    args: ['--format', '{{index .Config.Labels "org.opencontainers.image.version"}}', image],
+    captureOutput: true,
+    silent: true,
+  })
+  return results.success
+    ? success<string>(results.toString().trim())
+    : failure<string>(`Unable to inspect image: ${image}`, results)
+}
+
+/**
+ * Get the registry digests of a local image
+ *
+ * @param {string} image - The image name, e.g. n8nio/n8n:latest
+ * @returns {Promise<TryCatchResult<string[]>>} The RepoDigests, e.g. ['n8nio/n8n@sha256:...']
+ */
+export async function getDockerImageDigests(image: string): Promise<TryCatchResult<string[]>> {
+  const apiResult = await tryDockerApi((api) => api.inspectImage(image))
+  if (apiResult) {
+    return apiResult.success
+      ? success<string[]>(apiResult.data?.RepoDigests || [])
+      : failure<string[]>(`Unable to inspect image: ${image}`, apiResult)
+  }
+  const results = await tryDocker('image', {
+    args: ['inspect', '--format', '{{json .RepoDigests}}', image],
+    captureOutput: true,
+    silent: true,
+  })
+  if (!results.success) {
+    return failure<string[]>(`Unable to inspect image: ${image}`, results)
+  }
+  // Podman outputs null for images without digests
+  const digests = results.data?.toJson()
+  return success<string[]>(Array.isArray(digests) ? digests as string[] : [])
+}
+
+/**
+ * Pull an image from its registry
+ *
+ * @param {string} image - The image name, e.g. n8nio/n8n:latest
+ */
+export async function pullDockerImage(
+  image: string,
+  { silent = true }: { silent?: boolean } = {},
+): Promise<TryCatchResult<boolean>> {
+  const results = await tryDocker('pull', { args: [image], silent })
+  return results.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to pull image: ${image}`, results, false)
+}
+
+/**
+ * Tag an image, e.g. to add the tag back to an image pulled by digest
+ */
+export async function tagDockerImage(
+  source: string,
+  target: string,
+): Promise<TryCatchResult<boolean>> {
+  const results = await tryDocker('tag', { args: [source, target], silent: true })
+  return results.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to tag image ${source} as ${target}`, results, false)
+}
+
+/**
+ * Save images to a tar archive with `docker save`
+ *
+ * @param {string[]} images - The images to save
+ * @param {string} file - The archive to write
+ */
+export async function saveDockerImages(
+  images: string[],
+  file: string,
+  { silent = true }: { silent?: boolean } = {},
+): Promise<TryCatchResult<boolean>> {
+  const results = await tryDocker('save', { args: ['-o', file, ...images], silent })
+  return results.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to save images to ${file}`, results, false)
+}
+
+/**
+ * Load images from a tar archive created with `docker save`
+ */
+export async function loadDockerImages(
+  file: string,
+  { silent = true }: { silent?: boolean } = {},
+): Promise<TryCatchResult<boolean>> {
+  const results = await tryDocker('load', { args: ['-i', file], silent })
+  return results.success
+    ? success<boolean>(true)
+    : failure<boolean>(`Unable to load images from ${file}`, results, false)
+}
+
+/**
+ * Get the memory and CPUs available to containers
+ *
+ * Falls back to `info --format json`, podman nests the values under host.
+ * @returns {Promise<TryCatchResult<DockerSystemInfo>>} The system info
+ */
+export async function getDockerInfo(): Promise<TryCatchResult<DockerSystemInfo>> {
+  const apiResult = await tryDockerApi((api) => api.info())
+  if (apiResult) {
+    return apiResult.success
+      ? success<DockerSystemInfo>(apiResult.data!)
+      : failure<DockerSystemInfo>('Unable to get docker info', apiResult)
+  }
+  const results = await tryDocker('info', {
+    args: ['--format', 'json'],
+    captureOutput: true,
+    silent: true,
+  })
+  if (!results.success || !results.data) {
+    return failure<DockerSystemInfo>('Unable to get docker info', results)
+  }
+  const info = results.data.toJson() as Record<string, unknown> & {
+    host?: { memTotal?: number; cpus?: number; os?: string }
+    version?: { Version?: string }
+  }
+  return success<DockerSystemInfo>({
+    MemTotal: Number(info.MemTotal ?? info.host?.memTotal ?? 0),
+    NCPU: Number(info.NCPU ?? info.host?.cpus ?? 0),
+    ServerVersion: String(info.ServerVersion ?? info.version?.Version ?? ''),
+    OperatingSystem: String(info.OperatingSystem ?? info.host?.os ?? ''),
+  })
+}
+
+/**
+ * Inspect all networks with an exact name
+ *
+ * Docker allows multiple networks with the same name, compose fails when the name is ambiguous.
+ * @param {string} name - The network name
+ * @returns {Promise<TryCatchResult<DockerNetwork[]>>} The networks with their attached containers
+ */
+export async function inspectDockerNetworks(
+  name: string,
+): Promise<TryCatchResult<DockerNetwork[]>> {
+  const apiResult = await tryDockerApi(async (api) => {
+    // The name filter matches partial names
+    const networks = (await api.listNetworks({ filters: { name: [name] } }))
+      .filter((network) => network.Name === name)
+    return await Promise.all(networks.map((network) => api.inspectNetwork(network.Id)))
+  })
+  if (apiResult) {
+    return apiResult.success
+      ? success<DockerNetwork[]>(apiResult.data || [])
+      : failure<DockerNetwork[]>(`Unable to inspect docker network: ${name}`, apiResult, [])
+  }
+
+  const idsResult = await getDockerNetworks({ name })
+  if (!idsResult.success || !idsResult.data?.length) {
+    return idsResult.success
+      ? success<DockerNetwork[]>([])
+      : failure<DockerNetwork[]>(`Unable to inspect docker network: ${name}`, idsResult, [])
+  }
+  const results = await tryDocker('network', {
+    args: ['inspect', ...idsResult.data],
+    captureOutput: true,
+    silent: true,
+  })
+  if (!results.success || !results.data) {
+    return failure<DockerNetwork[]>(`Unable to inspect docker network: ${name}`, results, [])
+  }
+  // podman uses lowercase keys
+  const networks = (results.data.toJson() as Array<Record<string, unknown>>).map((network) => ({
+    Id: String(network.Id ?? network.id ?? ''),
+    Name: String(network.Name ?? network.name ?? ''),
+    Driver: String(network.Driver ?? network.driver ?? ''),
+    Labels: (network.Labels ?? network.labels ?? {}) as Record<string, string>,
+    Containers: (network.Containers ?? network.containers ?? {}) as DockerNetwork['Containers'],
+  }))
+  return success<DockerNetwork[]>(networks.filter((network) => network.Name === name))
+}
+
+/**
+ * Stream the logs of a docker compose container line by line
+ *
+ * Log prefixes and colors are disabled so the caller can format each line.
+ * When timestamps is true, each line starts with an RFC3339 timestamp.
+ *
+ * @param {string} projectName - The name of the project
+ * @param {string} containerName - The compose service name of the container
+ * @param {Object} options - The options for the logs command
+ * @returns {RunCommandOutput} The output of the command once the stream ends
+ */
+export async function dockerComposeLogs(
+  projectName: string,
+  containerName: string,
+  {
+    composeFile,
+    profiles,
+    follow = true,
+    since,
+    tail,
+    timestamps = true,
+    onLine,
+    signal,
+  }: {
+    composeFile: string
+    profiles?: string[]
+    follow?: boolean
+    since?: string
+    tail?: string | number
+    timestamps?: boolean
+    onLine: (line: string, stream: 'stdout' | 'stderr') => void
+    signal?: AbortSignal
+  },
+): Promise<RunCommandOutput> {
+  const compose = getComposeCommand(await getRuntime(), {
+    projectName,
+    composeFiles: [composeFile],
+    profiles,
+  })
+  return await streamCommand(compose.cmd, {
+    args: [
+      ...compose.args,
+      'logs',
+      '--no-color',
+      '--no-log-prefix',
+      follow && '--follow',
+      timestamps && '--timestamps',
+      ...(since ? ['--since', since] : []),
+      ...(tail !== undefined ? ['--tail', String(tail)] : []),
+      containerName,
+    ],
+    env: { ...(await dockerEnv()), ...compose.env },
+    onLine,
+    signal,
   })
 }
 
@@ -409,6 +917,76 @@ export async function dockerComposeRun(
   })
 }
 
+// Image used by temporary containers to read and write docker volumes
+const VOLUME_HELPER_IMAGE = 'alpine:3'
+
+/**
+ * Archive a docker volume or host directory to a tar.gz file
+ *
+ * Runs tar in a temporary container so files owned by container users can be read.
+ *
+ * @param {string} source - Docker volume name or absolute host path
+ * @param {string} archiveFile - Absolute path of the tar.gz file to create
+ * @param {string[]} exclude - Paths relative to the source to exclude
+ * @returns {TryCatchResult<RunCommandOutput>} The output of the command
+ */
+export async function dockerArchiveVolume(
+  source: string,
+  archiveFile: string,
+  { exclude = [] }: { exclude?: string[] } = {},
+): Promise<TryCatchResult<RunCommandOutput, CommandError>> {
+  return await tryDocker('run', {
+    args: [
+      '--rm',
+      '-v',
+      `${source}:/source:ro`,
+      '-v',
+      `${path.dirname(archiveFile)}:/backup`,
+      VOLUME_HELPER_IMAGE,
+      'tar',
+      ...exclude.map((exclude) => `--exclude=./${exclude}`),
+      '-czf',
+      `/backup/${path.basename(archiveFile)}`,
+      '-C',
+      '/source',
+      '.',
+    ],
+    silent: true,
+    captureOutput: true,
+  })
+}
+
+/**
+ * Restore a tar.gz file created by dockerArchiveVolume to a docker volume or host directory
+ *
+ * @param {string} target - Docker volume name or absolute host path
+ * @param {string} archiveFile - Absolute path of the tar.gz file to restore
+ * @param {boolean} clean - Delete the existing contents of the target first
+ * @returns {TryCatchResult<RunCommandOutput>} The output of the command
+ */
+export async function dockerRestoreVolume(
+  target: string,
+  archiveFile: string,
+  { clean = false }: { clean?: boolean } = {},
+): Promise<TryCatchResult<RunCommandOutput, CommandError>> {
+  const extract = `tar -xzf /backup/${path.basename(archiveFile)} -C /target`
+  return await tryDocker('run', {
+    args: [
+      '--rm',
+      '-v',
+      `${target}:/target`,
+      '-v',
+      `${path.dirname(archiveFile)}:/backup:ro`,
+      VOLUME_HELPER_IMAGE,
+      'sh',
+      '-c',
+      clean ? `find /target -mindepth 1 -delete && ${extract}` : extract,
+    ],
+    silent: true,
+    captureOutput: true,
+  })
+}
+
 /**
  * Build a docker image
  */
@@ -0,0 +1,248 @@
+import { assert, assertEquals } from 'jsr:@std/assert'
+import { parse as parseDotEnv } from 'jsr:@std/dotenv'
+import {
+  EnvDocument,
+  formatEnvValue,
+  loadEnv,
+  mergeEnvFragment,
+  parseWithoutExpand,
+} from './env.ts'
+
+// Files that must serialize back to the exact same text
+const ROUND_TRIP_CORPUS: Record<string, string> = {
+  'empty file': '',
+  'single newline': '\n',
+  'comment without newline': '# comment only',
+  'entry without newline': 'A=1',
+  'entry': 'A=1\n',
+  'CRLF line endings': 'A=1\r\nB=2\r\n',
+  'export, indentation and spacing': '  export   A = value  # comment\n',
+  'hash in double quotes': 'A="has # hash" # real comment\n',
+  'quote styles': "A='single ${NOT_EXPANDED}'\nB=\"double ${EXPANDED}\"\n",
+  'multiline double quotes': 'A="line1\nline2\nline3"\nB=after\n',
+  'multiline single quotes': "A='\nmulti\nline\n'\nB=2\n",
+  'escaped characters': 'A="escaped\\nnewline\\ttab"\n',
+  'empty values': 'A= # Generated by init script\nB=\n',
+  'duplicate keys': 'A=1\nA=2\n',
+  'invalid lines': 'not a valid line\n1BAD=x\nGOOD=y\n',
+  'unclosed quote': 'A="unclosed\nB=2\n',
+  'hash in unquoted value': 'A=x#y\nB=x # y\n',
+  'text after closing quote': 'A="a"b\n',
+  'blank lines and sections': '\n\n# Section\n\nA=1\n\n\n',
+  'multiline CRLF': 'A="crlf\r\nmulti"\r\nB=2',
+  'other quote inside quotes': "A='it\"s' # quote\n",
+}
+
+// Shipped .env.example files
+async function readExampleFiles(): Promise<Record<string, string>> {
+  const files: Record<string, string> = {
+    '.env.example': await Deno.readTextFile(new URL('../../.env.example', import.meta.url)),
+  }
+  const servicesDir = new URL('../../services/', import.meta.url)
+  for await (const entry of Deno.readDir(servicesDir)) {
+    if (!entry.isDirectory) continue
+    const file = new URL(`${entry.name}/.env.example`, servicesDir)
+    try {
+      files[`services/${entry.name}/.env.example`] = await Deno.readTextFile(file)
+    } catch (error) {
+      if (!(error instanceof Deno.errors.NotFound)) throw error
+    }
+  }
+  return files
+}
+
+// Compare with std dotenv, which expands ${VAR} references in values that aren't single quoted
+function assertSameAsDotEnv(text: string, name: string): void {
+  const doc = EnvDocument.parse(text)
+  const expected = parseDotEnv(text)
+  assertEquals(Object.keys(doc.toObject()), Object.keys(expected), name)
+  for (const entry of doc.entries) {
+    if (entry.quote !== "'" && entry.value.includes('$')) continue
+    assertEquals(doc.get(entry.key), expected[entry.key], `${name}: ${entry.key}`)
+  }
+}
+
+function setValue(text: string, key: string, value: string): string {
+  return EnvDocument.parse(text).set(key, value).toString()
+}
+
+Deno.test('EnvDocument round trips the corpus exactly', () => {
+  for (const [name, text] of Object.entries(ROUND_TRIP_CORPUS)) {
+    assertEquals(EnvDocument.parse(text).toString(), text, name)
+  }
+})
+
+Deno.test('EnvDocument parses values the same way as std dotenv', () => {
+  for (const [name, text] of Object.entries(ROUND_TRIP_CORPUS)) {
+    assertSameAsDotEnv(text, name)
+  }
+})
+
+Deno.test('EnvDocument round trips the shipped .env.example files', async () => {
+  const files = await readExampleFiles()
+  assert(Object.keys(files).length > 1)
+  for (const [name, text] of Object.entries(files)) {
+    assertEquals(EnvDocument.parse(text).toString(), text, name)
+    assertSameAsDotEnv(text, name)
+  }
+})
+
+Deno.test('EnvDocument gets values', () => {
+  const doc = EnvDocument.parse(
+    [
+      '# Comment=not a key',
+      'export A="has # hash" # comment',
+      "B='${NOT_EXPANDED}'",
+      'C="multi',
+      'line"',
+      'D="escaped\\n"',
+      'E= # Generated',
+      'A=last wins',
+      '',
+    ].join('\n'),
+  )
+  assertEquals(doc.keys, ['A', 'B', 'C', 'D', 'E'])
+  assertEquals(doc.get('A'), 'last wins')
+  assertEquals(doc.get('B'), '${NOT_EXPANDED}')
+  assertEquals(doc.get('C'), 'multi\nline')
+  assertEquals(doc.get('D'), 'escaped\n')
+  assertEquals(doc.get('E'), '')
+  assertEquals(doc.get('Comment'), undefined)
+  assert(doc.has('E'))
+  assert(!doc.has('F'))
+})
+
+Deno.test('EnvDocument.set keeps comments, export prefix and quotes', () => {
+  assertEquals(
+    setValue('# c\nexport A="old" # keep\nB=1\n', 'A', 'new'),
+    '# c\nexport A="new" # keep\nB=1\n',
+  )
+  assertEquals(setValue("A='old'\n", 'A', 'new'), "A='new'\n")
+  assertEquals(setValue('A="x"# c\n', 'A', 'y'), 'A="y"# c\n')
+  assertEquals(setValue('A=x\n', 'A', 'has space'), "A='has space'\n")
+  assertEquals(setValue('A=1\nA=2\n', 'A', '3'), 'A=3\nA=3\n')
+  assertEquals(setValue('A=same # c\n', 'A', 'same'), 'A=same # c\n')
+})
+
+Deno.test('EnvDocument.set fills and clears values before an inline comment', () => {
+  assertEquals(setValue('A= # Generated\n', 'A', 'abc'), 'A=abc # Generated\n')
+  assertEquals(setValue('A=abc # Generated\n', 'A', ''), 'A= # Generated\n')
+  assertEquals(setValue("A='x'# c\n", 'A', 'y'), "A='y'# c\n")
+})
+
+Deno.test('EnvDocument.set appends new keys', () => {
+  assertEquals(setValue('', 'B', '2'), 'B=2\n')
+  assertEquals(setValue('A=1', 'B', '2'), 'A=1\nB=2\n')
+  assertEquals(setValue('A=1\n', 'B', '2'), 'A=1\nB=2\n')
+  assertEquals(setValue('A=1\r\n', 'B', '2'), 'A=1\r\nB=2\r\n')
+  assertEquals(setValue('A=1\n', 'B', 'a\nb'), 'A=1\nB="a\\nb"\n')
+})
+
+Deno.test('EnvDocument.set values read back unchanged', () => {
+  const values = ['plain', 'with space', '${VAR}', "it's", 'a"b', 'multi\nline', '#hash', 'a\\b']
+  for (const value of values) {
+    const text = EnvDocument.parse('A=1\n').set('KEY', value).toString()
+    assertEquals(EnvDocument.parse(text).get('KEY'), value, value)
+    assertEquals(parseDotEnv(text).KEY, value, value)
+  }
+})
+
+Deno.test('EnvDocument.unset removes every entry for a key', () => {
+  const doc = EnvDocument.parse('# c\nA=1\nB=2\nA="3\n4"\n')
+  assert(doc.unset('A'))
+  assertEquals(doc.toString(), '# c\nB=2\n')
+  assert(!doc.unset('A'))
+})
+
+Deno.test('EnvDocument.append keeps the line endings', () => {
+  const doc = EnvDocument.parse('A=1\r\n').append('\n# Section\nB=2\n')
+  assertEquals(doc.toString(), 'A=1\r\n\r\n# Section\r\nB=2\r\n')
+})
+
+Deno.test('formatEnvValue only quotes values when needed', () => {
+  assertEquals(formatEnvValue(''), '')
+  assertEquals(formatEnvValue('abc'), 'abc')
+  assertEquals(formatEnvValue('a b'), "'a b'")
+  assertEquals(formatEnvValue('${VAR}'), "'${VAR}'")
+  assertEquals(formatEnvValue("a'b"), `"a'b"`)
+  assertEquals(formatEnvValue('abc', '"'), '"abc"')
+  assertEquals(formatEnvValue('a"b', '"'), `'a"b'`)
+})
+
+Deno.test('parseWithoutExpand does not expand variables', () => {
+  assertEquals(parseWithoutExpand('A=1\nB=${A}\nC="${A}"\n'), { A: '1', B: '${A}', C: '${A}' })
+})
+
+const FRAGMENT = `# =============================================
+# N8N CONFIG
+# =============================================
+
+# Postgres settings for n8n
+N8N_POSTGRES_USER=postgres
+N8N_POSTGRES_SCHEMA=service_n8n
+
+# Timezone for n8n
+# GENERIC_TIMEZONE=America/Los_Angeles
+
+# Set to a long random string
+N8N_ENCRYPTION_KEY= # Generated by init script
+`
+
+Deno.test('mergeEnvFragment appends the whole fragment for a new service', () => {
+  const { content, added } = mergeEnvFragment('OPENAI_API_KEY=sk-test\n', FRAGMENT)
+  assertEquals(added, ['N8N_POSTGRES_USER', 'N8N_POSTGRES_SCHEMA', 'N8N_ENCRYPTION_KEY'])
+  assertEquals(content, `OPENAI_API_KEY=sk-test\n\n\n${FRAGMENT}`)
+  assertEquals(mergeEnvFragment('', FRAGMENT).content, FRAGMENT)
+})
+
+Deno.test('mergeEnvFragment only adds missing keys and keeps existing values', () => {
+  const env = 'N8N_POSTGRES_USER=custom\nN8N_ENCRYPTION_KEY=secret\n'
+  const { content, added } = mergeEnvFragment(env, FRAGMENT, { source: 'n8n .env.example' })
+  assertEquals(added, ['N8N_POSTGRES_SCHEMA'])
+  assertEquals(
+    content,
+    `${env}\n\n# Added from n8n .env.example\nN8N_POSTGRES_SCHEMA=service_n8n\n`,
+  )
+})
+
+Deno.test('mergeEnvFragment adds the comments above a missing key', () => {
+  const env = 'N8N_POSTGRES_USER=custom\nN8N_POSTGRES_SCHEMA=custom'
+  const { content, added } = mergeEnvFragment(env, FRAGMENT)
+  assertEquals(added, ['N8N_ENCRYPTION_KEY'])
+  assertEquals(
+    content,
+    `${env}\n\n\n# Set to a long random string\nN8N_ENCRYPTION_KEY= # Generated by init script\n`,
+  )
+})
+
+Deno.test('mergeEnvFragment leaves the file unchanged when all keys are set', () => {
+  const env = 'export N8N_POSTGRES_USER=a\nN8N_POSTGRES_SCHEMA="b"\nN8N_ENCRYPTION_KEY=\n'
+  assertEquals(mergeEnvFragment(env, FRAGMENT), { content: env, added: [] })
+})
+
+Deno.test('loadEnv layers the overlay file and expands with the overridden values', async () => {
+  const dir = await Deno.makeTempDir()
+  const keys = ['ENV_TEST_PASSWORD', 'ENV_TEST_URL', 'ENV_TEST_USER']
+  try {
+    await Deno.writeTextFile(
+      `${dir}/.env`,
+      'ENV_TEST_PASSWORD=dev\nENV_TEST_URL=postgres://${ENV_TEST_PASSWORD}@db\nENV_TEST_USER=dev\n',
+    )
+    await Deno.writeTextFile(`${dir}/.env.ci`, 'ENV_TEST_PASSWORD=ci')
+    const env = await loadEnv({ envPath: `${dir}/.env`, overlayPath: `${dir}/.env.ci` })
+    assertEquals(env.ENV_TEST_PASSWORD, 'ci')
+    assertEquals(env.ENV_TEST_URL, 'postgres://ci@db')
+    assertEquals(env.ENV_TEST_USER, 'dev')
+    assertEquals(Deno.env.get('ENV_TEST_PASSWORD'), 'ci')
+
+    const raw = await loadEnv({
+      envPath: `${dir}/.env`,
+      overlayPath: `${dir}/.env.missing`,
+      expand: false,
+    })
+    assertEquals(raw.ENV_TEST_URL, 'postgres://${ENV_TEST_PASSWORD}@db')
+  } finally {
+    keys.forEach((key) => Deno.env.delete(key))
+    await Deno.remove(dir, { recursive: true })
+  }
+})
@@ -1,15 +1,19 @@
 /**
- * Utils for parsing .env files
+ * Utils for parsing and editing .env files
  *
  * Deno's dotenv auto expands env variables with no ability to turn off expansion.
  * The functions in this file allow for processing .env files without expansion.
  *
+ * .env files are edited with EnvDocument, which keeps comments, blank lines, ordering and
+ * quoting intact. Values are parsed the same way as 'jsr:@std/dotenv' so edits match the
+ * values loaded by loadEnv.
+ *
  * See mod.ts and parse.ts from 'jsr:@std/dotenv'
  */
 
 import { ensureDir, path, readTextFile } from '@/lib/fs.ts'
-import { failure, tryCatchBoolean, TryCatchResult } from '@/lib/try-catch.ts'
-import { load as loadDotEnv } from 'jsr:@std/dotenv'
+import { failure, success, tryCatchBoolean, TryCatchResult } from '@/lib/try-catch.ts'
+import { parse as parseDotEnv } from 'jsr:@std/dotenv'
 
 /**
  * Load the .env file
@@ -27,29 +31,31 @@ import { load as loadDotEnv } from 'jsr:@std/dotenv'
  *
  * @param {Object} options - The options for loading the .env file
  * @param {string} options.envPath - The path to the .env file
+ * @param {string} options.overlayPath - The path to an .env file that overrides .env values
  * @param {boolean} options.reload - Whether to reload the .env file into Deno.env
  * @param {boolean} options.expand - Whether to expand values in the .env file
  * @returns {Promise<Record<string, string>>} The environment variables
  */
 export async function loadEnv(
-  { envPath = '.env', reload = false, expand = true }: {
+  { envPath = '.env', overlayPath = null, reload = false, expand = true }: {
     envPath?: string
+    overlayPath?: string | null
     reload?: boolean
     expand?: boolean
   } = {},
 ): Promise<Record<string, string>> {
-  // Use deno's built-in env loader if expand is true, otherwise use the custom loader
-  const loadFunc = expand ? loadDotEnv : loadWithoutExpand
+  const envPaths = [envPath, overlayPath]
   let envValues = {} as Record<string, string>
   if (!reload) {
-    envValues = await loadFunc({ envPath, export: true })
+    envValues = await loadEnvFiles({ envPaths, expand, export: true })
   } else { // reload is true
-    envValues = await loadFunc({
-      envPath,
+    envValues = await loadEnvFiles({
+      envPaths,
+      expand,
       export: false, // Don't automatically export to Deno.env
     })
     // Set each variable in Deno.env even if already set
-    // loadDonEnv({ export: true }) will only set variables if undefined in Deno.env
+    // loadEnvFiles({ export: true }) will only set variables if undefined in Deno.env
     // The reload flag sets all variables even if they are already set in Deno.env
     for (const [key, value] of Object.entries(envValues)) {
       Deno.env.set(key, value)
@@ -58,10 +64,318 @@ export async function loadEnv(
   return envValues
 }
 
+//
+// EnvDocument
+//
+
+export type EnvQuote = '' | "'" | '"'
+
+/**
+ * A line in a .env file that sets a key
+ *
+ * raw is prefix + value + suffix, e.g. `export KEY=` + `"value"` + ` # comment`.
+ * Multiline quoted values span several lines of the file.
+ */
+export interface IEnvEntry {
+  type: 'entry'
+  key: string
+  value: string // Parsed value, quotes removed and escapes expanded
+  quote: EnvQuote
+  prefix: string // Indentation, export prefix, key and =
+  suffix: string // Whitespace and inline comment after the value
+  raw: string
+}
+
+/**
+ * A blank line, comment, or any other line that doesn't set a key
+ */
+export interface IEnvText {
+  type: 'text'
+  raw: string
+}
+
+export type EnvNode = IEnvEntry | IEnvText
+
+const RE_ENTRY_PREFIX = /^(\s*(?:export\s+)?([^\s=#]+)[ \t]*=)/
+const RE_VALID_KEY = /^[a-zA-Z_][a-zA-Z0-9_]*$/
+
+// Values that can be written without quotes
+const RE_UNQUOTED_VALUE = /^[^\s'"#\\$`]*$/
+
+/**
+ * Format a value for a .env file
+ *
+ * Values are only quoted when needed. Single quotes are used by default so ${VAR} is not
+ * expanded, double quotes for values with single quotes or newlines.
+ * dotenv has no escape for quotes, a value with both quote types can't be written exactly.
+ *
+ * @param value - The value to format
+ * @param quote - The preferred quote, e.g. the quote of the existing value
+ */
+export function formatEnvValue(value: string, quote: EnvQuote = ''): string {
+  if (!value) {
+    return ''
+  }
+  if (!quote && RE_UNQUOTED_VALUE.test(value)) {
+    return value
+  }
+  if ((quote === '"' && !value.includes('"')) || /['\r\n]/.test(value)) {
+    return `"${value.replaceAll('\n', '\\n').replaceAll('\r', '\\r')}"`
+  }
+  return `'${value}'`
+}
+
+function expandCharacters(str: string): string {
+  const charactersMap: Record<string, string> = {
+    '\\n': '\n',
+    '\\r': '\r',
+    '\\t': '\t',
+  }
+  return str.replace(/\\([nrt])/g, (match: string): string => charactersMap[match] ?? '')
+}
+
+/**
+ * Parse a quoted value the same way as 'jsr:@std/dotenv'
+ *
+ * A newline directly after the opening quote or before the closing quote is removed.
+ */
+function parseQuotedValue(inner: string, quote: EnvQuote): string {
+  const value = inner.replace(/^\r?\n?/, '').replace(/\r?\n?$/, '')
+  return quote === '"' ? expandCharacters(value) : value
+}
+
+/**
+ * Parse the entry starting at lines[start]
+ *
+ * @returns The entry and the index of its last line, null if the line doesn't set a key
+ */
+function parseEntry(lines: string[], start: number): { node: IEnvEntry; end: number } | null {
+  const line = lines[start]
+  const match = line.match(RE_ENTRY_PREFIX)
+  if (!match || !RE_VALID_KEY.test(match[2])) {
+    return null
+  }
+  const key = match[2]
+  const rest = line.slice(match[1].length)
+  const gap = rest.match(/^[ \t]*/)![0]
+  const quote = rest[gap.length]
+
+  // Quoted values end at the next matching quote, which can be on a later line
+  if (quote === "'" || quote === '"') {
+    let text = rest.slice(gap.length + 1)
+    for (let end = start; end < lines.length; end++) {
+      if (end > start) {
+        text += `\n${lines[end]}`
+      }
+      const close = text.indexOf(quote)
+      if (close === -1) continue
+      return {
+        node: {
+          type: 'entry',
+          key,
+          value: parseQuotedValue(text.slice(0, close), quote),
+          quote,
+          prefix: `${match[1]}${gap}`,
+          suffix: text.slice(close + 1),
+          raw: lines.slice(start, end + 1).join('\n'),
+        },
+        end,
+      }
+    }
+    // Values with an unclosed quote are read as unquoted values
+  }
+
+  // Unquoted values end at the first #
+  const hashIndex = rest.indexOf('#')
+  const value = (hashIndex === -1 ? rest : rest.slice(0, hashIndex)).trim()
+  // Whitespace around an empty value stays with the comment, a new value is added before it
+  const valueStart = value ? rest.indexOf(value) : 0
+  return {
+    node: {
+      type: 'entry',
+      key,
+      value,
+      quote: '',
+      prefix: `${match[1]}${rest.slice(0, valueStart)}`,
+      suffix: rest.slice(valueStart + value.length),
+      raw: line,
+    },
+    end: start,
+  }
+}
+
+/**
+ * Lossless .env document
+ *
+ * Parses a .env file into nodes that serialize back to the exact original text.
+ * Only the entries that are changed are reformatted.
+ *
+ * @example
+ * const doc = EnvDocument.parse(await Deno.readTextFile('.env'))
+ * doc.set('OPENAI_API_KEY', 'sk-...')
+ * await writeEnvDocument('.env', doc)
+ */
+export class EnvDocument {
+  readonly nodes: EnvNode[]
+  private _crlf: boolean
+
+  private constructor(nodes: EnvNode[], crlf: boolean) {
+    this.nodes = nodes
+    this._crlf = crlf
+  }
+
+  /**
+   * Parse the contents of a .env file
+   */
+  static parse(text: string): EnvDocument {
+    // Lines keep their \r so the original line endings are preserved
+    const lines = text.split('\n')
+    const nodes: EnvNode[] = []
+    for (let i = 0; i < lines.length; i++) {
+      const entry = parseEntry(lines, i)
+      if (entry) {
+        nodes.push(entry.node)
+        i = entry.end
+      } else {
+        nodes.push({ type: 'text', raw: lines[i] })
+      }
+    }
+    return new EnvDocument(nodes, text.includes('\r\n'))
+  }
+
+  /**
+   * Get the entries in the document, in order
+   */
+  get entries(): IEnvEntry[] {
+    return this.nodes.filter((node): node is IEnvEntry => node.type === 'entry')
+  }
+
+  /**
+   * Get the keys set in the document, in order of first appearance
+   */
+  get keys(): string[] {
+    return [...new Set(this.entries.map((entry) => entry.key))]
+  }
+
+  has(key: string): boolean {
+    return this.entries.some((entry) => entry.key === key)
+  }
+
+  /**
+   * Get the value of a key, the last entry wins if a key is set more than once
+   */
+  get(key: string): string | undefined {
+    return this.entries.findLast((entry) => entry.key === key)?.value
+  }
+
+  /**
+   * Get all the key values, ${VAR} references are not expanded
+   */
+  toObject(): Record<string, string> {
+    return Object.fromEntries(this.entries.map((entry) => [entry.key, entry.value]))
+  }
+
+  /**
+   * Set the value of a key
+   *
+   * Existing entries are updated in place, keeping the export prefix, quote style and inline
+   * comment. New keys are added to the end of the document.
+   */
+  set(key: string, value: string): this {
+    const entries = this.entries.filter((entry) => entry.key === key)
+    if (!entries.length) {
+      return this.append(`${key}=${formatEnvValue(value)}`)
+    }
+    for (const entry of entries) {
+      if (entry.value === value) continue
+      const formatted = formatEnvValue(value, entry.quote)
+      const quote = (/^['"]/.test(formatted) ? formatted[0] : '') as EnvQuote
+      // Keep a comment that directly followed a quoted value separate from an unquoted value
+      const suffix = formatted && !quote && entry.suffix.startsWith('#')
+        ? ` ${entry.suffix}`
+        : entry.suffix
+      Object.assign(entry, { value, quote, suffix, raw: `${entry.prefix}${formatted}${suffix}` })
+    }
+    return this
+  }
+
+  /**
+   * Remove all entries for a key
+   *
+   * @returns True if the key was removed
+   */
+  unset(key: string): boolean {
+    const nodes = this.nodes.filter((node) => node.type !== 'entry' || node.key !== key)
+    const removed = nodes.length !== this.nodes.length
+    this.nodes.splice(0, this.nodes.length, ...nodes)
+    return removed
+  }
+
+  /**
+   * Add lines to the end of the document, the document always ends with a newline
+   *
+   * @param text - The lines to add, e.g. a comment and an entry
+   */
+  append(text: string): this {
+    const nodes = EnvDocument.parse(text.replace(/\r?\n$/, '')).nodes
+    if (this._crlf) {
+      nodes.forEach((node) => {
+        node.raw = node.raw.split('\n').map((line) => line.replace(/\r?$/, '\r')).join('\n')
+      })
+    }
+    // An empty last node is the newline at the end of the file
+    const last = this.nodes.at(-1)
+    if (last?.type === 'text' && last.raw === '') {
+      this.nodes.splice(this.nodes.length - 1, 0, ...nodes)
+    } else {
+      this.nodes.push(...nodes, { type: 'text', raw: '' })
+    }
+    return this
+  }
+
+  toString(): string {
+    return this.nodes.map((node) => node.raw).join('\n')
+  }
+}
+
+/**
+ * Read a .env file into an EnvDocument
+ *
+ * @returns An empty document if the file doesn't exist
+ */
+export async function readEnvDocument(filePath: string): Promise<TryCatchResult<EnvDocument>> {
+  const readResult = await readTextFile(filePath)
+  if (!readResult.success && !(readResult.error instanceof Deno.errors.NotFound)) {
+    return failure<EnvDocument>(`Unable to read env file: ${filePath}`, readResult)
+  }
+  return success<EnvDocument>(EnvDocument.parse(readResult.data || ''))
+}
+
+/**
+ * Write a .env document to a file
+ *
+ * All functions that change a .env file write it here.
+ * @param filePath - The path to the .env file
+ * @param doc - The document to write
+ * @returns A TryCatchResult<boolean>
+ */
+export async function writeEnvDocument(
+  filePath: string,
+  doc: EnvDocument,
+): Promise<TryCatchResult<boolean>> {
+  const writeResults = await tryCatchBoolean(Deno.writeTextFile(filePath, doc.toString()))
+  if (!writeResults.success) {
+    return failure<boolean>(`Unable to save env file: ${filePath}`, writeResults, false)
+  }
+  return writeResults
+}
+
 /**
  * Update a .env file with new environment variables values
+ *
+ * Comments, ordering and quoting of existing keys are kept, new keys are added to the end.
  * @param filePath - The path to the .env file
- * @param envVars - The environment variables to save
+ * @param envVars - The environment variables to save, empty values are skipped
  * @returns A TryCatchResult<boolean>
  */
 export async function updateEnv(
@@ -86,75 +400,190 @@ export async function updateEnv(
   }
 
   // Load existing env file content to update, if any
-  const readResults = await readTextFile(filePath)
+  const readResults = await readEnvDocument(filePath)
+  if (!readResults.success || !readResults.data) {
+    return failure<boolean>(`Unable to update env file: ${filePath}`, readResults, false)
+  }
+  const doc = readResults.data
+
+  for (const [key, value] of Object.entries(envVars)) {
+    // Keep existing value in .env if envVars value not set
+    if (!value) continue
+    if (!doc.has(key)) {
+      results.addMessage('debug', `Key '${key}' not found in env file, adding to end of file`)
+    }
+    doc.set(key, value)
+  }
+
+  const writeResults = await writeEnvDocument(filePath, doc)
+  if (!writeResults.success) {
+    results.collect([writeResults])
+  }
+
+  return results
+}
 
+/**
+ * Clear the values of keys in a .env file, keeping the keys in place
+ *
+ * Used when secrets are moved to the encrypted secrets file.
+ * @param filePath - The path to the .env file
+ * @param keys - The keys to clear
+ * @returns A TryCatchResult<boolean>
+ */
+export async function clearEnvValues(
+  filePath: string,
+  keys: string[],
+): Promise<TryCatchResult<boolean>> {
+  const readResults = await readTextFile(filePath)
   if (!readResults.success) {
-    results.addMessage('debug', `Creating new .env file: ${filePath}`)
+    return failure<boolean>(`Unable to read env file: ${filePath}`, readResults, false)
   }
+  const doc = EnvDocument.parse(readResults.data || '')
+  keys.filter((key) => doc.has(key)).forEach((key) => doc.set(key, ''))
+  return await writeEnvDocument(filePath, doc)
+}
 
-  const envFileContent = readResults.success ? readResults.data || '' : ''
+/**
+ * Remove keys from a .env file
+ *
+ * @param filePath - The path to the .env file
+ * @param keys - The keys to remove
+ * @returns A TryCatchResult<boolean>, data is false if none of the keys were in the file
+ */
+export async function unsetEnv(
+  filePath: string,
+  keys: string[],
+): Promise<TryCatchResult<boolean>> {
+  const readResults = await readEnvDocument(filePath)
+  if (!readResults.success || !readResults.data) {
+    return failure<boolean>(`Unable to update env file: ${filePath}`, readResults, false)
+  }
+  const doc = readResults.data
+  const removed = keys.filter((key) => doc.unset(key))
+  if (!removed.length) {
+    return success<boolean>(false)
+  }
+  return await writeEnvDocument(filePath, doc)
+}
 
-  const updatedEnvFileContent = Object.entries(envVars).reduce((acc, [key, value]) => {
-    // Keep existing value in .env if envVars value not set
-    if (!value) return acc
+/**
+ * Merge a .env.example fragment into the contents of a .env file
+ *
+ * The whole fragment is appended if none of its keys are in the .env file. Otherwise only the
+ * missing keys are appended, each with the comment lines directly above it.
+ * Existing values are never changed.
+ *
+ * @param content - The .env file contents
+ * @param fragment - The .env.example fragment contents
+ * @param source - Name of the fragment, added as a comment above keys added to a section
+ * @returns The merged contents and the keys that were added
+ */
+export function mergeEnvFragment(
+  content: string,
+  fragment: string,
+  { source }: { source?: string } = {},
+): { content: string; added: string[] } {
+  const doc = EnvDocument.parse(content)
+  const nodes = EnvDocument.parse(fragment.trim()).nodes
+  const keys = nodes.map((node) => node.type === 'entry' ? node.key : null)
+  const added = keys.filter((key): key is string => !!key && !doc.has(key))
+  if (!added.length) {
+    return { content, added }
+  }
 
-    // Replace existing key value with new value
-    const regexp = new RegExp(`^\\s*${key}\\s*=.*`, 'gm')
-    const tmp = acc.replace(regexp, `${key}=${value}`)
+  // Separate the sections with blank lines
+  const separator = content ? '\n\n' : ''
+  if (added.length === keys.filter(Boolean).length) {
+    return { content: doc.append(`${separator}${fragment.trim()}`).toString(), added }
+  }
 
-    // If the key is not found in the .env file, add it to the end of the file
-    if (tmp === acc && !acc.includes(`${key}=${value}`)) {
-      results.addMessage('debug', `Key '${key}' not found in env file, adding to end of file`)
-      return `${acc}\n${key}=${value}\n`
+  const blocks: string[] = []
+  keys.forEach((key, i) => {
+    if (!key || !added.includes(key)) return
+    let start = i
+    while (start > 0 && nodes[start - 1].raw.trim().startsWith('#')) {
+      start--
     }
+    blocks.push(nodes.slice(start, i + 1).map((node) => node.raw).join('\n'))
+  })
+  const header = source ? `# Added from ${source}\n` : ''
+  return { content: doc.append(`${separator}${header}${blocks.join('\n')}`).toString(), added }
+}
 
-    return tmp
-  }, envFileContent)
+/**
+ * Merge .env.example fragments into a .env file
+ *
+ * See mergeEnvFragment. The file is only written if keys were added.
+ * @param filePath - The path to the .env file
+ * @param fragments - The .env.example fragments and their names
+ * @param dryRun - Get the keys that would be added without writing the file
+ * @returns A TryCatchResult with the keys that were added
+ */
+export async function mergeEnvFragments(
+  filePath: string,
+  fragments: { content: string; source?: string }[],
+  { dryRun = false }: { dryRun?: boolean } = {},
+): Promise<TryCatchResult<string[]>> {
+  const readResults = await readEnvDocument(filePath)
+  if (!readResults.success || !readResults.data) {
+    return failure<string[]>(`Unable to update env file: ${filePath}`, readResults)
+  }
+  let content = readResults.data.toString()
+  const added: string[] = []
+  const results = success<string[]>(added)
 
-  const writeResults = await tryCatchBoolean(Deno.writeTextFile(filePath, updatedEnvFileContent))
+  for (const { content: fragment, source } of fragments) {
+    const merged = mergeEnvFragment(content, fragment, { source })
+    if (merged.added.length) {
+      results.addMessage('debug', `Added ${source} env vars: ${merged.added.join(', ')}`)
+    }
+    content = merged.content
+    added.push(...merged.added)
+  }
 
+  if (!added.length || dryRun) {
+    return results
+  }
+  const writeResults = await writeEnvDocument(filePath, EnvDocument.parse(content))
   if (!writeResults.success) {
-    results.error = writeResults.error
-    results.addMessage('error', `Unable to save env file: ${filePath}`, {
-      error: writeResults.error,
-    })
+    return failure<string[]>(`Unable to update env file: ${filePath}`, writeResults, added)
   }
-
   return results
 }
 
-//
-// Helper functions for expanding env vars
-// Copied from mod.ts and parse.ts in 'jsr:@std/dotenv'
-//
-
-type LineParseResult = {
-  key: string
-  unquoted: string
-  interpolated: string
-  notInterpolated: string
+/**
+ * Parse the contents of a .env file, ${VAR} references are not expanded
+ */
+export function parseWithoutExpand(text: string): Record<string, string> {
+  return EnvDocument.parse(text).toObject()
 }
 
 interface LoadOptions {
-  envPath?: string | null
+  envPaths: (string | null)[]
+  expand?: boolean
   export?: boolean
 }
 
-type CharactersMap = { [key: string]: string }
-
-const RE_KEY_VALUE =
-  /^\s*(?:export\s+)?(?<key>[^\s=#]+?)\s*=[\ \t]*('\r?\n?(?<notInterpolated>(.|\r\n|\n)*?)\r?\n?'|"\r?\n?(?<interpolated>(.|\r\n|\n)*?)\r?\n?"|(?<unquoted>[^\r\n#]*)) *#*.*$/gm
-
-const RE_VALID_KEY = /^[a-zA-Z_][a-zA-Z0-9_]*$/
-
-async function loadWithoutExpand(
-  options: LoadOptions = {},
+/**
+ * Load .env files, values in later files override earlier files
+ *
+ * The files are parsed together so ${VAR} references are expanded with the overridden values.
+ * Missing files are skipped, same as load from 'jsr:@std/dotenv'.
+ */
+async function loadEnvFiles(
+  { envPaths, expand = true, export: _export = false }: LoadOptions,
 ): Promise<Record<string, string>> {
-  const {
-    envPath = '.env',
-    export: _export = false,
-  } = options
-  const conf = envPath ? await parseWithoutExpand(await Deno.readTextFile(envPath)) : {}
+  const texts: string[] = []
+  for (const envPath of envPaths.filter(Boolean) as string[]) {
+    try {
+      texts.push(await Deno.readTextFile(envPath))
+    } catch (error) {
+      if (!(error instanceof Deno.errors.NotFound)) throw error
+    }
+  }
+  const text = texts.join('\n')
+  const conf = expand ? parseDotEnv(text) : parseWithoutExpand(text)
   if (_export) {
     for (const [key, value] of Object.entries(conf)) {
       if (Deno.env.get(key) !== undefined) continue
@@ -163,40 +592,3 @@ async function loadWithoutExpand(
   }
   return conf
 }
-
-function expandCharacters(str: string): string {
-  const charactersMap: CharactersMap = {
-    '\\n': '\n',
-    '\\r': '\r',
-    '\\t': '\t',
-  }
-  return str.replace(
-    /\\([nrt])/g,
-    ($1: keyof CharactersMap): string => charactersMap[$1] ?? '',
-  )
-}
-
-export function parseWithoutExpand(text: string): Record<string, string> {
-  const env: Record<string, string> = {}
-  let match
-
-  while ((match = RE_KEY_VALUE.exec(text)) !== null) {
-    const { key, interpolated, notInterpolated, unquoted } = match
-      ?.groups as LineParseResult
-    if (!RE_VALID_KEY.test(key)) {
-      // TODO: replace with logger
-      console.warn(
-        `Ignored the key "${key}" as it is not a valid identifier: The key need to match the pattern /^[a-zA-Z_][a-zA-Z0-9_]*$/.`,
-      )
-      continue
-    }
-
-    env[key] = typeof notInterpolated === 'string'
-      ? notInterpolated
-      : typeof interpolated === 'string'
-      ? expandCharacters(interpolated)
-      : unquoted.trim()
-  }
-
-  return env
-}
@@ -0,0 +1,81 @@
+import { assert, assertEquals } from 'jsr:@std/assert'
+import {
+  createImagesLock,
+  getDigestReference,
+  getPinnedImages,
+  getRepoDigest,
+  normalizeImageName,
+  pinImage,
+  readImagesLock,
+  writeImagesLock,
+} from './images-lock.ts'
+
+const DIGEST = 'sha256:0123456789abcdef'
+
+Deno.test('normalizeImageName adds the default registry and removes tags', () => {
+  assertEquals(normalizeImageName('postgres'), 'docker.io/library/postgres')
+  assertEquals(normalizeImageName('postgres:16'), 'docker.io/library/postgres')
+  assertEquals(normalizeImageName('n8nio/n8n:latest'), 'docker.io/n8nio/n8n')
+  assertEquals(normalizeImageName(`n8nio/n8n@${DIGEST}`), 'docker.io/n8nio/n8n')
+  assertEquals(
+    normalizeImageName('ghcr.io/open-webui/open-webui:main'),
+    'ghcr.io/open-webui/open-webui',
+  )
+  assertEquals(normalizeImageName('localhost:5000/app:1.0'), 'localhost:5000/app')
+})
+
+Deno.test('getRepoDigest finds the digest for the image repository', () => {
+  const repoDigests = [`mirror.local/n8nio/n8n@sha256:other`, `docker.io/n8nio/n8n@${DIGEST}`]
+  assertEquals(getRepoDigest('n8nio/n8n:latest', repoDigests), DIGEST)
+  assertEquals(getRepoDigest('postgres:16', repoDigests), null)
+  assertEquals(getRepoDigest('postgres:16', []), null)
+})
+
+Deno.test('pinImage keeps the tag and skips pinned images', () => {
+  assertEquals(pinImage('n8nio/n8n:latest', DIGEST), `n8nio/n8n:latest@${DIGEST}`)
+  assertEquals(pinImage(`n8nio/n8n@${DIGEST}`, 'sha256:new'), `n8nio/n8n@${DIGEST}`)
+})
+
+Deno.test('getDigestReference removes the tag', () => {
+  assertEquals(getDigestReference('n8nio/n8n:latest', DIGEST), `n8nio/n8n@${DIGEST}`)
+  assertEquals(getDigestReference('localhost:5000/app', DIGEST), `localhost:5000/app@${DIGEST}`)
+})
+
+Deno.test('getPinnedImages maps image references to pinned images', () => {
+  const lock = createImagesLock()
+  lock.images['redis:7'] = { digest: DIGEST, services: ['redis'] }
+  assertEquals(getPinnedImages(lock), { 'redis:7': `redis:7@${DIGEST}` })
+  assertEquals(getPinnedImages(null), {})
+})
+
+Deno.test('readImagesLock round trips a written lock', async () => {
+  const dir = await Deno.makeTempDir()
+  try {
+    const file = `${dir}/images.lock`
+    assertEquals((await readImagesLock(file)).data, null)
+
+    const lock = createImagesLock()
+    lock.images['redis:7'] = { digest: DIGEST, services: ['redis', 'litellm'] }
+    lock.images['postgres:16'] = { digest: DIGEST, services: ['postgres'] }
+    assert((await writeImagesLock(file, lock)).success)
+
+    const result = await readImagesLock(file)
+    assert(result.success)
+    assertEquals(Object.keys(result.data!.images), ['postgres:16', 'redis:7'])
+    assertEquals(result.data!.images['redis:7'].services, ['litellm', 'redis'])
+    assert(result.data!.updated)
+  } finally {
+    await Deno.remove(dir, { recursive: true })
+  }
+})
+
+Deno.test('readImagesLock fails for an unsupported version', async () => {
+  const dir = await Deno.makeTempDir()
+  try {
+    const file = `${dir}/images.lock`
+    await Deno.writeTextFile(file, JSON.stringify({ version: 99, images: {} }))
+    assertEquals((await readImagesLock(file)).success, false)
+  } finally {
+    await Deno.remove(dir, { recursive: true })
+  }
+})
@@ -0,0 +1,153 @@
+/**
+ * Image digest lockfile
+ *
+ * Records the immutable digest of each image used by the stack so everyone on a project runs
+ * the same images. The lockfile is JSON and keyed by the image reference from the resolved
+ * compose files, e.g. `n8nio/n8n:latest`.
+ *
+ * Images are pinned by setting the image to `<image>@<digest>` in the compose overrides,
+ * see getComposeOverrideYaml.
+ */
+
+import { fileExists, readJson, saveJson } from '@/lib/fs.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+
+const IMAGES_LOCK_VERSION = 1
+
+export interface IImageLock {
+  digest: string // sha256:...
+  services: string[] // LLemonStack services that use the image
+}
+
+export interface IImagesLock {
+  version: number
+  updated: string // ISO 8601 timestamp
+  images: Record<string, IImageLock>
+}
+
+/**
+ * Create an empty lock
+ */
+export function createImagesLock(): IImagesLock {
+  return { version: IMAGES_LOCK_VERSION, updated: '', images: {} }
+}
+
+/**
+ * Remove the tag and digest from an image reference, registry ports are kept
+ *
+ * @example
+ * removeImageTag('localhost:5000/app:1.0') // 'localhost:5000/app'
+ */
+export function removeImageTag(image: string): string {
+  const name = image.split('@')[0]
+  // Registry ports are before the last slash
+  const tagIndex = name.lastIndexOf(':')
+  return tagIndex > name.lastIndexOf('/') ? name.slice(0, tagIndex) : name
+}
+
+/**
+ * Normalize an image reference to its fully qualified repository name without tag or digest
+ *
+ * @example
+ * normalizeImageName('postgres:16') // 'docker.io/library/postgres'
+ * normalizeImageName('ghcr.io/open-webui/open-webui:main') // 'ghcr.io/open-webui/open-webui'
+ */
+export function normalizeImageName(image: string): string {
+  const name = removeImageTag(image)
+  const [first, ...rest] = name.split('/')
+  if (!rest.length) {
+    return `docker.io/library/${first}`
+  }
+  if (!/[.:]/.test(first) && first !== 'localhost') {
+    return `docker.io/${name}`
+  }
+  return name
+}
+
+/**
+ * Get the digest of an image from the RepoDigests of an image inspect
+ *
+ * @param image - The image reference
+ * @param repoDigests - RepoDigests from the image inspect, e.g. ['n8nio/n8n@sha256:...']
+ * @returns The digest or null if the image was not pulled from a registry
+ */
+export function getRepoDigest(image: string, repoDigests: string[]): string | null {
+  const name = normalizeImageName(image)
+  const repoDigest = repoDigests.find((digest) => normalizeImageName(digest) === name)
+  return repoDigest?.split('@')[1] || null
+}
+
+/**
+ * Pin an image reference to a digest, the tag is kept for readability
+ *
+ * @example
+ * pinImage('n8nio/n8n:latest', 'sha256:abc') // 'n8nio/n8n:latest@sha256:abc'
+ */
+export function pinImage(image: string, digest: string): string {
+  return image.includes('@') ? image : `${image}@${digest}`
+}
+
+/**
+ * Get the reference to pull an image by digest, the tag is removed
+ *
+ * @example
+ * getDigestReference('n8nio/n8n:latest', 'sha256:abc') // 'n8nio/n8n@sha256:abc'
+ */
+export function getDigestReference(image: string, digest: string): string {
+  return `${removeImageTag(image)}@${digest}`
+}
+
+/**
+ * Get the pinned image references for all locked images
+ *
+ * @returns Map of image reference to pinned image reference
+ */
+export function getPinnedImages(lock: IImagesLock | null): Record<string, string> {
+  return Object.fromEntries(
+    Object.entries(lock?.images || {}).map(([image, { digest }]) => [
+      image,
+      pinImage(image, digest),
+    ]),
+  )
+}
+
+/**
+ * Read the lockfile
+ *
+ * @returns The lock or null if the lockfile doesn't exist
+ */
+export async function readImagesLock(file: string): Promise<TryCatchResult<IImagesLock | null>> {
+  if (!(await fileExists(file)).data) {
+    return success<IImagesLock | null>(null)
+  }
+  const result = await readJson<IImagesLock>(file)
+  if (!result.success || !result.data) {
+    return failure<IImagesLock | null>(`Unable to read images lock: ${file}`, result, null)
+  }
+  if (result.data.version !== IMAGES_LOCK_VERSION) {
+    return failure<IImagesLock | null>(
+      `Unsupported images lock version ${result.data.version}: ${file}`,
+      success(null),
+    )
+  }
+  return success<IImagesLock | null>({ ...createImagesLock(), ...result.data })
+}
+
+/**
+ * Write the lockfile, images are sorted to keep diffs small
+ */
+export async function writeImagesLock(
+  file: string,
+  lock: IImagesLock,
+): Promise<TryCatchResult<boolean>> {
+  const images = Object.fromEntries(
+    Object.entries(lock.images)
+      .sort(([a], [b]) => a.localeCompare(b))
+      .map(([image, entry]) => [image, { ...entry, services: [...entry.services].sort() }]),
+  )
+  return await saveJson(file, {
+    version: IMAGES_LOCK_VERSION,
+    updated: new Date().toISOString(),
+    images,
+  })
+}
@@ -0,0 +1,75 @@
+import { assertEquals } from 'jsr:@std/assert'
+import { getPortsFromComposeYaml } from './compose.ts'
+import { getHostPort, getHostPortEnvVar, isHostPortAvailable, offsetHostUrl } from './ports.ts'
+
+Deno.test('getHostPort uses the remapped port before the base port offset', () => {
+  const env = { LLEMONSTACK_HOST_PORT_N8N_5678: '5700' }
+  assertEquals(getHostPort(5678, 0), 5678)
+  assertEquals(getHostPort(5678, 10000), 15678)
+  assertEquals(getHostPort(5678, 10000, env, 'n8n'), 5700)
+  assertEquals(getHostPort(5678, 0, { LLEMONSTACK_HOST_PORT_N8N_5678: '' }, 'n8n'), 5678)
+})
+
+Deno.test('getHostPort remaps the same port of each service separately', () => {
+  const env = { [getHostPortEnvVar('browser-use', 8080)]: '8081' }
+  assertEquals(getHostPortEnvVar('browser-use', 8080), 'LLEMONSTACK_HOST_PORT_BROWSER_USE_8080')
+  assertEquals(getHostPort(8080, 0, env, 'browser-use'), 8081)
+  assertEquals(getHostPort(8080, 0, env, 'openwebui'), 8080)
+})
+
+Deno.test('offsetHostUrl updates localhost urls with remapped ports', () => {
+  const env = { LLEMONSTACK_HOST_PORT_LANGFUSE_3001: '3002' }
+  assertEquals(
+    offsetHostUrl('http://localhost:3001/home', 0, env, 'langfuse'),
+    'http://localhost:3002/home',
+  )
+  assertEquals(offsetHostUrl('http://localhost:5678', 10000, env, 'n8n'), 'http://localhost:15678')
+  assertEquals(offsetHostUrl('http://n8n:5678', 10000, env, 'n8n'), 'http://n8n:5678')
+})
+
+Deno.test({
+  name: 'isHostPortAvailable probes udp ports with a datagram socket',
+  // deno task test doesn't allow net access
+  ignore: Deno.permissions.querySync({ name: 'net' }).state !== 'granted',
+}, () => {
+  const socket = Deno.listenDatagram({ port: 0, hostname: '127.0.0.1', transport: 'udp' })
+  const { port } = socket.addr as Deno.NetAddr
+  try {
+    assertEquals(isHostPortAvailable(port, '127.0.0.1', 'udp'), false)
+  } finally {
+    socket.close()
+  }
+  assertEquals(isHostPortAvailable(port, '127.0.0.1', 'udp'), true)
+})
+
+Deno.test('getPortsFromComposeYaml returns the published ports of active profiles', async () => {
+  const dir = await Deno.makeTempDir()
+  try {
+    const composeFile = `${dir}/docker-compose.yaml`
+    await Deno.writeTextFile(
+      composeFile,
+      [
+        'services:',
+        '  app:',
+        '    ports:',
+        '      - "127.0.0.1:${APP_PORT:-8080}:80"',
+        '      - "9000-9001:9000-9001"',
+        '      - target: 53',
+        '        published: "5353"',
+        '        protocol: udp',
+        '  gpu:',
+        '    profiles: [gpu]',
+        '    ports:',
+        '      - 11434:11434',
+      ].join('\n'),
+    )
+    assertEquals(await getPortsFromComposeYaml(composeFile, { env: { APP_PORT: '8081' } }), [
+      { service: 'app', published: 8081, target: 80, protocol: 'tcp' },
+      { service: 'app', published: 5353, target: 53, protocol: 'udp' },
+    ])
+    const gpuPorts = await getPortsFromComposeYaml(composeFile, { profiles: ['gpu'] })
+    assertEquals(gpuPorts.map((port) => port.published), [8080, 11434])
+  } finally {
+    await Deno.remove(dir, { recursive: true })
+  }
+})
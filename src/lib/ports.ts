@@ -0,0 +1,212 @@
+import { tryDocker } from '@/lib/docker.ts'
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+
+// Distance between project base ports
+// The default host ports used by services are all below 11500, a step of 10000 keeps the
+// port ranges of projects from overlapping while allowing up to 6 projects: 0 - 50000
+export const BASE_PORT_STEP = 10_000
+export const MAX_BASE_PORT = 50_000
+export const MAX_HOST_PORT = 65_535
+
+// Env var prefix to remap a host port of a service, e.g. LLEMONSTACK_HOST_PORT_N8N_5678=5679
+// The value is the final host port, the base port is not added to it
+export const HOST_PORT_ENV_PREFIX = 'LLEMONSTACK_HOST_PORT_'
+
+// Label added to containers with the project base port, used to find the ports used by other
+// projects when initializing a new project
+export const BASE_PORT_LABEL = 'dev.llemonstack.base_port'
+
+/**
+ * Offset a host port by the project base port
+ *
+ * @param port - The default host port from the compose file
+ * @param basePort - The project base port, 0 keeps the default port
+ * @returns The host port for the project
+ */
+export function offsetHostPort(port: number, basePort: number): number {
+  const hostPort = port + basePort
+  if (hostPort > MAX_HOST_PORT) {
+    throw new RangeError(
+      `Host port ${port} with base port ${basePort} is greater than ${MAX_HOST_PORT}`,
+    )
+  }
+  return hostPort
+}
+
+/**
+ * Get the env var used to remap a host port of a service
+ *
+ * Services can publish the same default port, so each service is remapped separately.
+ *
+ * @example
+ * getHostPortEnvVar('n8n', 5678) // 'LLEMONSTACK_HOST_PORT_N8N_5678'
+ *
+ * @param service - The LLemonStack service that publishes the port, e.g. n8n
+ * @param port - The default host port from the compose file
+ */
+export function getHostPortEnvVar(service: string, port: number): string {
+  return `${HOST_PORT_ENV_PREFIX}${service.toUpperCase().replace(/[^A-Z0-9]/g, '_')}_${port}`
+}
+
+/**
+ * Get the host port for the project, remapped by env var or offset by the base port
+ *
+ * @example
+ * getHostPort(5678, 10000) // 15678
+ * getHostPort(5678, 10000, { LLEMONSTACK_HOST_PORT_N8N_5678: '5700' }, 'n8n') // 5700
+ *
+ * @param port - The default host port from the compose file
+ * @param basePort - The project base port
+ * @param env - Env vars with the remapped ports, usually Config.env
+ * @param service - The LLemonStack service that publishes the port, e.g. n8n
+ * @returns The host port for the project
+ */
+export function getHostPort(
+  port: number,
+  basePort: number,
+  env: Record<string, string | undefined> = {},
+  service?: string,
+): number {
+  const remapped = service ? env[getHostPortEnvVar(service, port)] : undefined
+  if (remapped && /^\d+$/.test(remapped)) {
+    return Number(remapped)
+  }
+  return offsetHostPort(port, basePort)
+}
+
+/**
+ * Offset the port of a localhost url by the project base port
+ *
+ * @example
+ * offsetHostUrl('http://localhost:5678', 10000) // 'http://localhost:15678'
+ *
+ * @param url - The url from the exposes.host section of a service llemonstack.yaml
+ * @param basePort - The project base port
+ * @param env - Env vars with the remapped ports, see getHostPort
+ * @param service - The LLemonStack service that publishes the port
+ * @returns The url with the port offset, urls for other hosts are returned unchanged
+ */
+export function offsetHostUrl(
+  url: string,
+  basePort: number,
+  env: Record<string, string | undefined> = {},
+  service?: string,
+): string {
+  if (!url) {
+    return url
+  }
+  return url.replace(
+    /^((?:[a-z]+:\/\/)?(?:[^@/]+@)?(?:localhost|127\.0\.0\.1)):(\d+)/i,
+    (_match, host: string, port: string) =>
+      `${host}:${getHostPort(Number(port), basePort, env, service)}`,
+  )
+}
+
+/**
+ * Get the base ports used by other LLemonStack projects
+ *
+ * Checks the base port label of all containers, including stopped containers.
+ *
+ * @param excludeProject - Project name to exclude, usually the current project
+ * @returns Map of base port to project names
+ */
+export async function getUsedBasePorts(
+  excludeProject?: string,
+): Promise<TryCatchResult<Map<number, string[]>>> {
+  const psResult = await tryDocker('ps', {
+    args: [
+      '-a',
+      '--filter',
+      `label=${BASE_PORT_LABEL}`,
+      '--format',
+      `{{.Label "${BASE_PORT_LABEL}"}} {{.Label "com.docker.compose.project"}}`,
+    ],
+    captureOutput: true,
+    silent: true,
+  })
+  if (!psResult.success || !psResult.data) {
+    return failure<Map<number, string[]>>('Failed to get base ports of other projects', psResult)
+  }
+
+  const used = new Map<number, string[]>()
+  psResult.data.toString().split('\n').forEach((line) => {
+    const [port, project] = line.trim().split(/\s+/)
+    if (!port || !project || project === excludeProject) {
+      return
+    }
+    const projects = used.get(Number(port)) || []
+    !projects.includes(project) && projects.push(project)
+    used.set(Number(port), projects)
+  })
+  return success<Map<number, string[]>>(used)
+}
+
+/**
+ * Get the lowest base port not used by another project
+ *
+ * @param excludeProject - Project name to exclude, usually the current project
+ * @param reserved - Base ports reserved by other projects, e.g. from the project registry
+ * @returns The base port or null if all base ports are in use
+ */
+export async function getAvailableBasePort(
+  excludeProject?: string,
+  { reserved = [] }: { reserved?: number[] } = {},
+): Promise<TryCatchResult<number | null>> {
+  const usedResult = await getUsedBasePorts(excludeProject)
+  if (!usedResult.success || !usedResult.data) {
+    return failure<number | null>('Unable to find an available base port', usedResult, null)
+  }
+  for (let port = 0; port <= MAX_BASE_PORT; port += BASE_PORT_STEP) {
+    if (!usedResult.data.has(port) && !reserved.includes(port)) {
+      return success<number | null>(port)
+    }
+  }
+  return success<number | null>(null)
+}
+
+/**
+ * Check if a host port is free by briefly listening on it
+ *
+ * Ports that can't be probed, e.g. privileged ports without permission, are reported as available.
+ *
+ * @param port - The host port to check
+ * @param hostname - The interface docker publishes ports on
+ * @param protocol - The protocol of the published port, udp ports are probed with a datagram socket
+ * @returns True if nothing is listening on the port
+ */
+export function isHostPortAvailable(
+  port: number,
+  hostname: string = '0.0.0.0',
+  protocol: string = 'tcp',
+): boolean {
+  try {
+    const listener = protocol === 'udp'
+      ? Deno.listenDatagram({ port, hostname, transport: 'udp' })
+      : Deno.listen({ port, hostname, transport: 'tcp' })
+    listener.close()
+    return true
+  } catch (error) {
+    return !(error instanceof Deno.errors.AddrInUse)
+  }
+}
+
+/**
+ * Find the next available host port after a port
+ *
+ * @param port - The port to start searching after
+ * @param exclude - Ports reserved by other services in the stack
+ * @param protocol - The protocol of the published port, tcp or udp
+ * @returns The port or null if no port is available
+ */
+export function findAvailableHostPort(
+  port: number,
+  exclude: Set<number> = new Set(),
+  protocol: string = 'tcp',
+): number | null {
+  for (let candidate = port + 1; candidate <= MAX_HOST_PORT; candidate++) {
+    if (!exclude.has(candidate) && isHostPortAvailable(candidate, '0.0.0.0', protocol)) {
+      return candidate
+    }
+  }
+  return null
+}
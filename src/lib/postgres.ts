@@ -233,6 +233,36 @@ export async function createServiceSchema(
   }
 }
 
+/**
+ * Sets a new password for a service user that was created by createServiceSchema
+ * @param username Name of the postgres user
+ * @param password The new password
+ * @param pgConfig PostgreSQL connection config for admin access
+ */
+export async function setServiceSchemaPassword(
+  username: string,
+  password: string,
+  pgConfig: ConnectionConfig,
+): Promise<void> {
+  const client = new Client(getConnectionConfig(pgConfig))
+  try {
+    await client.connect()
+    const userExists = await client.queryArray(
+      `SELECT 1 FROM pg_roles WHERE rolname = '${username.replace(/'/g, "''")}'`,
+    )
+    if (userExists.rows.length === 0) {
+      throw new Error(`Postgres user not found: ${username}`)
+    }
+    await client.queryArray(
+      `ALTER ROLE "${username.replace(/"/g, '""')}" WITH LOGIN PASSWORD '${
+        password.replace(/'/g, "''")
+      }'`,
+    )
+  } finally {
+    await client.end()
+  }
+}
+
 /**
  * Removes a PostgreSQL schema and user that were created by createServiceSchema
  * @param serviceName Name of the service whose schema should be removed
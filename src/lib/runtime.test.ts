@@ -0,0 +1,44 @@
+import { assertEquals } from 'jsr:@std/assert'
+import { getComposeCommand } from './docker.ts'
+import { DEFAULT_CONTAINER_RUNTIME, getRuntimeFeatures } from './runtime.ts'
+
+Deno.test('getRuntimeFeatures limits podman-compose features by version', () => {
+  assertEquals(getRuntimeFeatures('podman', 'podman-compose', '1.0.3'), {
+    profiles: false,
+    ansi: false,
+    configJson: false,
+    overrideTags: false,
+    pullPolicy: false,
+  })
+  assertEquals(getRuntimeFeatures('podman', 'podman-compose', '1.2.0').overrideTags, true)
+  assertEquals(
+    getRuntimeFeatures('podman', 'docker-compose', '2.30.0'),
+    DEFAULT_CONTAINER_RUNTIME.features,
+  )
+})
+
+Deno.test('getComposeCommand uses COMPOSE_PROFILES when --profile is not supported', () => {
+  const podman = {
+    ...DEFAULT_CONTAINER_RUNTIME,
+    name: 'podman' as const,
+    command: 'podman',
+    compose: ['podman-compose'],
+    features: getRuntimeFeatures('podman', 'podman-compose', '1.0.3'),
+  }
+  assertEquals(
+    getComposeCommand(podman, { projectName: 'demo', profiles: ['gpu-nvidia'], ansi: 'never' }),
+    { cmd: 'podman-compose', args: ['-p', 'demo'], env: { COMPOSE_PROFILES: 'gpu-nvidia' } },
+  )
+  assertEquals(
+    getComposeCommand(DEFAULT_CONTAINER_RUNTIME, {
+      projectName: 'demo',
+      composeFiles: ['a.yaml', null],
+      profiles: ['gpu-nvidia'],
+    }),
+    {
+      cmd: 'docker',
+      args: ['compose', '-p', 'demo', '-f', 'a.yaml', '--profile', 'gpu-nvidia'],
+      env: {},
+    },
+  )
+})
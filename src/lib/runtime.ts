@@ -0,0 +1,287 @@
+/**
+ * Container runtime detection
+ *
+ * LLemonStack uses Docker by default. Podman (with `podman compose` or `podman-compose`) and
+ * nerdctl are also supported. The runtime is chosen from the LLEMONSTACK_CONTAINER_RUNTIME env
+ * var, the `runtime` key in config.json, or detected from the installed commands.
+ */
+
+import { failure, success, TryCatchResult } from '@/lib/try-catch.ts'
+import { isVersionAtLeast } from '@/lib/utils/compare.ts'
+import { tryRunCommand } from './command.ts'
+
+export const CONTAINER_RUNTIMES = ['docker', 'podman', 'nerdctl'] as const
+export type ContainerRuntimeName = typeof CONTAINER_RUNTIMES[number]
+
+export const CONTAINER_RUNTIME_ENV = 'LLEMONSTACK_CONTAINER_RUNTIME'
+export const HOST_GATEWAY_ENV = 'LLEMONSTACK_HOST_GATEWAY'
+
+export interface IContainerRuntimeFeatures {
+  profiles: boolean // Compose supports --profile, otherwise COMPOSE_PROFILES is used
+  ansi: boolean // Compose supports --ansi
+  configJson: boolean // Compose supports `config --format json`
+  overrideTags: boolean // Compose supports the !override yaml tag in override files
+  pullPolicy: boolean // Compose up supports --pull never, used by offline projects
+}
+
+export interface IContainerRuntime {
+  name: ContainerRuntimeName
+  command: string // Runtime CLI, e.g. docker
+  compose: string[] // Compose CLI, e.g. ['docker', 'compose'] or ['podman-compose']
+  version: string
+  composeVersion: string
+  socketPath: string | null // Docker compatible API socket, null if not available
+  hostGateway: string // extra_hosts value for host.docker.internal
+  features: IContainerRuntimeFeatures
+}
+
+// Used when no runtime is detected so commands fail with the usual docker not found errors
+export const DEFAULT_CONTAINER_RUNTIME: IContainerRuntime = {
+  name: 'docker',
+  command: 'docker',
+  compose: ['docker', 'compose'],
+  version: '',
+  composeVersion: '',
+  socketPath: null,
+  hostGateway: 'host-gateway',
+  features: { profiles: true, ansi: true, configJson: true, overrideTags: true, pullPolicy: true },
+}
+
+// Minimum versions for runtime features
+const PODMAN_HOST_GATEWAY_VERSION = '5.3.0'
+const PODMAN_COMPOSE_PROFILES_VERSION = '1.0.6'
+const PODMAN_COMPOSE_OVERRIDE_TAGS_VERSION = '1.2.0'
+const NERDCTL_COMPOSE_V2_VERSION = '2.0.0'
+
+let _runtime: Promise<TryCatchResult<IContainerRuntime>> | null = null
+
+function parseVersion(output: string): string {
+  return output.match(/(\d+\.\d+(\.\d+)?)/)?.[1] || ''
+}
+
+async function getCommandOutput(cmd: string, args: string[]): Promise<string | null> {
+  const result = await tryRunCommand(cmd, {
+    args,
+    captureOutput: true,
+    silent: true,
+    autoLoadEnv: false,
+  })
+  return result.success && result.data ? `${result.data.stdout}\n${result.data.stderr}` : null
+}
+
+/**
+ * Get the IP address containers can use to reach the host
+ *
+ * Used for runtimes that don't support the host-gateway extra_hosts value.
+ * Services on the host must listen on all interfaces, e.g. OLLAMA_HOST=0.0.0.0
+ */
+export function getHostIp(): string {
+  try {
+    const address = Deno.networkInterfaces().find((iface) =>
+      iface.family === 'IPv4' && !iface.address.startsWith('127.') &&
+      !/^(docker|podman|cni|br-|veth|nerdctl)/.test(iface.name)
+    )
+    return address?.address || 'host-gateway'
+  } catch (_error) {
+    return 'host-gateway'
+  }
+}
+
+/**
+ * Get the Docker compatible API socket for a runtime
+ */
+export function getRuntimeSocketPath(name: ContainerRuntimeName): string | null {
+  const dockerHost = Deno.env.get('DOCKER_HOST')
+  if (dockerHost) {
+    return dockerHost.startsWith('unix://') ? dockerHost.slice('unix://'.length) : null
+  }
+  const home = Deno.env.get('HOME') || ''
+  const runtimeDir = Deno.env.get('XDG_RUNTIME_DIR') || ''
+  const candidates = name === 'docker'
+    ? ['/var/run/docker.sock', `${home}/.docker/run/docker.sock`]
+    : name === 'podman'
+    ? [runtimeDir && `${runtimeDir}/podman/podman.sock`, '/run/podman/podman.sock']
+    : [] // nerdctl doesn't provide a Docker compatible API
+  for (const socketPath of candidates.filter(Boolean)) {
+    try {
+      Deno.statSync(socketPath)
+      return socketPath
+    } catch (_error) {
+      // Try the next path
+    }
+  }
+  return null
+}
+
+/**
+ * Get the compose features supported by a runtime
+ *
+ * @param name - The runtime name
+ * @param composeProvider - The compose command, podman compose can wrap docker-compose
+ * @param composeVersion - The compose version
+ */
+export function getRuntimeFeatures(
+  name: ContainerRuntimeName,
+  composeProvider: 'docker-compose' | 'podman-compose' | 'nerdctl',
+  composeVersion: string,
+): IContainerRuntimeFeatures {
+  if (composeProvider === 'docker-compose') {
+    return { ...DEFAULT_CONTAINER_RUNTIME.features }
+  }
+  if (name === 'podman') {
+    return {
+      profiles: isVersionAtLeast(composeVersion, PODMAN_COMPOSE_PROFILES_VERSION),
+      ansi: false,
+      configJson: false,
+      overrideTags: isVersionAtLeast(composeVersion, PODMAN_COMPOSE_OVERRIDE_TAGS_VERSION),
+      pullPolicy: false,
+    }
+  }
+  return {
+    profiles: true,
+    ansi: false,
+    configJson: false,
+    overrideTags: isVersionAtLeast(composeVersion, NERDCTL_COMPOSE_V2_VERSION),
+    pullPolicy: true,
+  }
+}
+
+async function detectDocker(command = 'docker'): Promise<IContainerRuntime | null> {
+  const output = await getCommandOutput(command, ['--version'])
+  if (output === null) {
+    return null
+  }
+  // docker is sometimes an alias for podman
+  if (/podman/i.test(output)) {
+    return await detectPodman(command)
+  }
+  const composeOutput = await getCommandOutput(command, ['compose', 'version'])
+  if (composeOutput === null) {
+    return null
+  }
+  return {
+    ...DEFAULT_CONTAINER_RUNTIME,
+    command,
+    compose: [command, 'compose'],
+    version: parseVersion(output),
+    composeVersion: parseVersion(composeOutput),
+    socketPath: getRuntimeSocketPath('docker'),
+  }
+}
+
+async function detectPodman(command = 'podman'): Promise<IContainerRuntime | null> {
+  const output = await getCommandOutput(command, ['--version'])
+  if (output === null) {
+    return null
+  }
+  const version = parseVersion(output)
+
+  // podman compose wraps docker-compose or podman-compose, prefer it when available
+  let compose = [command, 'compose']
+  let composeOutput = await getCommandOutput(command, ['compose', 'version'])
+  let provider: 'docker-compose' | 'podman-compose' = /docker compose/i.test(composeOutput || '')
+    ? 'docker-compose'
+    : 'podman-compose'
+  if (composeOutput === null) {
+    compose = ['podman-compose']
+    composeOutput = await getCommandOutput('podman-compose', ['--version'])
+    provider = 'podman-compose'
+  }
+  if (composeOutput === null) {
+    return null
+  }
+  // podman-compose --version also prints the podman version, use the podman-compose line
+  const composeVersion = parseVersion(
+    composeOutput.split('\n').find((line) => /compose.*\d+\.\d+/i.test(line)) || composeOutput,
+  )
+
+  return {
+    name: 'podman',
+    command,
+    compose,
+    version,
+    composeVersion,
+    socketPath: getRuntimeSocketPath('podman'),
+    hostGateway: isVersionAtLeast(version, PODMAN_HOST_GATEWAY_VERSION)
+      ? 'host-gateway'
+      : getHostIp(),
+    features: getRuntimeFeatures('podman', provider, composeVersion),
+  }
+}
+
+async function detectNerdctl(command = 'nerdctl'): Promise<IContainerRuntime | null> {
+  const output = await getCommandOutput(command, ['--version'])
+  if (output === null) {
+    return null
+  }
+  const version = parseVersion(output)
+  const composeOutput = await getCommandOutput(command, ['compose', 'version'])
+  const composeVersion = parseVersion(composeOutput || '') || version
+  return {
+    name: 'nerdctl',
+    command,
+    compose: [command, 'compose'],
+    version,
+    composeVersion,
+    socketPath: null,
+    hostGateway: isVersionAtLeast(version, NERDCTL_COMPOSE_V2_VERSION)
+      ? 'host-gateway'
+      : getHostIp(),
+    features: getRuntimeFeatures('nerdctl', 'nerdctl', composeVersion),
+  }
+}
+
+/**
+ * Detect the container runtime
+ *
+ * @param preferred - Runtime to use, or auto to use the first installed runtime
+ * @returns {Promise<TryCatchResult<IContainerRuntime>>} Failure if the runtime is not installed
+ */
+export async function detectContainerRuntime(
+  preferred: ContainerRuntimeName | 'auto' = 'auto',
+): Promise<TryCatchResult<IContainerRuntime>> {
+  const detectors: Record<ContainerRuntimeName, () => Promise<IContainerRuntime | null>> = {
+    docker: () => detectDocker(),
+    podman: () => detectPodman(),
+    nerdctl: () => detectNerdctl(),
+  }
+  const names = preferred === 'auto' ? CONTAINER_RUNTIMES : [preferred]
+  for (const name of names) {
+    const runtime = await detectors[name]()
+    if (runtime) {
+      const hostGateway = Deno.env.get(HOST_GATEWAY_ENV)
+      return success<IContainerRuntime>(hostGateway ? { ...runtime, hostGateway } : runtime)
+    }
+  }
+  return failure<IContainerRuntime>(
+    preferred === 'auto'
+      ? `No container runtime found, install one of: ${CONTAINER_RUNTIMES.join(', ')}`
+      : `Container runtime not found: ${preferred} and a compose command`,
+    { data: null, error: new Error('Container runtime not found'), success: false },
+  )
+}
+
+/**
+ * Get the container runtime, falls back to docker if no runtime is found
+ *
+ * @param preferred - Runtime to use, see Config.containerRuntime
+ */
+export async function getContainerRuntime(
+  preferred: ContainerRuntimeName | 'auto' = 'auto',
+): Promise<IContainerRuntime> {
+  return (await tryGetContainerRuntime(preferred)).data || DEFAULT_CONTAINER_RUNTIME
+}
+
+/**
+ * Get the container runtime detection result, detected once per process
+ *
+ * @param preferred - Runtime to use, see Config.containerRuntime
+ */
+export async function tryGetContainerRuntime(
+  preferred: ContainerRuntimeName | 'auto' = 'auto',
+): Promise<TryCatchResult<IContainerRuntime>> {
+  if (!_runtime) {
+    _runtime = detectContainerRuntime(preferred)
+  }
+  return await _runtime
+}
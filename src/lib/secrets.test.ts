@@ -0,0 +1,36 @@
+import { assert, assertEquals, assertNotEquals, assertRejects } from 'jsr:@std/assert'
+import { decryptSecrets, encryptSecrets, readSecretsFile, writeSecretsFile } from './secrets.ts'
+
+const SECRETS = { FLOWISE_PASSWORD: 'p@ss word', N8N_ENCRYPTION_KEY: 'abc123=' }
+
+Deno.test('encryptSecrets round trips with the same passphrase', async () => {
+  const encrypted = await encryptSecrets(SECRETS, 'correct horse')
+  assert(!encrypted.data.includes('abc123'))
+  assertEquals(await decryptSecrets(encrypted, 'correct horse'), SECRETS)
+})
+
+Deno.test('encryptSecrets uses a new salt and iv each time', async () => {
+  const a = await encryptSecrets(SECRETS, 'correct horse')
+  const b = await encryptSecrets(SECRETS, 'correct horse')
+  assertNotEquals(a.kdf.salt, b.kdf.salt)
+  assertNotEquals(a.iv, b.iv)
+})
+
+Deno.test('decryptSecrets rejects a wrong passphrase', async () => {
+  const encrypted = await encryptSecrets(SECRETS, 'correct horse')
+  await assertRejects(() => decryptSecrets(encrypted, 'wrong horse'))
+})
+
+Deno.test('readSecretsFile returns empty secrets for a missing file', async () => {
+  const dir = await Deno.makeTempDir()
+  try {
+    const file = `${dir}/secrets.enc`
+    assertEquals((await readSecretsFile(file, 'pass')).data, {})
+
+    assert((await writeSecretsFile(file, SECRETS, 'pass')).success)
+    assertEquals((await readSecretsFile(file, 'pass')).data, SECRETS)
+    assert(!(await readSecretsFile(file, 'wrong')).success)
+  } finally {
+    await Deno.remove(dir, { recursive: true })
+  }
+})
@@ -0,0 +1,213 @@
+/**
+ * Encrypted secrets store
+ *
+ * Secrets are stored in a JSON file encrypted with AES-256-GCM. The encryption key is derived
+ * from a passphrase with PBKDF2. The passphrase is read from the LLEMONSTACK_SECRETS_PASSPHRASE
+ * env var, a key file outside of the project dir, or prompted for in interactive shells.
+ *
+ * Decrypted secrets are only kept in memory and passed to docker commands as process env vars.
+ */
+
+import { ensureDir, fileExists, path } from '@/lib/fs.ts'
+import { failure, success, tryCatch, TryCatchResult } from '@/lib/try-catch.ts'
+import { Secret } from '@cliffy/prompt'
+import { decodeBase64, encodeBase64 } from 'jsr:@std/encoding/base64'
+
+export const SECRETS_PASSPHRASE_ENV = 'LLEMONSTACK_SECRETS_PASSPHRASE'
+export const SECRETS_KEY_FILE_ENV = 'LLEMONSTACK_SECRETS_KEY_FILE'
+
+const SECRETS_FILE_VERSION = 1
+const PBKDF2_ITERATIONS = 600_000
+
+// Env var names that usually contain secrets, used to pick which .env values to encrypt
+export const SECRET_KEY_PATTERN = /(PASSWORD|PASS|SECRET|TOKEN|SALT|_KEY|API_KEY)$/
+
+/**
+ * Mask a secret value for display
+ */
+export function maskSecret(value: string): string {
+  return value.length <= 4 ? '****' : `${value.slice(0, 2)}${'*'.repeat(8)}`
+}
+
+interface ISecretsFile {
+  version: number
+  kdf: { name: 'PBKDF2'; hash: 'SHA-256'; iterations: number; salt: string }
+  cipher: 'AES-GCM'
+  iv: string
+  data: string
+}
+
+async function deriveKey(
+  passphrase: string,
+  salt: Uint8Array,
+  iterations: number,
+): Promise<CryptoKey> {
+  const baseKey = await crypto.subtle.importKey(
+    'raw',
+    new TextEncoder().encode(passphrase),
+    'PBKDF2',
+    false,
+    ['deriveKey'],
+  )
+  return await crypto.subtle.deriveKey(
+    { name: 'PBKDF2', hash: 'SHA-256', salt, iterations },
+    baseKey,
+    { name: 'AES-GCM', length: 256 },
+    false,
+    ['encrypt', 'decrypt'],
+  )
+}
+
+/**
+ * Encrypt secrets with a passphrase
+ */
+export async function encryptSecrets(
+  secrets: Record<string, string>,
+  passphrase: string,
+): Promise<ISecretsFile> {
+  const salt = crypto.getRandomValues(new Uint8Array(16))
+  const iv = crypto.getRandomValues(new Uint8Array(12))
+  const key = await deriveKey(passphrase, salt, PBKDF2_ITERATIONS)
+  const data = await crypto.subtle.encrypt(
+    { name: 'AES-GCM', iv },
+    key,
+    new TextEncoder().encode(JSON.stringify(secrets)),
+  )
+  return {
+    version: SECRETS_FILE_VERSION,
+    kdf: { name: 'PBKDF2', hash: 'SHA-256', iterations: PBKDF2_ITERATIONS, salt: encodeBase64(salt) },
+    cipher: 'AES-GCM',
+    iv: encodeBase64(iv),
+    data: encodeBase64(new Uint8Array(data)),
+  }
+}
+
+/**
+ * Decrypt secrets encrypted with encryptSecrets
+ *
+ * @throws If the passphrase is wrong or the file was modified
+ */
+export async function decryptSecrets(
+  file: ISecretsFile,
+  passphrase: string,
+): Promise<Record<string, string>> {
+  if (file.version !== SECRETS_FILE_VERSION) {
+    throw new Error(`Unsupported secrets file version: ${file.version}`)
+  }
+  const key = await deriveKey(passphrase, decodeBase64(file.kdf.salt), file.kdf.iterations)
+  let data: ArrayBuffer
+  try {
+    data = await crypto.subtle.decrypt(
+      { name: 'AES-GCM', iv: decodeBase64(file.iv) },
+      key,
+      decodeBase64(file.data),
+    )
+  } catch (_error) {
+    throw new Error('Unable to decrypt secrets, the passphrase is incorrect or the file is corrupt')
+  }
+  return JSON.parse(new TextDecoder().decode(data)) as Record<string, string>
+}
+
+/**
+ * Read and decrypt a secrets file
+ *
+ * A missing secrets file is not an error, an empty object is returned.
+ */
+export async function readSecretsFile(
+  filePath: string,
+  passphrase: string,
+): Promise<TryCatchResult<Record<string, string>>> {
+  if (!(await fileExists(filePath)).data) {
+    return success<Record<string, string>>({})
+  }
+  const result = await tryCatch(
+    Deno.readTextFile(filePath).then((contents) =>
+      decryptSecrets(JSON.parse(contents) as ISecretsFile, passphrase)
+    ),
+  )
+  if (!result.success) {
+    return failure<Record<string, string>>(`Unable to read secrets file: ${filePath}`, result)
+  }
+  return result
+}
+
+/**
+ * Encrypt and save secrets to a file, only readable by the current user
+ */
+export async function writeSecretsFile(
+  filePath: string,
+  secrets: Record<string, string>,
+  passphrase: string,
+): Promise<TryCatchResult<boolean>> {
+  const dirResult = await ensureDir(path.dirname(filePath))
+  if (!dirResult.success) {
+    return failure<boolean>(`Unable to create secrets dir: ${path.dirname(filePath)}`, dirResult)
+  }
+  const encrypted = await encryptSecrets(secrets, passphrase)
+  const result = await tryCatch(
+    Deno.writeTextFile(filePath, JSON.stringify(encrypted, null, 2) + '\n', { mode: 0o600 })
+      .then(() => true),
+  )
+  if (!result.success) {
+    return failure<boolean>(`Unable to save secrets file: ${filePath}`, result, false)
+  }
+  return result
+}
+
+/**
+ * Get the path to the key file for a project
+ *
+ * Key files are stored outside of the project dir so they aren't committed or backed up
+ * with the encrypted secrets file.
+ */
+export function getSecretsKeyFile(globalDir: string, projectName: string): string {
+  return Deno.env.get(SECRETS_KEY_FILE_ENV) || path.join(globalDir, 'keys', `${projectName}.key`)
+}
+
+/**
+ * Create a new random key file for a project
+ */
+export async function createSecretsKeyFile(keyFile: string): Promise<TryCatchResult<string>> {
+  const passphrase = encodeBase64(crypto.getRandomValues(new Uint8Array(32)))
+  const dirResult = await ensureDir(path.dirname(keyFile))
+  if (!dirResult.success) {
+    return failure<string>(`Unable to create key file dir: ${path.dirname(keyFile)}`, dirResult)
+  }
+  const result = await tryCatch(
+    Deno.writeTextFile(keyFile, passphrase + '\n', { mode: 0o600 }).then(() => passphrase),
+  )
+  if (!result.success) {
+    return failure<string>(`Unable to save key file: ${keyFile}`, result)
+  }
+  return result
+}
+
+/**
+ * Get the passphrase for the secrets file
+ *
+ * Checks the LLEMONSTACK_SECRETS_PASSPHRASE env var, then the key file.
+ * Prompts for the passphrase if prompt is true and neither is set.
+ */
+export async function getSecretsPassphrase(
+  keyFile: string,
+  { prompt = false }: { prompt?: boolean } = {},
+): Promise<TryCatchResult<string>> {
+  const envPassphrase = Deno.env.get(SECRETS_PASSPHRASE_ENV)
+  if (envPassphrase) {
+    return success<string>(envPassphrase)
+  }
+  if ((await fileExists(keyFile)).data) {
+    const result = await tryCatch(Deno.readTextFile(keyFile).then((key) => key.trim()))
+    if (!result.success || !result.data) {
+      return failure<string>(`Unable to read secrets key file: ${keyFile}`, result)
+    }
+    return result
+  }
+  if (prompt) {
+    return await tryCatch(Secret.prompt({ message: 'Secrets passphrase', minLength: 1 }))
+  }
+  return failure<string>(
+    `Secrets passphrase not found, set ${SECRETS_PASSPHRASE_ENV} or create ${keyFile}`,
+    { data: null, error: new Error('Secrets passphrase not found'), success: false },
+  )
+}
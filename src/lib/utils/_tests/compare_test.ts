@@ -1,5 +1,5 @@
-import { assertEquals } from 'jsr:@std/assert'
-import { isTruthy } from '../compare.ts'
+import { assert, assertEquals } from 'jsr:@std/assert'
+import { isTruthy, isVersionAtLeast } from '../compare.ts'
 
 // Test null and undefined
 Deno.test('isTruthy - returns false for null and undefined', () => {
@@ -36,3 +36,11 @@ Deno.test('isTruthy - returns false for other types', () => {
   assertEquals(isTruthy([]), false)
   assertEquals(isTruthy({}), false)
 })
+
+Deno.test('isVersionAtLeast compares semver versions', () => {
+  assert(isVersionAtLeast('1.2.0', '1.2.0'))
+  assert(isVersionAtLeast('podman-compose version 1.10.0', '1.2.0'))
+  assert(isVersionAtLeast('5.3', '5.2.9'))
+  assert(!isVersionAtLeast('1.0.5', '1.0.6'))
+  assert(!isVersionAtLeast('', '1.0.0'))
+})
@@ -1,3 +1,6 @@
+import { assertEquals } from 'jsr:@std/assert'
+import { getUnsetEnvVars } from '../envvars.ts'
+
 // TODO: convert to tests
 // // Example usage
 // const env: Record<string, string | undefined> = {
@@ -29,3 +32,21 @@
 // } catch (error) {
 //   console.error(`Error caught: ${error.message}`)
 // }
+
+Deno.test('getUnsetEnvVars finds required variables that are not set', () => {
+  const env = { SET: 'x', EMPTY: '' }
+  assertEquals(
+    getUnsetEnvVars('${SET} ${MISSING} $ALSO ${EMPTY}', env),
+    ['MISSING', 'ALSO', 'EMPTY'],
+  )
+  assertEquals(getUnsetEnvVars('${A}:${SET}@${B:-host}/db?schema=${A}', env), ['A'])
+  assertEquals(getUnsetEnvVars('${MISSING:?required} ${EMPTY-default}', env), ['MISSING'])
+})
+
+Deno.test('getUnsetEnvVars skips variables with defaults and escapes', () => {
+  const env = { SET: 'x' }
+  assertEquals(getUnsetEnvVars('${MISSING:-default} ${MISSING:+alt}', env), [])
+  assertEquals(getUnsetEnvVars('$$ESCAPED $${ESCAPED}', env), [])
+  assertEquals(getUnsetEnvVars('${MISSING:-${INNER}} ${SET:-${NOT_USED}}', env), ['INNER'])
+  assertEquals(getUnsetEnvVars('${SET:+${ALT}} ${MISSING:+${NOT_USED}}', env), ['ALT'])
+})
@@ -21,3 +21,19 @@ export function isTruthy(value: unknown): boolean {
   }
   return false
 }
+
+/**
+ * Compare two semver versions
+ * @returns {boolean} True if version is greater than or equal to minVersion
+ */
+export function isVersionAtLeast(version: string, minVersion: string): boolean {
+  const parse = (v: string) => (v.match(/\d+(\.\d+)*/)?.[0] || '0').split('.').map(Number)
+  const a = parse(version)
+  const b = parse(minVersion)
+  for (let i = 0; i < Math.max(a.length, b.length); i++) {
+    if ((a[i] || 0) !== (b[i] || 0)) {
+      return (a[i] || 0) > (b[i] || 0)
+    }
+  }
+  return true
+}
@@ -99,3 +99,60 @@ export function expandEnvVars(
 
   return result
 }
+
+/**
+ * Get the variables referenced in a string that are required but not set
+ *
+ * Variables with a default or alternate value, e.g. ${VAR:-default}, are not required.
+ * Variables in a default value are only required if the default is used.
+ * $$ escapes are skipped, same as Docker Compose.
+ *
+ * @param input - The string to check, e.g. a compose file value
+ * @param envVars - Object containing environment variables
+ * @returns The names of the unset variables, in order of first reference
+ */
+export function getUnsetEnvVars(
+  input: string,
+  envVars: Record<string, string | undefined>,
+): string[] {
+  const unset = new Set<string>()
+  const isSet = (name: string, colon: boolean) =>
+    colon ? !!envVars[name] : envVars[name] !== undefined
+
+  const check = (str: string) => {
+    for (let i = 0; i < str.length; i++) {
+      if (str[i] !== '$') continue
+      if (str[i + 1] === '$') {
+        i++
+        continue
+      }
+      if (str[i + 1] !== '{') {
+        const name = str.slice(i + 1).match(/^[a-zA-Z_][a-zA-Z0-9_]*/)?.[0]
+        if (name && !isSet(name, true)) unset.add(name)
+        i += name?.length ?? 0
+        continue
+      }
+
+      // Find the closing brace of ${...}, values can contain nested variables
+      let end = i + 2
+      for (let depth = 1; end < str.length; end++) {
+        if (str[end] === '{') depth++
+        if (str[end] === '}' && --depth === 0) break
+      }
+      const match = str.slice(i + 2, end).match(/^([a-zA-Z_][a-zA-Z0-9_]*)(:?)([?+=-]?)([\s\S]*)$/)
+      i = end
+      if (!match) continue
+      const [, name, colon, operator, operand] = match
+      const set = isSet(name, !!colon || !operator)
+      if (!operator || operator === '?') {
+        if (!set) unset.add(name)
+      } else if ((operator === '+') === set) {
+        // The alternate value is used when set, the default value when unset
+        check(operand)
+      }
+    }
+  }
+
+  check(input)
+  return [...unset]
+}
@@ -1,4 +1,4 @@
-export { isTruthy } from './compare.ts'
+export { isTruthy, isVersionAtLeast } from './compare.ts'
 export { expandEnvVars } from './envvars.ts'
 export { ObservableStruct, observe } from './observable.ts'
 export { searchObjectPaths } from './search-object.ts'
@@ -15,6 +15,7 @@ import {
   LogRecord,
   LogtapeLogger,
 } from './logger.ts'
+import { print } from './ui/output.ts'
 
 export type LogMethod = 'debug' | 'info' | 'warn' | 'error' | 'fatal'
 type WithLevelType = LogLevel | 'silent' | Filter
@@ -525,7 +526,7 @@ export class RelayerBase {
       const callStack = getCallStackInfo({
         callStack: properties._meta?.callStack,
       }).callStack
-      console.info(
+      print(
         '  Call stack:',
         callStack.reverse().map((c) =>
           colors.yellow(`${c.module ? `${c.module}.` : ''}${c.function}`)
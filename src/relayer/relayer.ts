@@ -1,7 +1,7 @@
 import { RelayerBase } from './base.ts'
 import { Logger, LogLevel } from './logger.ts'
-import { InterfaceRelayer } from './ui/interface.ts'
-export type { LogLevel }
+import { InterfaceRelayer, OutputFormat } from './ui/interface.ts'
+export type { LogLevel, OutputFormat }
 
 /**
  * Relayer handles relaying log and user interaction messages.
@@ -17,10 +17,11 @@ export class Relayer extends RelayerBase {
    * This needs to be called once before getInstance()
    */
   public static async initialize(
-    { logLevel = this.logLevel, reset = false, verbose = false }: {
+    { logLevel = this.logLevel, reset = false, verbose = false, output = 'text' }: {
       logLevel?: LogLevel
       reset?: boolean
       verbose?: boolean
+      output?: OutputFormat
     } = {},
   ): Promise<boolean> {
     if (this.initialized && !reset) {
@@ -31,6 +32,7 @@ export class Relayer extends RelayerBase {
 
     this.logLevel = logLevel
     this.verbose = verbose
+    InterfaceRelayer.setOutputFormat(output)
 
     // Create and configure Logtape logger
     await Logger.initLogger(
@@ -1,18 +1,22 @@
 import { CommandError } from '@/lib/command.ts'
 import { colors } from '@cliffy/ansi/colors'
+import * as yaml from 'jsr:@std/yaml'
 import { AppLogRecord, RelayerBase } from '../base.ts'
-import { LogMessageType, Sink } from '../logger.ts'
+import { LogLevel, LogMessageType, Sink } from '../logger.ts'
+import { print, setMessageOutput } from './output.ts'
 import { RowType, showTable, Table, TableOptions } from './tables.ts'
 
 interface UserLogRecord extends AppLogRecord {
   properties: Record<string, unknown> & {
     _meta?: AppLogRecord['properties']['_meta'] & {
-      type: 'user_action' | 'action'
+      type: 'user_action' | 'action' | 'container_log' | 'result'
       emoji?: string
     }
   }
 }
 
+export type OutputFormat = 'text' | 'json' | 'yaml'
+
 /**
  * Relayer for user interaction messages
  *
@@ -20,10 +24,63 @@ interface UserLogRecord extends AppLogRecord {
  * show.debug -> logger.debug -> this.filter -> this.log -> console
  */
 export class InterfaceRelayer extends RelayerBase {
+  // Format for command results, see result()
+  public static outputFormat: OutputFormat = 'text'
+  public static resultShown: boolean = false
+  // Error messages shown while running the command, included in the fallback result
+  public static errors: string[] = []
+
   public static override getSink(): Sink {
     return this.log.bind(this) as Sink
   }
 
+  /**
+   * Always show command results, other messages are filtered by log level
+   *
+   * @param record The log record
+   */
+  public static override filter(record: UserLogRecord): boolean {
+    if (record.properties._meta?.type === 'result') {
+      return true
+    }
+    return super.filter(record)
+  }
+
+  /**
+   * Set the output format for command results
+   *
+   * For json and yaml, user messages are routed to stderr so stdout only contains
+   * the result document and can be piped to other tools.
+   *
+   * @param format The output format
+   */
+  public static setOutputFormat(format: OutputFormat): void {
+    this.outputFormat = format
+    setMessageOutput(format === 'text' ? 'stdout' : 'stderr')
+  }
+
+  public static isStructuredOutput(): boolean {
+    return this.outputFormat !== 'text'
+  }
+
+  /**
+   * Write a result document to stdout in the current output format
+   *
+   * @param data The result data
+   */
+  public static writeResult(data: unknown): void {
+    if (!this.isStructuredOutput()) {
+      return
+    }
+    // Normalize dates and drop undefined values so json and yaml output match
+    const normalized = JSON.parse(JSON.stringify(data ?? null))
+    const doc = this.outputFormat === 'yaml'
+      ? yaml.stringify(normalized)
+      : `${JSON.stringify(normalized, null, 2)}\n`
+    Deno.stdout.writeSync(new TextEncoder().encode(doc))
+    this.resultShown = true
+  }
+
   /**
    * Console log sink for user interaction messages
    *
@@ -36,15 +93,30 @@ export class InterfaceRelayer extends RelayerBase {
     const level = record.level
     const emoji: string = meta?.emoji ? meta.emoji : ''
 
-    if (meta?.type === 'user_action') {
-      console.log(`${colors.magenta(message)}`)
+    if (meta?.type === 'result') {
+      this.writeResult(data.result)
+    } else if (meta?.type === 'container_log') {
+      // Container logs are formatted by the caller and shown as is
+      // In json and yaml mode, the log lines are the result of the command
+      if (this.isStructuredOutput()) {
+        Deno.stdout.writeSync(new TextEncoder().encode(`${message}\n`))
+        this.resultShown = true
+      } else {
+        print(message)
+      }
+    } else if (meta?.type === 'user_action') {
+      print(`${colors.magenta(message)}`)
     } else if (meta?.type === 'action') {
-      console.log(`${colors.green(message)}`)
+      print(`${colors.green(message)}`)
     } else if (level === 'fatal') {
       console.error(`‼️ ${colors.red('ERROR: unable to continue, exiting...')}`)
       showError(message, meta?.error)
+      if (!this.resultShown) {
+        this.writeResult({ success: false, error: message })
+      }
       Deno.exit(1)
     } else if (level === 'error') {
+      this.errors.push(message)
       showError(message, meta?.error)
     } else if (level === 'warning') {
       console.warn(`${emoji ? `${emoji} ` : '❗ '}${colors.yellow.bold(message)}`)
@@ -88,7 +160,7 @@ export class InterfaceRelayer extends RelayerBase {
     if (header.length < len) {
       header += '-' // handle odd number of characters
     }
-    console.log(`\n${colors.cyan.bold(header)}`)
+    print(`\n${colors.cyan.bold(header)}`)
   }
 
   public credentials(credentials: Record<string, string | null | undefined>): void {
@@ -98,7 +170,7 @@ export class InterfaceRelayer extends RelayerBase {
   }
 
   public serviceHost(service: string, url: string): void {
-    console.log(`${service}: ${colors.yellow(url)}`)
+    print(`${service}: ${colors.yellow(url)}`)
   }
 
   //
@@ -113,6 +185,18 @@ export class InterfaceRelayer extends RelayerBase {
     this.logger.info(message as string, { ...this._context, ...data, _meta: { type: 'action' } })
   }
 
+  /**
+   * Show the structured result of a command
+   *
+   * Results are only output when the output format is json or yaml.
+   * Each command should show a single result, typically at the end of the command.
+   *
+   * @param data - The result document
+   */
+  public result(data: Record<string, unknown> | unknown[]): void {
+    this.logger.info('result', { ...this._context, result: data, _meta: { type: 'result' } })
+  }
+
   public userAction(message: LogMessageType, data?: Record<string, unknown>): void {
     this.logger.info(message as string, {
       ...this._context,
@@ -121,6 +205,29 @@ export class InterfaceRelayer extends RelayerBase {
     })
   }
 
+  /**
+   * Show a line from a container log
+   *
+   * The line is logged at the given level so log level filtering and sinks apply,
+   * but the message is output without any additional formatting.
+   *
+   * @param level - The level detected for the log line
+   * @param message - The formatted log line
+   * @param data - Additional data, e.g. service and container names
+   */
+  public containerLog(
+    level: Exclude<LogLevel, 'fatal'>,
+    message: string,
+    data?: Record<string, unknown>,
+  ): void {
+    const method = level === 'warning' ? 'warn' : level
+    this.logger[method](message, {
+      ...this._context,
+      ...data,
+      _meta: { type: 'container_log' },
+    })
+  }
+
   //
   // Override base logger methods
   // debug, info, warn, and error are inherited from RelayerBase
@@ -148,7 +255,7 @@ export class InterfaceRelayer extends RelayerBase {
 //
 
 function showInfo(message: string): void {
-  console.log(`${colors.gray(message)}`)
+  print(`${colors.gray(message)}`)
 }
 
 function showError(msgOrError: string | unknown, err?: unknown): void {
@@ -0,0 +1,21 @@
+/**
+ * Console output for user messages
+ *
+ * When the output format is json or yaml, stdout only contains the result document
+ * so messages are written to stderr instead. See InterfaceRelayer.setOutputFormat.
+ */
+
+let useStderr = false
+
+export function setMessageOutput(stream: 'stdout' | 'stderr'): void {
+  useStderr = stream === 'stderr'
+}
+
+/**
+ * Write a user message to stdout, or stderr for json and yaml output
+ *
+ * Use instead of console.log for user messages so output from other code isn't affected.
+ */
+export function print(...args: unknown[]): void {
+  useStderr ? console.error(...args) : console.log(...args)
+}
@@ -10,6 +10,7 @@
 import { Config } from '@/core/config/config.ts'
 import { CommandError } from '@/lib/command.ts'
 import { Relayer } from '@/relayer/relayer.ts'
+import { print } from '@/relayer/ui/output.ts'
 import type { LogMessage } from '@/types'
 import { colors } from '@cliffy/ansi/colors'
 import { Cell, CellType, Column, Row, RowType, Table } from '@cliffy/table'
@@ -43,12 +44,12 @@ export async function showDebug(message: string, ...args: unknown[]): Promise<vo
 
 // Shows magenta text, prompting user to take an action later on
 export function showUserAction(message: string): void {
-  console.log(`${colors.magenta(message)}`)
+  print(`${colors.magenta(message)}`)
 }
 
 // Shows service name in default and url in yellow text
 export function showService(service: string, url: string): void {
-  console.log(`${service}: ${colors.yellow(url)}`)
+  print(`${service}: ${colors.yellow(url)}`)
 }
 
 // Shows username and password in gray text
@@ -60,7 +61,7 @@ export function showCredentials(credentials: Record<string, string | null | unde
 
 // Shows green text
 export function showAction(message: string): void {
-  console.log(`${colors.green(message)}`)
+  print(`${colors.green(message)}`)
 }
 
 // Shows cyan text in uppercase
@@ -70,7 +71,7 @@ export function showHeader(message: string, len = 50): void {
   if (header.length < len) {
     header += '-' // handle odd number of characters
   }
-  console.log(`\n${colors.cyan.bold(header)}`)
+  print(`\n${colors.cyan.bold(header)}`)
 }
 
 export function showError(msgOrError: string | unknown, err?: unknown): void {
@@ -112,7 +113,7 @@ export function showWarning(message: string, emoji?: string): void {
 
 // Shows gray text
 export function showInfo(message: string): void {
-  console.log(`${colors.gray(message)}`)
+  print(`${colors.gray(message)}`)
 }
 
 // Use Deno.env a the basic global config manager and Config for the complicated stack config
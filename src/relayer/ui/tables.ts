@@ -1,5 +1,6 @@
 import { colors } from '@cliffy/ansi/colors'
 import { Border, CellType, RowType, Table } from '@cliffy/table'
+import { print } from './output.ts'
 
 export { type RowType, Table }
 
@@ -25,7 +26,7 @@ export interface TableOptions {
  * ```typescript
  * const table = showTable(['H1', 'H2'], [['Row1A', 'Row1B']], { render: false })
  * table.column(0, new Column().align('right'))
- * table.render()
+ * print(table.toString())
  * ```
  *
  * @param header - The header row of the table
@@ -103,11 +104,7 @@ export function showTable(
   // },
 
   if (render) {
-    if (tableColor) {
-      console.log(tableColor(table.toString()))
-    } else {
-      table.render()
-    }
+    print(tableColor ? tableColor(table.toString()) : table.toString())
   }
 
   return table
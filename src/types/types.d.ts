@@ -29,6 +29,14 @@ export interface LLemonStackConfig {
     volumes: string
     services?: string | string[]
   }
+  ports?: {
+    base: number // Added to all host ports, allows multiple projects to run at the same time
+  }
+  secrets?: {
+    store: 'env' | 'encrypted' // Where generated secrets are saved, .env by default
+  }
+  runtime?: 'auto' | 'docker' | 'podman' | 'nerdctl' // Container runtime, auto detected by default
+  offline?: boolean // Set by `llmn bundle load`, repos are not cloned and images are not pulled
   services: {
     [key: string]: IServiceConfigState
   }
@@ -63,6 +71,7 @@ export interface RunCommandOptions {
   autoLoadEnv?: boolean
   debug?: boolean
   relayer?: RelayerInstance
+  interactive?: boolean // Pass stdin, stdout & stderr through to the terminal
 }
 
 export type OllamaProfile =
@@ -93,6 +102,7 @@ export interface IServiceImage {
  * From service's llemonstack.yaml
  */
 export interface ServiceYaml {
+  version?: string // Config file version
   id?: string // The ID of the service
   service: string // The name of the service
   name: string // Friendly name of the service to show to users
@@ -108,7 +118,7 @@ export interface ServiceYaml {
     from_repo?: true
   }[]
   provides?: Record<string, string> // The services that the service provides
-  depends_on?: Record<string, { condition: string }> // The services that the service depends on
+  depends_on?: Record<string, { condition: ServiceDependsOnCondition }> // The services that the service depends on
   app_version_cmd?: string[] // The command to run to get the version of the service
   exposes?: ExposeHostConfig
   init?: {
@@ -121,10 +131,32 @@ export interface ServiceYaml {
       method: string
       length?: number
       prefix?: string
+      rotatable?: boolean // False if the value is stored in the service data, e.g. a db password
     }>
   }
 }
 
+/**
+ * Settings for a secret generated during service init
+ */
+export interface IServiceSecret {
+  method: string // generateSecretKey | generateRandomBase64 | generateUUID
+  length?: number
+  prefix?: string
+  rotatable?: boolean // Only rotated when named explicitly if false
+  postgresUserKey?: string // Env var of the postgres user for postgres_schema passwords
+}
+
+/**
+ * Conditions a dependency must meet before a dependent service is started
+ *
+ * Mirrors the Docker Compose depends_on conditions.
+ */
+export type ServiceDependsOnCondition =
+  | 'service_started' // Container is running
+  | 'service_healthy' // Container is running and the healthcheck is passing
+  | 'service_completed_successfully' // Container ran to completion with exit code 0
+
 export interface IServiceState {
   enabled: boolean
   started: boolean | null
@@ -132,6 +164,9 @@ export interface IServiceState {
   ready: boolean | null
   last_checked: Date | null
   state: string | null // Value of state string from Docker Compose ps
+  exit_code: number | null // Exit code of the primary container from Docker Compose ps
+  status: string | null // Status string from Docker Compose ps, e.g. 'Up 2 hours (healthy)'
+  ports: string[] // Published host ports for all of the service's containers, e.g. '5678->5678/tcp'
 }
 
 export type ServiceStatusType =
@@ -189,6 +224,24 @@ export interface IServiceStartOptions extends IServiceActionOptions {
   build?: boolean
 }
 
+// What a command would do to a service, see Service.getPlan and scripts/plan.ts
+export type ServicePlanAction = 'start' | 'stop' | 'update' | 'enable' | 'disable' | 'none'
+
+export interface IServicePlan {
+  service: string
+  name: string
+  action: ServicePlanAction
+  enabled: boolean
+  auto: boolean // Enabled state is resolved from the enabled services that depend on it
+  composeFile: string
+  overrideFile: string // Generated compose override, see prepareComposeOverride
+  profiles: string[]
+  env: string[] // Env vars generated by `llmn init` that are not set yet
+  secrets: string[] // Env vars in env that are saved to the encrypted secrets file
+  repo: { url: string; dir: string; action: 'clone' | 'none' } | null
+  volumes: string[] // Volume dirs that don't exist yet
+}
+
 // Define the type for the Docker Compose configuration
 export interface ComposeYaml {
   include?: string | string[] | { path: string }[]
@@ -205,10 +258,38 @@ export interface ComposeYaml {
         dockerfile_inline?: string
       }
       container_name?: string
+      profiles?: string[]
+      ports?: Array<ComposePort | string | number>
     }
   }
 }
 
+// Long syntax of a compose service port
+export interface ComposePort {
+  target: number
+  published?: string
+  host_ip?: string
+  protocol?: string
+}
+
+// Published host port of a service, see Config.getServicesHostPorts
+export interface IServiceHostPort {
+  service: string // LLemonStack service, e.g. n8n
+  name: string // Human readable service name
+  composeService: string // Compose service that publishes the port
+  port: number // Host port in the compose file
+  hostPort: number // Host port for the project after the base port offset or remap
+  protocol: string // tcp or udp
+}
+
+// Published host port of a compose service, see getPortsFromComposeYaml
+export interface IComposeHostPort {
+  service: string // Compose service name
+  published: number // Host port in the compose file, before the base port offset
+  target: number
+  protocol: string
+}
+
 //
 // TryCatchResult
 //